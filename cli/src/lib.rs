@@ -4,10 +4,12 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use common::{get_openmwcfg, get_plugins_in_folder};
+use common::{get_openmwcfg, get_plugins_in_folder, tes3::RecordIndex, vfs::VfsIndex};
 use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
 
+pub mod manager;
+
 #[derive(Default, Serialize, Deserialize, Debug)]
 pub struct Manifest {
     pub files: Vec<String>,
@@ -388,3 +390,83 @@ pub fn import(data_files_opt: Option<PathBuf>, cfg_opt: Option<PathBuf>, clean:
 
     false
 }
+
+/// Lists the files each data directory in the openmw.cfg overrides or loses to other data directories
+pub fn conflicts(cfg_path_option: Option<PathBuf>, verbose: bool) -> Option<usize> {
+    // checks
+    let in_path = check_cfg_path(cfg_path_option)?;
+
+    // parse cfg for data dirs
+    let info = common::parse_cfg(in_path)?;
+    info!("Found {} data dirs", info.data.len());
+
+    // index the vfs
+    info!("Indexing data dirs ...");
+    let index = VfsIndex::new(&info.data);
+    let mut count = 0;
+    for dir_conflicts in index.get_conflicts() {
+        if dir_conflicts.is_empty() {
+            continue;
+        }
+        count += 1;
+        info!(
+            "{} (+{} / -{})",
+            dir_conflicts.data_dir.display(),
+            dir_conflicts.overrides.len(),
+            dir_conflicts.overridden.len()
+        );
+        if verbose {
+            for file in &dir_conflicts.overrides {
+                info!("\t+ {}", file);
+            }
+            for file in &dir_conflicts.overridden {
+                info!("\t- {}", file);
+            }
+        }
+    }
+
+    info!("Found {} conflicting data dirs", count);
+    Some(count)
+}
+
+/// Lists the records that more than one plugin in the openmw.cfg changes, and which plugin wins them
+pub fn record_conflicts(cfg_path_option: Option<PathBuf>, verbose: bool) -> Option<usize> {
+    // checks
+    let in_path = check_cfg_path(cfg_path_option)?;
+
+    // parse cfg for data dirs and content files
+    let info = common::parse_cfg(in_path)?;
+    info!("Found {} data dirs", info.data.len());
+    info!("Found {} plugins", info.plugins.len());
+
+    // index the records in load order
+    info!("Reading plugins ...");
+    let plugins = common::resolve_plugins(&info.data, &info.plugins);
+    let index = RecordIndex::new(&plugins);
+    for plugin_conflicts in index.get_conflicts() {
+        if plugin_conflicts.is_empty() {
+            continue;
+        }
+        info!(
+            "{} (+{} / -{})",
+            plugin_conflicts.plugin,
+            plugin_conflicts.overrides.len(),
+            plugin_conflicts.overridden.len()
+        );
+    }
+
+    let conflicts = index.get_record_conflicts();
+    if verbose {
+        for conflict in &conflicts {
+            info!(
+                "{}: {} wins ({})",
+                conflict.record,
+                conflict.get_winner(),
+                conflict.plugins.join(", ")
+            );
+        }
+    }
+
+    info!("Found {} conflicting records", conflicts.len());
+    Some(conflicts.len())
+}
@@ -1,5 +1,6 @@
-use clap::{Parser, Subcommand};
-use omw_util::{cleanup, export, import};
+use clap::{Args, Parser, Subcommand};
+use omw_util::manager::Manager;
+use omw_util::{cleanup, conflicts, export, import, record_conflicts};
 use std::path::PathBuf;
 use std::process::ExitCode;
 
@@ -54,6 +55,204 @@ enum Commands {
         #[arg(short, long)]
         cleanup: bool,
     },
+    /// Lists the files each data directory overrides (+) or loses (-) to other data directories
+    Conflicts {
+        // options
+        /// The path to the openmw.cfg, default is openMWs's default location
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+    },
+    /// Lists the records that more than one plugin changes, and which plugin wins them
+    RecordConflicts {
+        // options
+        /// The path to the openmw.cfg, default is openMWs's default location
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+    },
+    /// Installs, enables and disables the mods of an omwmm profile
+    Mod {
+        #[command(subcommand)]
+        command: ModCommands,
+
+        #[command(flatten)]
+        options: ManagerOptions,
+    },
+    /// Creates, switches and exports omwmm profiles
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommands,
+
+        #[command(flatten)]
+        options: ManagerOptions,
+    },
+    /// Writes omwmm profiles to openmw.cfg files
+    Cfg {
+        #[command(subcommand)]
+        command: CfgCommands,
+
+        #[command(flatten)]
+        options: ManagerOptions,
+    },
+    /// Lists the entries that writing a profile adds (+), removes (-) or moves (~) in its openmw.cfg
+    Diff {
+        // options
+        /// Compare with this openmw.cfg instead of the one of the profile
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+
+        /// Compare the profile with another profile instead
+        #[arg(long)]
+        with: Option<String>,
+
+        #[command(flatten)]
+        options: ManagerOptions,
+    },
+}
+
+/// Where omwmm keeps its settings and mods, omwmm should be closed while they are changed
+#[derive(Args)]
+struct ManagerOptions {
+    /// The omwmm folder with the settings and profiles, default is omwmm's config folder
+    #[arg(long, global = true)]
+    omwmm_dir: Option<PathBuf>,
+
+    /// The folder mods are extracted to, default is the mods library of omwmm
+    #[arg(long, global = true)]
+    mods_library: Option<PathBuf>,
+
+    /// The folder mod archives are stored in, default is the downloads library of omwmm
+    #[arg(long, global = true)]
+    downloads_library: Option<PathBuf>,
+
+    /// The profile to change, default is the current profile
+    #[arg(short, long, global = true)]
+    profile: Option<String>,
+}
+
+impl ManagerOptions {
+    fn get_manager(&self) -> Option<Manager> {
+        let mut manager = Manager::new(self.omwmm_dir.to_owned())?;
+        if self.mods_library.is_some() {
+            manager.settings.mods_library = self.mods_library.to_owned();
+        }
+        if self.downloads_library.is_some() {
+            manager.settings.downloads_library = self.downloads_library.to_owned();
+        }
+        Some(manager)
+    }
+}
+
+#[derive(Subcommand)]
+enum ModCommands {
+    /// Extracts an archive into the mods library and adds the mod to the profile
+    Install {
+        // arguments
+        /// The zip or 7z archive of the mod
+        archive: PathBuf,
+
+        // options
+        /// Enable the mod and its plugins
+        #[arg(short, long)]
+        enable: bool,
+
+        /// Replace the mod if it is already installed
+        #[arg(long)]
+        reinstall: bool,
+    },
+    /// Enables a mod by its folder name or path
+    Enable {
+        // arguments
+        name: String,
+
+        // options
+        /// Enable the plugins of the mod too
+        #[arg(long)]
+        plugins: bool,
+    },
+    /// Disables a mod by its folder name or path
+    Disable {
+        // arguments
+        name: String,
+    },
+    /// Lists the mods of the profile in load order
+    List,
+}
+
+#[derive(Subcommand)]
+enum ProfileCommands {
+    /// Creates a new profile
+    Create {
+        // arguments
+        name: String,
+
+        // options
+        /// Copy the mods, plugins and configs of another profile
+        #[arg(long)]
+        from: Option<String>,
+    },
+    /// Makes a profile the current profile of omwmm
+    Switch {
+        // arguments
+        name: String,
+    },
+    /// Copies the mod and plugin lists and the configs of the profile to a directory
+    Export {
+        // arguments
+        /// The directory to export to, default is current working directory
+        dir: Option<PathBuf>,
+    },
+    /// Writes the profile with the archives of its mods to a modlist file that can be shared
+    ExportModlist {
+        // arguments
+        /// The modlist file to write
+        file: PathBuf,
+    },
+    /// Creates a profile from a modlist file and lists the archives missing in the downloads library
+    ImportModlist {
+        // arguments
+        /// The modlist file to read
+        file: PathBuf,
+
+        // options
+        /// The name of the new profile, default is the name in the modlist
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Installs the mods of a modlist file from the downloads library and creates a profile with them.
+    /// Fails if an archive is missing or differs from the one the modlist was made with
+    InstallModlist {
+        // arguments
+        /// The modlist file to read
+        file: PathBuf,
+
+        // options
+        /// The name of the new profile, default is the name in the modlist
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Replace mods that are already in the mods library
+        #[arg(long)]
+        replace: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum CfgCommands {
+    /// Writes the enabled mods and plugins of the profile to its openmw.cfg
+    Apply {
+        // options
+        /// Write to this openmw.cfg instead of the one of the profile
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+    },
+}
+
+fn to_exit_code(success: bool) -> ExitCode {
+    if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
 }
 
 fn main() -> ExitCode {
@@ -84,6 +283,98 @@ fn main() -> ExitCode {
             Some(_) => ExitCode::SUCCESS,
             None => ExitCode::FAILURE,
         },
+        Some(Commands::Conflicts { config }) => match conflicts(config.to_owned(), cli.verbose) {
+            Some(_) => ExitCode::SUCCESS,
+            None => ExitCode::FAILURE,
+        },
+        Some(Commands::RecordConflicts { config }) => {
+            match record_conflicts(config.to_owned(), cli.verbose) {
+                Some(_) => ExitCode::SUCCESS,
+                None => ExitCode::FAILURE,
+            }
+        }
+        Some(Commands::Mod { command, options }) => {
+            let Some(manager) = options.get_manager() else {
+                return ExitCode::FAILURE;
+            };
+            let profile = options.profile.as_deref();
+            match command {
+                ModCommands::Install {
+                    archive,
+                    enable,
+                    reinstall,
+                } => to_exit_code(manager.install_mod(profile, archive, *enable, *reinstall)),
+                ModCommands::Enable { name, plugins } => {
+                    to_exit_code(manager.set_mod_enabled(profile, name, true, *plugins))
+                }
+                ModCommands::Disable { name } => {
+                    to_exit_code(manager.set_mod_enabled(profile, name, false, false))
+                }
+                ModCommands::List => {
+                    to_exit_code(manager.list_mods(profile, cli.verbose).is_some())
+                }
+            }
+        }
+        Some(Commands::Profile { command, options }) => {
+            let Some(mut manager) = options.get_manager() else {
+                return ExitCode::FAILURE;
+            };
+            match command {
+                ProfileCommands::Create { name, from } => {
+                    to_exit_code(manager.create_profile(name, from.as_deref()))
+                }
+                ProfileCommands::Switch { name } => to_exit_code(manager.switch_profile(name)),
+                ProfileCommands::Export { dir } => {
+                    let dir = dir
+                        .to_owned()
+                        .unwrap_or_else(|| std::env::current_dir().unwrap());
+                    to_exit_code(manager.export_profile(options.profile.as_deref(), &dir))
+                }
+                ProfileCommands::ExportModlist { file } => {
+                    to_exit_code(manager.export_modlist(options.profile.as_deref(), file))
+                }
+                ProfileCommands::ImportModlist { file, name } => {
+                    to_exit_code(manager.import_modlist(file, name.as_deref()).is_some())
+                }
+                ProfileCommands::InstallModlist {
+                    file,
+                    name,
+                    replace,
+                } => match manager.install_modlist(file, name.as_deref(), *replace) {
+                    Some(result) => to_exit_code(
+                        result.missing_archives.is_empty()
+                            && result.missing_mods.is_empty()
+                            && result.mismatches.is_empty(),
+                    ),
+                    None => ExitCode::FAILURE,
+                },
+            }
+        }
+        Some(Commands::Cfg { command, options }) => {
+            let Some(manager) = options.get_manager() else {
+                return ExitCode::FAILURE;
+            };
+            match command {
+                CfgCommands::Apply { config } => {
+                    to_exit_code(manager.apply_cfg(options.profile.as_deref(), config.to_owned()))
+                }
+            }
+        }
+        Some(Commands::Diff {
+            config,
+            with,
+            options,
+        }) => {
+            let Some(manager) = options.get_manager() else {
+                return ExitCode::FAILURE;
+            };
+            let profile = options.profile.as_deref();
+            let diff = match with {
+                Some(other) => manager.diff_profiles(profile, other),
+                None => manager.diff_cfg(profile, config.to_owned()),
+            };
+            to_exit_code(diff.is_some())
+        }
         None => ExitCode::FAILURE,
     }
 }
@@ -0,0 +1,469 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use common::{
+    archive::{install_archive, is_supported_archive},
+    diff::{CfgDiff, EDiffKind},
+    get_plugins_in_folder,
+    meta::{get_meta_path, update_archive_meta},
+    modlist::{find_mod_archives, get_base_game_dirs, Modlist, ModlistImport},
+    profile::{
+        copy_dir, get_manager_dir, get_new_profile_dir, get_profile_cfg, get_profiles,
+        get_profiles_dir, is_base_game_dir, ManagerSettings, ProfileLists, OPENMW_CFG,
+    },
+};
+use log::{error, info, warn};
+
+/// The omwmm settings and profiles, changed without the gui
+pub struct Manager {
+    /// the folder with the settings and profiles
+    pub dir: PathBuf,
+    pub settings: ManagerSettings,
+}
+
+impl Manager {
+    /// reads the omwmm settings, default is omwmm's config folder
+    /// None if no folder is given and the system has no config folder
+    pub fn new(dir: Option<PathBuf>) -> Option<Self> {
+        let Some(dir) = dir.or_else(get_manager_dir) else {
+            error!("Could not find the config folder, pass --omwmm-dir");
+            return None;
+        };
+        let settings = ManagerSettings::read(&dir).unwrap_or_default();
+        Some(Self { dir, settings })
+    }
+
+    /// Gets the folder of a profile, default is the current profile
+    /// the current profile is created if it doesn't exist yet
+    pub fn get_profile_dir(&self, profile: Option<&str>) -> Option<PathBuf> {
+        let name = profile.unwrap_or(&self.settings.current_profile);
+        let profile_dir = get_profiles_dir(&self.dir).join(name);
+        if !profile_dir.exists() {
+            if name != self.settings.current_profile {
+                error!("Profile {} does not exist", name);
+                return None;
+            }
+            if let Err(err) = fs::create_dir_all(&profile_dir) {
+                error!("Could not create profile {}: {}", name, err);
+                return None;
+            }
+        }
+        Some(profile_dir)
+    }
+
+    /// extracts an archive into the mods library and adds the mod to a profile
+    /// the archive is copied to the downloads library first, if there is one
+    pub fn install_mod(
+        &self,
+        profile: Option<&str>,
+        archive: &Path,
+        enable: bool,
+        reinstall: bool,
+    ) -> bool {
+        // checks
+        let Some(library) = &self.settings.mods_library else {
+            error!("No mods library set");
+            return false;
+        };
+        let Some(file_name) = archive.file_name() else {
+            error!("Invalid archive {}", archive.display());
+            return false;
+        };
+        if !archive.is_file() || !is_supported_archive(archive) {
+            error!("{} is not a supported archive", archive.display());
+            return false;
+        }
+        let Some(profile_dir) = self.get_profile_dir(profile) else { return false };
+
+        let mut install_path = library.join(file_name);
+        install_path.set_extension("");
+        if install_path.exists() && !reinstall {
+            error!("{} is already installed", file_name.to_string_lossy());
+            return false;
+        }
+
+        // keep the archive with the others so omwmm knows where the mod came from
+        let mut archive = archive.to_path_buf();
+        if let Some(downloads) = &self.settings.downloads_library {
+            let destination = downloads.join(file_name);
+            if !destination.exists() {
+                info!("Copying {} to {}", archive.display(), downloads.display());
+                if let Err(err) = fs::copy(&archive, &destination) {
+                    error!("Could not copy {}: {}", archive.display(), err);
+                    return false;
+                }
+                let meta_path = get_meta_path(&archive);
+                if meta_path.exists() {
+                    if let Err(err) = fs::copy(&meta_path, get_meta_path(&destination)) {
+                        warn!("Could not copy {}: {}", meta_path.display(), err);
+                    }
+                }
+            }
+            archive = destination;
+        }
+
+        info!("Installing {} ...", archive.display());
+        if let Err(err) = install_archive(&archive, &install_path, |_, _| true) {
+            error!("Could not install {}: {}", archive.display(), err);
+            return false;
+        }
+        update_archive_meta(&archive, true);
+
+        // the new mod is appended to the profile
+        let mut lists = ProfileLists::load(&profile_dir, Some(library));
+        if enable {
+            let plugins = get_plugin_names(&install_path);
+            for (path, enabled) in lists.mods.iter_mut() {
+                *enabled |= *path == install_path;
+            }
+            for (name, enabled) in lists.plugins.iter_mut() {
+                *enabled |= plugins.contains(name);
+            }
+        }
+        lists.save(&profile_dir);
+        info!("Installed {}", install_path.display());
+        true
+    }
+
+    /// enables or disables a mod of a profile by its folder name or path
+    /// enabling can also enable the plugins of the mod
+    pub fn set_mod_enabled(
+        &self,
+        profile: Option<&str>,
+        name: &str,
+        enabled: bool,
+        with_plugins: bool,
+    ) -> bool {
+        let Some(profile_dir) = self.get_profile_dir(profile) else { return false };
+        let mut lists = ProfileLists::load(&profile_dir, self.settings.mods_library.as_deref());
+        let Some(i) = lists.mods.iter().position(|(path, _)| is_mod(path, name)) else {
+            error!("Mod {} not found", name);
+            return false;
+        };
+        let path = lists.mods[i].0.to_owned();
+        if !enabled && is_base_game_dir(&path) {
+            error!("Base game data can not be disabled");
+            return false;
+        }
+
+        lists.mods[i].1 = enabled;
+        if enabled && with_plugins {
+            let plugins = get_plugin_names(&path);
+            for (name, enabled) in lists.plugins.iter_mut() {
+                *enabled |= plugins.contains(name);
+            }
+        }
+        lists.save(&profile_dir);
+        info!(
+            "{} {}",
+            if enabled { "Enabled" } else { "Disabled" },
+            path.display()
+        );
+        true
+    }
+
+    /// lists the mods of a profile in load order, + if enabled and - if disabled
+    pub fn list_mods(&self, profile: Option<&str>, verbose: bool) -> Option<ProfileLists> {
+        let profile_dir = self.get_profile_dir(profile)?;
+        let lists = ProfileLists::load(&profile_dir, self.settings.mods_library.as_deref());
+        for (path, enabled) in lists.mods.iter() {
+            info!("{}{}", if *enabled { '+' } else { '-' }, path.display());
+        }
+        if verbose {
+            for (name, enabled) in lists.plugins.iter() {
+                info!("\t{}{}", if *enabled { '+' } else { '-' }, name);
+            }
+        }
+        Some(lists)
+    }
+
+    /// creates a new profile, optionally as a copy of another one
+    pub fn create_profile(&self, name: &str, from: Option<&str>) -> bool {
+        let Some(profile_dir) = get_new_profile_dir(&get_profiles_dir(&self.dir), name) else { return false };
+        let result = match from {
+            Some(from) => {
+                let Some(from_dir) = self.get_profile_dir(Some(from)) else { return false };
+                copy_dir(&from_dir, &profile_dir)
+            }
+            None => fs::create_dir_all(&profile_dir),
+        };
+        if let Err(err) = result {
+            error!("Could not create profile {}: {}", name, err);
+            return false;
+        }
+        info!("Created profile {}", name);
+        true
+    }
+
+    /// makes a profile the current one, omwmm picks it up on the next start
+    pub fn switch_profile(&mut self, name: &str) -> bool {
+        if !get_profiles(&get_profiles_dir(&self.dir))
+            .iter()
+            .any(|p| p == name)
+        {
+            error!("Profile {} does not exist", name);
+            return false;
+        }
+        self.settings.current_profile = name.to_owned();
+        // library paths given on the command line are not saved
+        let mut settings = ManagerSettings::read(&self.dir).unwrap_or_default();
+        settings.current_profile = name.to_owned();
+        if let Err(err) = settings.write(&self.dir) {
+            error!("Could not save settings: {}", err);
+            return false;
+        }
+        info!("Switched to profile {}", name);
+        true
+    }
+
+    /// copies the lists and configs of a profile to a folder
+    pub fn export_profile(&self, profile: Option<&str>, dir: &Path) -> bool {
+        let Some(profile_dir) = self.get_profile_dir(profile) else { return false };
+        if let Err(err) = copy_dir(&profile_dir, dir) {
+            error!("Could not export profile to {}: {}", dir.display(), err);
+            return false;
+        }
+        info!("Exported {} to {}", profile_dir.display(), dir.display());
+        true
+    }
+
+    /// writes a profile with the archives and hashes of its mods to a modlist file
+    pub fn export_modlist(&self, profile: Option<&str>, file: &Path) -> bool {
+        let Some(profile_dir) = self.get_profile_dir(profile) else { return false };
+        let name = profile.unwrap_or(&self.settings.current_profile);
+        let library = self.settings.mods_library.as_deref();
+        let lists = ProfileLists::load(&profile_dir, library);
+        let archives = match &self.settings.downloads_library {
+            Some(downloads) => {
+                let mods: Vec<PathBuf> = lists.mods.iter().map(|(p, _)| p.to_owned()).collect();
+                find_mod_archives(&mods, downloads)
+            }
+            None => HashMap::new(),
+        };
+
+        info!("Hashing {} archives ...", archives.len());
+        let result = Modlist::new(name, &lists, library, &archives, |_, _| true)
+            .and_then(|modlist| modlist.write(file));
+        if let Err(err) = result {
+            error!("Could not export modlist {}: {}", file.display(), err);
+            return false;
+        }
+        info!("Exported {} to {}", name, file.display());
+        true
+    }
+
+    /// creates a profile from a modlist file and reports the archives that are missing in the downloads library
+    pub fn import_modlist(&self, file: &Path, name: Option<&str>) -> Option<ModlistImport> {
+        let modlist = match Modlist::read(file) {
+            Ok(modlist) => modlist,
+            Err(err) => {
+                error!("Could not read modlist {}: {}", file.display(), err);
+                return None;
+            }
+        };
+        let name = name.unwrap_or(&modlist.name);
+        let profile_dir = get_new_profile_dir(&get_profiles_dir(&self.dir), name)?;
+
+        let result = modlist.import(
+            self.settings.mods_library.as_deref(),
+            self.settings.downloads_library.as_deref(),
+            &get_base_game_dirs(),
+        );
+        if let Err(err) = fs::create_dir_all(&profile_dir) {
+            error!("Could not create profile {}: {}", name, err);
+            return None;
+        }
+        result.lists.save(&profile_dir);
+        info!("Imported profile {}", name);
+        log_modlist_report(&result);
+        Some(result)
+    }
+
+    /// installs the mods of a modlist file from the downloads library and creates a profile with them
+    /// installed mods are kept unless replace is set
+    /// reports archives that are missing or differ from the ones the modlist was made with
+    pub fn install_modlist(
+        &self,
+        file: &Path,
+        name: Option<&str>,
+        replace: bool,
+    ) -> Option<ModlistImport> {
+        let (Some(library), Some(downloads)) = (
+            &self.settings.mods_library,
+            &self.settings.downloads_library,
+        ) else {
+            error!("No mods library or downloads library set");
+            return None;
+        };
+        let modlist = match Modlist::read(file) {
+            Ok(modlist) => modlist,
+            Err(err) => {
+                error!("Could not read modlist {}: {}", file.display(), err);
+                return None;
+            }
+        };
+        let name = name.unwrap_or(&modlist.name);
+        let profile_dir = get_new_profile_dir(&get_profiles_dir(&self.dir), name)?;
+
+        for installed in modlist.get_installed_mods(library) {
+            if replace {
+                info!("Replacing installed {}", installed);
+            } else {
+                warn!(
+                    "{} is installed and kept, use --replace to reinstall it",
+                    installed
+                );
+            }
+        }
+        let base_game_dirs = get_base_game_dirs();
+        let result = modlist.install(
+            library,
+            downloads,
+            &base_game_dirs,
+            replace,
+            |done, total| {
+                info!("Installing mods {}/{}", done, total);
+                true
+            },
+        );
+        let result = match result {
+            Ok(result) => result,
+            Err(err) => {
+                error!("Could not install modlist {}: {}", file.display(), err);
+                return None;
+            }
+        };
+        if let Err(err) = fs::create_dir_all(&profile_dir) {
+            error!("Could not create profile {}: {}", name, err);
+            return None;
+        }
+        result.lists.save(&profile_dir);
+        info!("Installed profile {}", name);
+        log_modlist_report(&result);
+        Some(result)
+    }
+
+    /// writes the enabled mods and plugins of a profile to its openmw.cfg or another cfg
+    pub fn apply_cfg(&self, profile: Option<&str>, cfg_path: Option<PathBuf>) -> bool {
+        let Some(profile_dir) = self.get_profile_dir(profile) else { return false };
+        let cfg_path = cfg_path.unwrap_or_else(|| get_profile_cfg(&profile_dir));
+        if !cfg_path.exists() {
+            error!("No cfg found at {}", cfg_path.display());
+            return false;
+        }
+
+        // new mods of the library are stored like omwmm does
+        let lists = ProfileLists::load(&profile_dir, self.settings.mods_library.as_deref());
+        lists.save(&profile_dir);
+        if !lists.apply_cfg(&cfg_path) {
+            return false;
+        }
+        info!("Updated {}", cfg_path.display());
+        true
+    }
+
+    /// compares the openmw.cfg of a profile with what apply_cfg would write to it
+    pub fn diff_cfg(&self, profile: Option<&str>, cfg_path: Option<PathBuf>) -> Option<CfgDiff> {
+        let profile_dir = self.get_profile_dir(profile)?;
+        let cfg_path = match cfg_path {
+            Some(cfg_path) => {
+                if !cfg_path.exists() {
+                    error!("No cfg found at {}", cfg_path.display());
+                    return None;
+                }
+                cfg_path
+            }
+            // the diff does not create the profile cfg, a missing cfg has no entries
+            None => profile_dir.join(OPENMW_CFG),
+        };
+
+        let lists = ProfileLists::load(&profile_dir, self.settings.mods_library.as_deref());
+        let (data, content) = lists.get_merged_cfg_entries(&cfg_path);
+        let diff = CfgDiff::from_cfg(&cfg_path, &data, &content);
+        log_cfg_diff(&diff);
+        Some(diff)
+    }
+
+    /// compares what the cfgs of two profiles load, from the profile to the other one
+    pub fn diff_profiles(&self, profile: Option<&str>, other: &str) -> Option<CfgDiff> {
+        let (old_data, old_content) = self.get_cfg_entries(profile)?;
+        let (data, content) = self.get_cfg_entries(Some(other))?;
+        let diff = CfgDiff::new(&old_data, &old_content, &data, &content);
+        log_cfg_diff(&diff);
+        Some(diff)
+    }
+
+    /// the data directories and content files apply_cfg writes to the cfg of a profile
+    fn get_cfg_entries(&self, profile: Option<&str>) -> Option<(Vec<PathBuf>, Vec<String>)> {
+        let profile_dir = self.get_profile_dir(profile)?;
+        let lists = ProfileLists::load(&profile_dir, self.settings.mods_library.as_deref());
+        Some(lists.get_merged_cfg_entries(&profile_dir.join(OPENMW_CFG)))
+    }
+}
+
+/// logs the changed entries of a cfg: + added, - removed, ~ moved
+fn log_cfg_diff(diff: &CfgDiff) {
+    if diff.is_empty() {
+        info!("No changes");
+        return;
+    }
+    let data = diff
+        .data
+        .iter()
+        .map(|e| ("data", e.entry.to_string_lossy().into_owned(), e.kind));
+    let content = diff
+        .content
+        .iter()
+        .map(|e| ("content", e.entry.to_owned(), e.kind));
+    for (key, entry, kind) in data.chain(content) {
+        match kind {
+            EDiffKind::Unchanged => {}
+            EDiffKind::Added => info!("+{}={}", key, entry),
+            EDiffKind::Removed => info!("-{}={}", key, entry),
+            EDiffKind::Moved { from, to } => {
+                info!("~{}={} ({} -> {})", key, entry, from + 1, to + 1)
+            }
+        }
+    }
+}
+
+/// logs what is missing of an imported modlist
+fn log_modlist_report(result: &ModlistImport) {
+    for archive in result.missing_archives.iter() {
+        warn!(
+            "Missing archive {} {}",
+            archive.file_name,
+            archive.get_url().unwrap_or_default()
+        );
+    }
+    for mod_name in result.missing_mods.iter() {
+        warn!("Missing mod {}", mod_name);
+    }
+    for mismatch in result.mismatches.iter() {
+        warn!("Mismatch {}", mismatch);
+    }
+    info!(
+        "{} archives and {} mods are missing, {} mismatches",
+        result.missing_archives.len(),
+        result.missing_mods.len(),
+        result.mismatches.len()
+    );
+}
+
+/// matches a mod by its path or case-insensitively by its folder name
+fn is_mod(path: &Path, name: &str) -> bool {
+    path == Path::new(name)
+        || path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().eq_ignore_ascii_case(name))
+}
+
+fn get_plugin_names(data_dir: &Path) -> Vec<String> {
+    get_plugins_in_folder(&data_dir, true)
+        .iter()
+        .filter_map(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .collect()
+}
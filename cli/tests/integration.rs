@@ -2,7 +2,23 @@
 mod integration_tests {
     use std::path::{Path, PathBuf};
 
+    use common::archive::{extract_archive, hash_file};
+    use common::diff::{diff_lists, CfgDiff, DiffEntry, EDiffKind};
+    use common::import::{read_mo2_instance, read_morrowind_ini_plugins};
+    use common::meta::{compare_versions, get_meta_path, read_meta, write_meta};
+    use common::modlist::{find_mod_archives, update_mod, Modlist};
     use common::parse_cfg;
+    use common::profile::{
+        get_external_entries, get_profiles_dir, ManagerSettings, ProfileLists, MODS_LIST,
+        OPENMW_CFG, PLUGINS_LIST,
+    };
+    use common::tes3::{read_plugin, RecordIndex};
+    use common::trash::{
+        copy_and_remove_dir, delete_from_trash, empty_trash, get_trash_dir, get_trashed_mods,
+        move_to_trash, restore_from_trash,
+    };
+    use common::vfs::VfsIndex;
+    use omw_util::manager::Manager;
     use omw_util::{cleanup, copy_files, get_plugins};
     use omw_util::{export, import};
 
@@ -110,4 +126,993 @@ mod integration_tests {
         // destroy test environment
         std::fs::remove_dir_all(test_env).expect("Failed destroy test env");
     }
+
+    #[test]
+    fn test_conflicts() {
+        // setup test environment
+        let test_env = Path::new("tests/integration/conflicts");
+        let mod1 = test_env.join("mod1");
+        let mod2 = test_env.join("mod2");
+        let mod3 = test_env.join("mod3");
+        std::fs::create_dir_all(mod1.join("Meshes")).expect("Failed setup test env: folders");
+        std::fs::create_dir_all(mod2.join("meshes")).expect("Failed setup test env: folders");
+        std::fs::create_dir_all(&mod3).expect("Failed setup test env: folders");
+        std::fs::write(mod1.join("Meshes").join("a.nif"), b"1").expect("Failed setup test env");
+        std::fs::write(mod1.join("b.esp"), b"1").expect("Failed setup test env");
+        std::fs::write(mod2.join("meshes").join("A.NIF"), b"2").expect("Failed setup test env");
+        std::fs::write(mod3.join("B.esp"), b"3").expect("Failed setup test env");
+
+        let index = VfsIndex::new(&[mod1.clone(), mod2.clone(), mod3.clone()]);
+        let conflicts = index.get_conflicts();
+        assert_eq!(conflicts.len(), 3);
+
+        // mod1 loses both files
+        assert!(conflicts[0].overrides.is_empty());
+        assert_eq!(conflicts[0].overridden, vec!["b.esp", "meshes/a.nif"]);
+        // mod2 wins the mesh over mod1
+        assert_eq!(conflicts[1].overrides, vec!["meshes/a.nif"]);
+        assert!(conflicts[1].overridden.is_empty());
+        // mod3 wins the plugin over mod1
+        assert_eq!(conflicts[2].overrides, vec!["b.esp"]);
+        assert!(conflicts[2].overridden.is_empty());
+
+        // destroy test environment
+        std::fs::remove_dir_all(test_env).expect("Failed destroy test env");
+    }
+
+    #[test]
+    fn test_extract_archive() {
+        use std::io::Write;
+
+        // setup test environment
+        let test_env = Path::new("tests/integration/archive");
+        std::fs::create_dir_all(test_env).expect("Failed setup test env: folders");
+        let archive = test_env.join("mod.zip");
+        let file = std::fs::File::create(&archive).expect("Failed setup test env");
+        let mut zip = zip::ZipWriter::new(file);
+        let options = zip::write::FileOptions::default();
+        zip.add_directory("Data Files/", options)
+            .expect("Failed setup test env");
+        zip.start_file("Data Files/mod.esp", options)
+            .expect("Failed setup test env");
+        zip.write_all(b"abc").expect("Failed setup test env");
+        zip.start_file("../outside.txt", options)
+            .expect("Failed setup test env");
+        zip.write_all(b"abc").expect("Failed setup test env");
+        zip.finish().expect("Failed setup test env");
+
+        // cancelling stops before the first entry
+        let out = test_env.join("out");
+        let result = extract_archive(&archive, &out, |_, _| false);
+        assert_eq!(
+            result.map_err(|e| e.kind()),
+            Err(std::io::ErrorKind::Interrupted)
+        );
+
+        let mut progress = vec![];
+        extract_archive(&archive, &out, |done, total| {
+            progress.push((done, total));
+            true
+        })
+        .expect("Failed to extract archive");
+        assert_eq!(progress.last(), Some(&(3, 3)));
+        let esp = out.join("Data Files").join("mod.esp");
+        assert_eq!(std::fs::read(&esp).expect("Failed to read file"), b"abc");
+        // entries outside of the destination are skipped
+        assert!(!test_env.join("outside.txt").exists());
+
+        // skipped entries of a solid 7z archive do not shift the data of the next entries
+        let archive = test_env.join("mod.7z");
+        let mut writer =
+            sevenz_rust::SevenZWriter::create(&archive).expect("Failed setup test env");
+        let entries = ["../outside.txt", "Data Files/solid.esp"]
+            .map(|name| {
+                let mut entry = sevenz_rust::SevenZArchiveEntry::new();
+                entry.name = name.to_owned();
+                entry.has_stream = true;
+                entry
+            })
+            .to_vec();
+        let data: Vec<sevenz_rust::SourceReader<&[u8]>> =
+            vec![b"outside"[..].into(), b"abc"[..].into()];
+        writer
+            .push_archive_entries(entries, data.into())
+            .expect("Failed setup test env");
+        writer.finish().expect("Failed setup test env");
+        extract_archive(&archive, &out, |_, _| true).expect("Failed to extract archive");
+        assert_eq!(
+            std::fs::read(out.join("Data Files").join("solid.esp")).expect("Failed to read file"),
+            b"abc"
+        );
+        assert!(!test_env.join("outside.txt").exists());
+
+        let hash = hash_file(&esp, |_, _| true).expect("Failed to hash file");
+        assert_eq!(
+            hash,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        // destroy test environment
+        std::fs::remove_dir_all(test_env).expect("Failed destroy test env");
+    }
+
+    #[test]
+    fn test_import_mo2() {
+        // setup test environment
+        let test_env = Path::new("tests/integration/mo2");
+        let profile_dir = test_env.join("profiles").join("Default");
+        std::fs::create_dir_all(test_env.join("mods").join("Mod A"))
+            .expect("Failed setup test env: folders");
+        std::fs::create_dir_all(&profile_dir).expect("Failed setup test env: folders");
+        std::fs::write(
+            profile_dir.join("modlist.txt"),
+            "# This file was automatically generated by Mod Organizer.\n+Mod B\n+Patches_separator\n-Mod A\n*Unmanaged: Tribunal\n",
+        )
+        .expect("Failed setup test env");
+        std::fs::write(
+            profile_dir.join("loadorder.txt"),
+            "Morrowind.esm\nA.esp\nB.esp\n",
+        )
+        .expect("Failed setup test env");
+        std::fs::write(
+            profile_dir.join("plugins.txt"),
+            "*Morrowind.esm\nA.esp\n*b.esp\n*C.esp\n",
+        )
+        .expect("Failed setup test env");
+        std::fs::write(
+            test_env.join("Morrowind.ini"),
+            "[General]\nGameFile0=wrong.esp\n[Game Files]\nGameFile1=Tribunal.esm\nGameFile0=Morrowind.esm\n",
+        )
+        .expect("Failed setup test env");
+
+        let instance = read_mo2_instance(test_env).expect("Failed to read MO2 instance");
+        assert_eq!(instance.mods_dir, test_env.join("mods"));
+        assert_eq!(instance.profiles.len(), 1);
+        let profile = &instance.profiles[0];
+        assert_eq!(profile.name, "Default");
+        // MO2 lists the winning mod first
+        assert_eq!(
+            profile.mods,
+            vec![("Mod A".to_owned(), false), ("Mod B".to_owned(), true)]
+        );
+        assert_eq!(
+            profile.plugins,
+            vec![
+                ("Morrowind.esm".to_owned(), true),
+                ("A.esp".to_owned(), false),
+                ("B.esp".to_owned(), true),
+                ("C.esp".to_owned(), true)
+            ]
+        );
+
+        // the mods folder can be moved in the instance settings
+        std::fs::create_dir_all(test_env.join("moved").join("Mod C"))
+            .expect("Failed setup test env: folders");
+        std::fs::write(
+            test_env.join("ModOrganizer.ini"),
+            "[General]\ngameName=Morrowind\n[Settings]\nmod_directory=%BASE_DIR%/moved\n",
+        )
+        .expect("Failed setup test env");
+        let instance = read_mo2_instance(test_env).expect("Failed to read MO2 instance");
+        assert_eq!(instance.mods_dir, test_env.join("moved"));
+        assert_eq!(instance.profiles.len(), 1);
+
+        let plugins = read_morrowind_ini_plugins(&test_env.join("Morrowind.ini"))
+            .expect("Failed to read Morrowind.ini");
+        assert_eq!(plugins, vec!["Morrowind.esm", "Tribunal.esm"]);
+
+        // destroy test environment
+        std::fs::remove_dir_all(test_env).expect("Failed destroy test env");
+    }
+
+    #[test]
+    fn test_meta() {
+        // setup test environment
+        let test_env = Path::new("tests/integration/meta");
+        std::fs::create_dir_all(test_env).expect("Failed setup test env: folders");
+        let meta_path = get_meta_path(&test_env.join("Mod-123-1-0.7z"));
+        assert_eq!(meta_path, test_env.join("Mod-123-1-0.7z.meta"));
+        std::fs::write(
+            &meta_path,
+            "[General]\ngameName=morrowind\nmodid=123\nfileID=456\nurl=\"https://www.nexusmods.com/morrowind/mods/123\"\nname=Mod\ndescription=\"first line\\nsecond \\\"line\\\"\"\nmodName=Mod\nversion=1.0\ninstalled=false\nuninstalled=false\nnexusFileStatus=1\npaused=false\n\n[installedFiles]\nsize=0\n",
+        )
+        .expect("Failed setup test env");
+
+        let mut meta = read_meta(&meta_path).expect("Failed to read meta");
+        assert_eq!(meta.game_name.as_deref(), Some("morrowind"));
+        assert_eq!(meta.mod_id, Some(123));
+        assert_eq!(meta.file_id, Some(456));
+        assert_eq!(
+            meta.url.as_deref(),
+            Some("https://www.nexusmods.com/morrowind/mods/123")
+        );
+        assert_eq!(
+            meta.description.as_deref(),
+            Some("first line\nsecond \"line\"")
+        );
+        assert_eq!(meta.version.as_deref(), Some("1.0"));
+        assert_eq!(meta.installed, Some(false));
+        assert_eq!(meta.nexus_file_status, Some(1));
+
+        // unknown keys and sections are kept
+        meta.installed = Some(true);
+        write_meta(&meta_path, &meta).expect("Failed to write meta");
+        let written = std::fs::read_to_string(&meta_path).expect("Failed to read meta");
+        assert!(written.contains("paused=false"));
+        assert!(written.contains("[installedFiles]"));
+        assert!(!written.contains("modid="));
+        assert_eq!(read_meta(&meta_path).expect("Failed to read meta"), meta);
+
+        // destroy test environment
+        std::fs::remove_dir_all(test_env).expect("Failed destroy test env");
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        use std::cmp::Ordering;
+
+        assert_eq!(compare_versions("1.10", "1.9"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("v2.1", "2.1"), Ordering::Equal);
+        assert_eq!(compare_versions("1.2", "1.2.1"), Ordering::Less);
+        assert_eq!(compare_versions("1.0a", "1.0b"), Ordering::Less);
+        assert_eq!(compare_versions("10", "2beta"), Ordering::Greater);
+        assert_eq!(compare_versions("1.2beta", "1.10"), Ordering::Less);
+        assert_eq!(compare_versions("2beta", "2BETA"), Ordering::Equal);
+    }
+
+    fn subrecord(tag: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut bytes = tag.to_vec();
+        bytes.extend((data.len() as u32).to_le_bytes());
+        bytes.extend(data);
+        bytes
+    }
+
+    fn record(tag: &[u8; 4], subrecords: &[Vec<u8>]) -> Vec<u8> {
+        let data = subrecords.concat();
+        let mut bytes = tag.to_vec();
+        bytes.extend((data.len() as u32).to_le_bytes());
+        bytes.extend([0_u8; 8]);
+        bytes.extend(data);
+        bytes
+    }
+
+    /// writes a minimal TES3 plugin with NAME records
+    fn write_plugin(path: &Path, masters: &[&str], records: &[(&[u8; 4], &str)]) {
+        let mut header = vec![subrecord(b"HEDR", &[0_u8; 300])];
+        for master in masters {
+            header.push(subrecord(b"MAST", format!("{}\0", master).as_bytes()));
+            header.push(subrecord(b"DATA", &[0_u8; 8]));
+        }
+        let mut bytes = record(b"TES3", &header);
+        for (tag, id) in records {
+            bytes.extend(record(
+                tag,
+                &[subrecord(b"NAME", format!("{}\0", id).as_bytes())],
+            ));
+        }
+        std::fs::write(path, bytes).expect("Failed setup test env: plugin");
+    }
+
+    #[test]
+    fn test_record_conflicts() {
+        // setup test environment
+        let test_env = Path::new("tests/integration/record_conflicts");
+        std::fs::create_dir_all(test_env).expect("Failed setup test env: folders");
+        let master = test_env.join("Master.esm");
+        let mod1 = test_env.join("mod1.esp");
+        let mod2 = test_env.join("mod2.esp");
+        write_plugin(&master, &[], &[(b"NPC_", "fargoth"), (b"GLOB", "gamehour")]);
+        write_plugin(
+            &mod1,
+            &["Master.esm"],
+            &[(b"NPC_", "Fargoth"), (b"BOOK", "mod1_book")],
+        );
+        write_plugin(&mod2, &["Master.esm"], &[(b"NPC_", "fargoth")]);
+
+        let index = RecordIndex::new(&[master, mod1, mod2]);
+
+        // only the two esps clash, the master is overridden intentionally
+        let conflicts = index.get_record_conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].record.to_string(), "NPC_:fargoth");
+        assert_eq!(conflicts[0].plugins, vec!["mod1.esp", "mod2.esp"]);
+        assert_eq!(conflicts[0].get_winner(), "mod2.esp");
+
+        let plugin_conflicts = index.get_conflicts();
+        assert!(plugin_conflicts[0].is_empty());
+        assert!(plugin_conflicts[1].overrides.is_empty());
+        assert_eq!(plugin_conflicts[1].overridden.len(), 1);
+        assert_eq!(plugin_conflicts[2].overrides.len(), 1);
+        assert!(plugin_conflicts[2].overridden.is_empty());
+        assert!(index.get_missing_masters().is_empty());
+
+        // masters have to load first
+        let index = RecordIndex::new(&[
+            test_env.join("mod1.esp"),
+            test_env.join("Master.esm"),
+            test_env.join("mod2.esp"),
+        ]);
+        assert_eq!(
+            index.get_missing_masters(),
+            vec![("mod1.esp".to_owned(), vec!["Master.esm".to_owned()])]
+        );
+
+        // a record larger than the file is rejected before it is read
+        let broken = test_env.join("broken.esp");
+        let mut bytes = b"TES3".to_vec();
+        bytes.extend(u32::MAX.to_le_bytes());
+        bytes.extend([0_u8; 8]);
+        std::fs::write(&broken, bytes).expect("Failed setup test env: plugin");
+        assert_eq!(
+            read_plugin(&broken).map_err(|e| e.kind()).err(),
+            Some(std::io::ErrorKind::InvalidData)
+        );
+
+        // destroy test environment
+        std::fs::remove_dir_all(test_env).expect("Failed destroy test env");
+    }
+
+    #[test]
+    fn test_profile_lists() {
+        // setup test environment
+        let test_env = Path::new("tests/integration/profile");
+        let library = test_env.join("mods");
+        let base_game = test_env.join("Data Files");
+        let external = test_env.join("external");
+        let profile_dir = test_env.join("profiles").join("default");
+        for dir in [
+            library.join("mod_a"),
+            library.join("mod_b"),
+            library.join(".mod_c.part"),
+            base_game.to_owned(),
+            external.to_owned(),
+            profile_dir.to_owned(),
+        ] {
+            std::fs::create_dir_all(dir).expect("Failed setup test env: folders");
+        }
+        for file in [
+            library.join("mod_a").join("a.esp"),
+            library.join("mod_b").join("b.esp"),
+            base_game.join("Morrowind.esm"),
+            base_game.join("Morrowind.bsa"),
+        ] {
+            std::fs::write(file, b"").expect("Failed setup test env: files");
+        }
+        std::fs::write(
+            profile_dir.join(MODS_LIST),
+            format!(
+                "+{}\n-{}\n+{}\n",
+                base_game.display(),
+                library.join("mod_b").display(),
+                library.join("removed").display()
+            ),
+        )
+        .expect("Failed setup test env: mods");
+        std::fs::write(profile_dir.join(PLUGINS_LIST), "+b.esp\n+removed.esp\n")
+            .expect("Failed setup test env: plugins");
+
+        // removed entries are dropped, new ones appended
+        let lists = ProfileLists::load(&profile_dir, Some(&library));
+        assert_eq!(
+            lists.mods,
+            vec![
+                (base_game.to_owned(), true),
+                (library.join("mod_b"), false),
+                (library.join("mod_a"), false),
+            ]
+        );
+        assert_eq!(
+            lists.plugins,
+            vec![
+                ("b.esp".to_owned(), true),
+                ("Morrowind.esm".to_owned(), true),
+                ("a.esp".to_owned(), false),
+            ]
+        );
+
+        // plugins of disabled mods are not loaded
+        let (data, content) = lists.get_cfg_entries();
+        assert_eq!(data, vec![base_game.to_owned()]);
+        assert_eq!(content, vec!["Morrowind.esm".to_owned()]);
+
+        // other lines and external entries are kept
+        let cfg_path = profile_dir.join(OPENMW_CFG);
+        std::fs::write(
+            &cfg_path,
+            format!(
+                "encoding=win1252\ndata=\"{}\"\ndata=\"{}\"\ncontent=external.esp\ncontent=b.esp\n",
+                external.display(),
+                library.join("mod_b").display()
+            ),
+        )
+        .expect("Failed setup test env: cfg");
+        assert!(lists.apply_cfg(&cfg_path));
+        let info = parse_cfg(cfg_path.to_owned()).expect("Failed to parse cfg");
+        assert_eq!(info.data, vec![external.to_owned(), base_game.to_owned()]);
+        assert_eq!(info.plugins, vec!["external.esp", "Morrowind.esm"]);
+        let cfg = std::fs::read_to_string(&cfg_path).expect("Failed to read cfg");
+        assert!(cfg.starts_with("encoding=win1252\nfallback-archive=Morrowind.bsa\n"));
+
+        // lists survive saving
+        lists.save(&profile_dir);
+        assert_eq!(ProfileLists::load(&profile_dir, Some(&library)), lists);
+
+        // the caller can decide what is base game data, e.g. a mod the user marked
+        let lists = ProfileLists::load_with(&profile_dir, Some(&library), |path| {
+            path == library.join("mod_a")
+        });
+        assert!(lists.mods.contains(&(library.join("mod_a"), true)));
+        assert!(lists.plugins.contains(&("a.esp".to_owned(), false)));
+
+        // destroy test environment
+        std::fs::remove_dir_all(test_env).expect("Failed destroy test env");
+    }
+
+    #[test]
+    fn test_external_entries() {
+        let list = ["a", "b", "c"];
+        assert_eq!(
+            get_external_entries(&list, vec!["x", "b", "y", "z", "c"]),
+            vec![(0, "x"), (3, "y"), (4, "z")]
+        );
+        assert!(get_external_entries(&list, vec!["c", "a"]).is_empty());
+    }
+
+    #[test]
+    fn test_manager() {
+        use std::io::Write;
+
+        // setup test environment
+        let test_env = Path::new("tests/integration/manager");
+        if test_env.exists() {
+            std::fs::remove_dir_all(test_env).expect("Failed setup test env");
+        }
+        let library = test_env.join("mods");
+        let downloads = test_env.join("downloads");
+        std::fs::create_dir_all(&library).expect("Failed setup test env: folders");
+        std::fs::create_dir_all(&downloads).expect("Failed setup test env: folders");
+        let archive = test_env.join("Mod C.zip");
+        let file = std::fs::File::create(&archive).expect("Failed setup test env");
+        let mut zip = zip::ZipWriter::new(file);
+        zip.start_file("c.esp", zip::write::FileOptions::default())
+            .expect("Failed setup test env");
+        zip.write_all(b"abc").expect("Failed setup test env");
+        zip.finish().expect("Failed setup test env");
+
+        let mut manager =
+            Manager::new(Some(test_env.join("omwmm"))).expect("Failed to create manager");
+        assert_eq!(manager.settings, ManagerSettings::default());
+        assert!(!manager.install_mod(None, &archive, true, false));
+        manager.settings.mods_library = Some(library.to_owned());
+        manager.settings.downloads_library = Some(downloads.to_owned());
+
+        // install and enable
+        assert!(manager.install_mod(None, &archive, true, false));
+        let mod_path = library.join("Mod C");
+        assert!(mod_path.join("c.esp").exists());
+        assert!(downloads.join("Mod C.zip").exists());
+        let meta = read_meta(get_meta_path(&downloads.join("Mod C.zip"))).expect("No meta");
+        assert_eq!(meta.installed, Some(true));
+        let lists = manager.list_mods(None, false).expect("Failed to list mods");
+        assert_eq!(lists.mods, vec![(mod_path.to_owned(), true)]);
+        assert_eq!(lists.plugins, vec![("c.esp".to_owned(), true)]);
+
+        // installed mods are only replaced on request
+        assert!(!manager.install_mod(None, &archive, false, false));
+        assert!(manager.install_mod(None, &archive, false, true));
+
+        // enable and disable by name
+        assert!(manager.set_mod_enabled(None, "mod c", false, false));
+        assert!(!manager.set_mod_enabled(None, "missing", true, false));
+        let lists = manager.list_mods(None, false).expect("Failed to list mods");
+        assert_eq!(lists.mods, vec![(mod_path.to_owned(), false)]);
+
+        // profiles
+        assert!(manager.create_profile("other", Some("default")));
+        assert!(!manager.create_profile("other", None));
+        assert!(!manager.create_profile("../other", None));
+        assert!(!manager.switch_profile("missing"));
+        assert!(manager.switch_profile("other"));
+        let mut manager =
+            Manager::new(Some(test_env.join("omwmm"))).expect("Failed to create manager");
+        assert_eq!(manager.settings.current_profile, "other");
+        // command line libraries are not saved
+        assert_eq!(manager.settings.mods_library, None);
+        manager.settings.mods_library = Some(library.to_owned());
+        assert!(manager.set_mod_enabled(None, &mod_path.to_string_lossy(), true, false));
+        assert!(
+            !manager
+                .list_mods(Some("default"), false)
+                .expect("Failed to list mods")
+                .mods[0]
+                .1
+        );
+
+        // cfg
+        let cfg_path = test_env.join(OPENMW_CFG);
+        assert!(!manager.apply_cfg(None, Some(cfg_path.to_owned())));
+        std::fs::write(&cfg_path, "").expect("Failed setup test env: cfg");
+        // preview the changes
+        let diff = manager
+            .diff_cfg(None, Some(cfg_path.to_owned()))
+            .expect("Failed to diff cfg");
+        assert_eq!(diff.data[0].kind, EDiffKind::Added);
+        assert_eq!(diff.content[0].kind, EDiffKind::Added);
+        assert!(manager.apply_cfg(None, Some(cfg_path.to_owned())));
+        let diff = manager
+            .diff_cfg(None, Some(cfg_path.to_owned()))
+            .expect("Failed to diff cfg");
+        assert!(diff.is_empty());
+        let info = parse_cfg(cfg_path).expect("Failed to parse cfg");
+        assert_eq!(info.data, vec![mod_path.to_owned()]);
+        assert_eq!(info.plugins, vec!["c.esp"]);
+
+        // the preview does not create the profile cfg
+        let profile_cfg = get_profiles_dir(&manager.dir)
+            .join("other")
+            .join(OPENMW_CFG);
+        assert!(!profile_cfg.exists());
+        let diff = manager.diff_cfg(None, None).expect("Failed to diff cfg");
+        assert_eq!(diff.data[0].kind, EDiffKind::Added);
+        assert!(!profile_cfg.exists());
+
+        // the mod is disabled in the default profile
+        for profile in ["default", "other"] {
+            let profile_cfg = get_profiles_dir(&manager.dir)
+                .join(profile)
+                .join(OPENMW_CFG);
+            std::fs::write(profile_cfg, "").expect("Failed setup test env: cfg");
+        }
+        let diff = manager
+            .diff_profiles(None, "default")
+            .expect("Failed to diff profiles");
+        assert_eq!(
+            diff.data,
+            vec![DiffEntry {
+                entry: mod_path,
+                kind: EDiffKind::Removed
+            }]
+        );
+        assert!(manager.diff_profiles(None, "missing").is_none());
+
+        // export
+        let export_dir = test_env.join("export");
+        assert!(manager.export_profile(None, &export_dir));
+        assert!(export_dir.join(MODS_LIST).exists());
+        assert!(export_dir.join(PLUGINS_LIST).exists());
+
+        // destroy test environment
+        std::fs::remove_dir_all(test_env).expect("Failed destroy test env");
+    }
+
+    #[test]
+    fn test_modlist() {
+        // setup test environment
+        let test_env = Path::new("tests/integration/modlist");
+        if test_env.exists() {
+            std::fs::remove_dir_all(test_env).expect("Failed setup test env");
+        }
+        let library = test_env.join("mods");
+        let downloads = test_env.join("downloads");
+        let base_game = test_env.join("Data Files");
+        for dir in [
+            library.join("Mod A"),
+            library.join("Mod B"),
+            downloads.to_owned(),
+            base_game.to_owned(),
+        ] {
+            std::fs::create_dir_all(dir).expect("Failed setup test env: folders");
+        }
+        std::fs::write(library.join("Mod A").join("a.esp"), b"").expect("Failed setup test env");
+        std::fs::write(base_game.join("Morrowind.esm"), b"").expect("Failed setup test env");
+        std::fs::write(downloads.join("Mod A.7z"), b"abc").expect("Failed setup test env");
+        let meta = common::meta::ModNexusMetaData {
+            mod_id: Some(123),
+            version: Some("1.0".to_owned()),
+            ..Default::default()
+        };
+        write_meta(get_meta_path(&downloads.join("Mod A.7z")), &meta)
+            .expect("Failed setup test env");
+
+        // export
+        let lists = ProfileLists {
+            mods: vec![
+                (base_game.to_owned(), true),
+                (library.join("Mod A"), true),
+                (library.join("Mod B"), false),
+            ],
+            plugins: vec![
+                ("Morrowind.esm".to_owned(), true),
+                ("a.esp".to_owned(), true),
+            ],
+        };
+        let mods: Vec<PathBuf> = lists.mods.iter().map(|(p, _)| p.to_owned()).collect();
+        let archives = find_mod_archives(&mods, &downloads);
+        assert_eq!(archives.len(), 1);
+        let modlist = Modlist::new("shared", &lists, Some(&library), &archives, |_, _| true)
+            .expect("Failed to create modlist");
+        assert!(modlist.mods[0].base_game);
+        assert_eq!(modlist.mods[1].name, "Mod A");
+        let archive = modlist.mods[1].archive.as_ref().expect("No archive");
+        assert_eq!(archive.file_name, "Mod A.7z");
+        assert_eq!(archive.size, 3);
+        assert_eq!(
+            archive.sha256.as_deref(),
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        assert_eq!(archive.mod_id, Some(123));
+        assert_eq!(
+            archive.get_url().as_deref(),
+            Some("https://www.nexusmods.com/morrowind/mods/123")
+        );
+        assert_eq!(modlist.mods[2].archive, None);
+
+        // the file keeps everything
+        let file = test_env.join("shared.toml");
+        modlist.write(&file).expect("Failed to write modlist");
+        assert_eq!(
+            Modlist::read(&file).expect("Failed to read modlist"),
+            modlist
+        );
+
+        // import on a machine with other folders and without the archive
+        let other_library = test_env.join("other");
+        let other_base_game = test_env.join("Morrowind").join("Data Files");
+        let result = modlist.import(
+            Some(&other_library),
+            Some(test_env),
+            &[other_base_game.to_owned()],
+        );
+        assert_eq!(
+            result.lists.mods,
+            vec![
+                (other_base_game, true),
+                (other_library.join("Mod A"), true),
+                (other_library.join("Mod B"), false),
+            ]
+        );
+        assert_eq!(result.lists.plugins, lists.plugins);
+        assert_eq!(result.missing_archives, vec![archive.clone()]);
+        assert_eq!(result.missing_mods, vec!["Mod B".to_owned()]);
+
+        // nothing is missing where it was exported
+        let result = modlist.import(Some(&library), Some(&downloads), &[base_game]);
+        assert_eq!(result.lists, lists);
+        assert!(result.missing_archives.is_empty());
+        assert!(result.missing_mods.is_empty());
+
+        // omw-util
+        let mut manager =
+            Manager::new(Some(test_env.join("omwmm"))).expect("Failed to create manager");
+        manager.settings.mods_library = Some(library.to_owned());
+        manager.settings.downloads_library = Some(downloads.to_owned());
+        let result = manager
+            .import_modlist(&file, Some("imported"))
+            .expect("Failed to import modlist");
+        assert!(result.missing_archives.is_empty());
+        assert!(manager.import_modlist(&file, Some("imported")).is_none());
+        let file = test_env.join("imported.toml");
+        assert!(manager.export_modlist(Some("imported"), &file));
+        let exported = Modlist::read(&file).expect("Failed to read modlist");
+        assert_eq!(exported.name, "imported");
+        // base game data comes from the default openmw.cfg of this machine
+        let get_mods = |modlist: &Modlist| -> Vec<common::modlist::ModlistMod> {
+            modlist
+                .mods
+                .iter()
+                .filter(|m| !m.base_game)
+                .cloned()
+                .collect()
+        };
+        assert_eq!(get_mods(&exported), get_mods(&modlist));
+
+        // destroy test environment
+        std::fs::remove_dir_all(test_env).expect("Failed destroy test env");
+    }
+
+    #[test]
+    fn test_install_modlist() {
+        use std::io::Write;
+
+        // setup test environment
+        let test_env = Path::new("tests/integration/install_modlist");
+        if test_env.exists() {
+            std::fs::remove_dir_all(test_env).expect("Failed setup test env");
+        }
+        let library = test_env.join("mods");
+        let downloads = test_env.join("downloads");
+        std::fs::create_dir_all(&downloads).expect("Failed setup test env: folders");
+        let archive = downloads.join("Mod D.zip");
+        let file = std::fs::File::create(&archive).expect("Failed setup test env");
+        let mut zip = zip::ZipWriter::new(file);
+        let options = zip::write::FileOptions::default();
+        for (name, data) in [
+            ("Data Files/d.esp", &b"abc"[..]),
+            ("Data Files/readme.txt", b"read me"),
+            ("Data Files/textures/d.dds", b"dds"),
+        ] {
+            zip.start_file(name, options)
+                .expect("Failed setup test env");
+            zip.write_all(data).expect("Failed setup test env");
+        }
+        zip.finish().expect("Failed setup test env");
+
+        // the author installed the data folder and changed files by hand
+        let mod_dir = library.join("Mod D");
+        std::fs::create_dir_all(mod_dir.join("textures")).expect("Failed setup test env");
+        std::fs::write(mod_dir.join("d.esp"), b"abcd").expect("Failed setup test env");
+        std::fs::write(mod_dir.join("textures/d.dds"), b"dds").expect("Failed setup test env");
+        std::fs::write(mod_dir.join("d.ini"), b"x=1").expect("Failed setup test env");
+        let lists = ProfileLists {
+            mods: vec![(mod_dir.to_owned(), true)],
+            plugins: vec![("d.esp".to_owned(), true)],
+        };
+        let archives = find_mod_archives(&[mod_dir.to_owned()], &downloads);
+        let modlist = Modlist::new("author", &lists, Some(&library), &archives, |_, _| true)
+            .expect("Failed to create modlist");
+        let m = &modlist.mods[0];
+        assert_eq!(m.installer.root.as_deref(), Some("Data Files"));
+        let patched: Vec<(&str, bool)> = m
+            .patches
+            .iter()
+            .map(|p| (p.path.as_str(), p.data.is_some()))
+            .collect();
+        assert_eq!(
+            patched,
+            vec![("d.esp", true), ("d.ini", true), ("readme.txt", false)]
+        );
+
+        // install on another machine reproduces the mod folder
+        let file = test_env.join("author.toml");
+        modlist.write(&file).expect("Failed to write modlist");
+        let mut manager =
+            Manager::new(Some(test_env.join("omwmm"))).expect("Failed to create manager");
+        let other_library = test_env.join("other");
+        manager.settings.mods_library = Some(other_library.to_owned());
+        manager.settings.downloads_library = Some(downloads.to_owned());
+        let result = manager
+            .install_modlist(&file, Some("installed"), false)
+            .expect("Failed to install modlist");
+        assert!(result.missing_archives.is_empty());
+        assert!(result.mismatches.is_empty());
+        let installed = other_library.join("Mod D");
+        for file in ["d.esp", "d.ini", "textures/d.dds"] {
+            assert_eq!(
+                std::fs::read(installed.join(file)).expect("Missing installed file"),
+                std::fs::read(mod_dir.join(file)).expect("Missing mod file")
+            );
+        }
+        assert!(!installed.join("readme.txt").exists());
+        assert!(!other_library.join(".Mod D.part").exists());
+        let lists = manager
+            .list_mods(Some("installed"), false)
+            .expect("Failed to list profile");
+        assert!(lists.mods.contains(&(installed.to_owned(), true)));
+
+        // installed mods are only replaced on request
+        assert_eq!(modlist.get_installed_mods(&other_library), vec!["Mod D"]);
+        std::fs::write(installed.join("d.esp"), b"changed").expect("Failed setup test env");
+        let result = manager
+            .install_modlist(&file, Some("kept"), false)
+            .expect("Failed to install modlist");
+        assert!(result.mismatches.is_empty());
+        assert_eq!(
+            std::fs::read(installed.join("d.esp")).expect("Missing installed file"),
+            b"changed"
+        );
+        manager
+            .install_modlist(&file, Some("replaced"), true)
+            .expect("Failed to install modlist");
+        assert_eq!(
+            std::fs::read(installed.join("d.esp")).expect("Missing installed file"),
+            b"abcd"
+        );
+
+        // names that leave the mods library are rejected
+        let mut evil = modlist.clone();
+        evil.mods[0].name = "../outside".to_owned();
+        let mut empty = modlist.clone();
+        empty.mods[0].name = String::new();
+        for modlist in [evil, empty] {
+            let result = modlist
+                .install(&other_library, &downloads, &[], true, |_, _| true)
+                .expect("Failed to install modlist");
+            assert_eq!(result.mismatches.len(), 1);
+            assert!(result.lists.mods.is_empty());
+        }
+        assert!(!test_env.join("outside").exists());
+        assert!(installed.join("d.esp").exists());
+
+        // archive names that leave the downloads library are rejected
+        let outside = test_env.join("outside.zip");
+        std::fs::copy(&archive, &outside).expect("Failed setup test env");
+        let mut evil = modlist.clone();
+        evil.mods[0].archive.as_mut().expect("No archive").file_name = "../outside.zip".to_owned();
+        let evil_file = test_env.join("evil.toml");
+        evil.write(&evil_file).expect("Failed to write modlist");
+        assert!(Modlist::read(&evil_file).is_err());
+        assert!(manager
+            .install_modlist(&evil_file, Some("evil"), false)
+            .is_none());
+        let result = evil
+            .install(&test_env.join("evil"), &downloads, &[], false, |_, _| true)
+            .expect("Failed to install modlist");
+        assert_eq!(result.mismatches.len(), 1);
+        assert!(result.missing_archives.is_empty());
+        assert!(evil.get_installed_mods(&other_library).is_empty());
+        assert!(!test_env.join("evil").join("Mod D").exists());
+        std::fs::remove_file(&outside).expect("Failed setup test env");
+
+        // an update installs the folder of the archive that matches the installed mod
+        update_mod(&archive, &installed, |_, _| true).expect("Failed to update mod");
+        assert_eq!(
+            std::fs::read(installed.join("d.esp")).expect("Missing updated file"),
+            b"abc"
+        );
+        assert!(installed.join("textures/d.dds").exists());
+        assert!(!installed.join("Data Files").exists());
+        assert!(!other_library.join(".Mod D.part").exists());
+
+        // a different archive with the same name is not installed
+        std::fs::write(&archive, b"abc").expect("Failed setup test env");
+        let result = modlist
+            .install(&test_env.join("third"), &downloads, &[], false, |_, _| true)
+            .expect("Failed to install modlist");
+        assert_eq!(result.mismatches.len(), 1);
+        assert!(!test_env.join("third").join("Mod D").exists());
+
+        // a kept mod that differs from the modlist is reported
+        let result = modlist
+            .install(&other_library, &downloads, &[], false, |_, _| true)
+            .expect("Failed to install modlist");
+        assert_eq!(result.mismatches.len(), 1);
+        assert!(installed.join("d.esp").exists());
+
+        // destroy test environment
+        std::fs::remove_dir_all(test_env).expect("Failed destroy test env");
+    }
+
+    #[test]
+    fn test_trash() {
+        // setup test environment
+        let library = Path::new("tests/integration/trash");
+        for name in ["Mod A", "Mod B"] {
+            std::fs::create_dir_all(library.join(name).join("Textures"))
+                .expect("Failed setup test env: folders");
+            std::fs::write(library.join(name).join("Mod.esp"), name)
+                .expect("Failed setup test env");
+        }
+        let meta = common::meta::ModNexusMetaData {
+            mod_id: Some(123),
+            ..Default::default()
+        };
+
+        // uninstalled mods keep their files and metadata
+        let trashed_a =
+            move_to_trash(library, &library.join("Mod A"), Some(&meta)).expect("Failed to trash");
+        assert!(!library.join("Mod A").exists());
+        assert!(trashed_a.join("Mod.esp").exists());
+        assert!(trashed_a.starts_with(get_trash_dir(library)));
+        move_to_trash(library, &library.join("Mod B"), None).expect("Failed to trash");
+        std::fs::create_dir_all(library.join("Mod B")).expect("Failed setup test env: folders");
+
+        let trashed = get_trashed_mods(library);
+        assert_eq!(trashed.len(), 2);
+        let mod_a = trashed
+            .iter()
+            .find(|t| t.name == "Mod A")
+            .expect("No Mod A");
+        let mod_b = trashed
+            .iter()
+            .find(|t| t.name == "Mod B")
+            .expect("No Mod B");
+        assert_eq!(mod_a.meta_data.as_ref().and_then(|m| m.mod_id), Some(123));
+        assert_eq!(mod_b.meta_data, None);
+
+        // a mod with the same name is installed
+        assert!(restore_from_trash(library, mod_b).is_err());
+        assert!(mod_b.path.exists());
+
+        let restored = restore_from_trash(library, mod_a).expect("Failed to restore");
+        assert_eq!(restored, library.join("Mod A"));
+        assert!(restored.join("Mod.esp").exists());
+        assert!(!get_meta_path(&mod_a.path).exists());
+        assert!(!mod_a.path.parent().expect("No entry").exists());
+
+        delete_from_trash(mod_b).expect("Failed to delete");
+        assert!(get_trashed_mods(library).is_empty());
+
+        // mods on another drive are copied with their subfolders
+        let nested = library.join("Mod A").join("Meshes").join("x");
+        std::fs::create_dir_all(&nested).expect("Failed setup test env: folders");
+        std::fs::write(nested.join("a.nif"), b"nif").expect("Failed setup test env");
+        let copied = library.join("Mod A copy");
+        copy_and_remove_dir(&library.join("Mod A"), &copied).expect("Failed to move");
+        assert!(!library.join("Mod A").exists());
+        assert!(copied.join("Mod.esp").exists());
+        assert_eq!(
+            std::fs::read(copied.join("Meshes/x/a.nif")).expect("Missing nested file"),
+            b"nif"
+        );
+        // a failed copy keeps the mod
+        std::fs::write(library.join("blocked"), b"").expect("Failed setup test env");
+        assert!(copy_and_remove_dir(&copied, &library.join("blocked")).is_err());
+        assert!(copied.join("Meshes/x/a.nif").exists());
+        std::fs::rename(&copied, library.join("Mod A")).expect("Failed setup test env");
+
+        move_to_trash(library, &library.join("Mod A"), None).expect("Failed to trash");
+        empty_trash(library).expect("Failed to empty trash");
+        assert!(!get_trash_dir(library).exists());
+
+        // destroy test environment
+        std::fs::remove_dir_all(library).expect("Failed destroy test env");
+    }
+
+    #[test]
+    fn test_diff() {
+        let get_kinds = |old: &[&str], new: &[&str]| -> Vec<(String, EDiffKind)> {
+            diff_lists(old, new)
+                .into_iter()
+                .map(|e| (e.entry.to_owned(), e.kind))
+                .collect()
+        };
+        let entry = |name: &str, kind: EDiffKind| (name.to_owned(), kind);
+
+        assert!(get_kinds(&[], &[]).is_empty());
+        assert_eq!(
+            get_kinds(&["a", "b"], &["a", "b"]),
+            vec![
+                entry("a", EDiffKind::Unchanged),
+                entry("b", EDiffKind::Unchanged)
+            ]
+        );
+        // removed entries are listed after the entry that preceded them
+        assert_eq!(
+            get_kinds(&["a", "b", "c"], &["a", "x", "c"]),
+            vec![
+                entry("a", EDiffKind::Unchanged),
+                entry("b", EDiffKind::Removed),
+                entry("x", EDiffKind::Added),
+                entry("c", EDiffKind::Unchanged)
+            ]
+        );
+        // only the entries out of the common order are moved
+        assert_eq!(
+            get_kinds(&["a", "b", "c", "d"], &["b", "c", "d", "a"]),
+            vec![
+                entry("b", EDiffKind::Unchanged),
+                entry("c", EDiffKind::Unchanged),
+                entry("d", EDiffKind::Unchanged),
+                entry("a", EDiffKind::Moved { from: 0, to: 3 })
+            ]
+        );
+
+        // a cfg on disk
+        let test_env = Path::new("tests/integration/diff");
+        std::fs::create_dir_all(test_env).expect("Failed setup test env: folders");
+        let cfg_path = test_env.join(OPENMW_CFG);
+        std::fs::write(
+            &cfg_path,
+            "data=\"Data Files\"\ndata=\"Mod A\"\ncontent=Morrowind.esm\ncontent=a.esp\n",
+        )
+        .expect("Failed setup test env: cfg");
+        let diff = CfgDiff::from_cfg(
+            &cfg_path,
+            &[PathBuf::from("Mod A"), PathBuf::from("Data Files")],
+            &["Morrowind.esm".to_owned()],
+        );
+        assert!(!diff.is_empty());
+        assert_eq!(diff.data[1].kind, EDiffKind::Moved { from: 0, to: 1 });
+        assert_eq!(diff.content[1].kind, EDiffKind::Removed);
+        let diff = CfgDiff::from_cfg(
+            &cfg_path,
+            &[PathBuf::from("Data Files"), PathBuf::from("Mod A")],
+            &["Morrowind.esm".to_owned(), "a.esp".to_owned()],
+        );
+        assert!(diff.is_empty());
+
+        // destroy test environment
+        std::fs::remove_dir_all(test_env).expect("Failed destroy test env");
+    }
 }
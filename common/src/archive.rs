@@ -0,0 +1,193 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, Read},
+    path::{Component, Path, PathBuf},
+};
+
+use log::warn;
+use sevenz_rust::{Password, SevenZReader};
+use sha2::{Digest, Sha256};
+
+/// Archive extensions that can be extracted
+pub const SUPPORTED_ARCHIVES: [&str; 2] = ["zip", "7z"];
+
+/// Checks if an archive can be extracted by its extension
+pub fn is_supported_archive(path: &Path) -> bool {
+    if let Some(ext) = path.extension() {
+        return SUPPORTED_ARCHIVES
+            .iter()
+            .any(|supported| ext.eq_ignore_ascii_case(supported));
+    }
+    false
+}
+
+/// Extracts a zip or 7z archive into a folder.
+/// on_progress is called with the number of extracted and total entries, return false to cancel.
+pub fn extract_archive<F>(archive: &Path, destination: &Path, mut on_progress: F) -> io::Result<()>
+where
+    F: FnMut(usize, usize) -> bool,
+{
+    let ext = archive
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "zip" => extract_zip(archive, destination, &mut on_progress),
+        "7z" => extract_7z(archive, destination, &mut on_progress),
+        _ => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("unsupported archive {}", archive.display()),
+        )),
+    }
+}
+
+/// the hidden folder an archive is extracted to before it replaces the mod folder: mods/.name.part
+pub fn get_part_path(install_path: &Path) -> Option<PathBuf> {
+    let name = install_path.file_name()?;
+    Some(install_path.with_file_name(format!(".{}.part", name.to_string_lossy())))
+}
+
+/// Extracts an archive into a mod folder, an existing folder is replaced.
+/// The files are extracted next to the folder first so a failed or cancelled install leaves it untouched.
+pub fn install_archive<F>(archive: &Path, install_path: &Path, on_progress: F) -> io::Result<()>
+where
+    F: FnMut(usize, usize) -> bool,
+{
+    let Some(part_path) = get_part_path(install_path) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "invalid mod name",
+        ));
+    };
+    let result = extract_archive(archive, &part_path, on_progress).and_then(|_| {
+        if install_path.exists() {
+            fs::remove_dir_all(install_path)?;
+        }
+        fs::rename(&part_path, install_path)
+    });
+    if result.is_err() && part_path.exists() {
+        if let Err(err) = fs::remove_dir_all(&part_path) {
+            warn!("Could not clean up {}: {}", part_path.display(), err);
+        }
+    }
+    result
+}
+
+fn extract_zip<F>(archive: &Path, destination: &Path, on_progress: &mut F) -> io::Result<()>
+where
+    F: FnMut(usize, usize) -> bool,
+{
+    let mut zip = zip::ZipArchive::new(BufReader::new(File::open(archive)?))?;
+    let total = zip.len();
+    for i in 0..total {
+        if !on_progress(i, total) {
+            return Err(cancelled());
+        }
+        let mut entry = zip.by_index(i)?;
+        // skip entries that would be written outside of the destination
+        let Some(relative) = entry.enclosed_name().map(|p| p.to_owned()) else { continue };
+        write_entry(&destination.join(relative), entry.is_dir(), &mut entry)?;
+    }
+    on_progress(total, total);
+    Ok(())
+}
+
+fn extract_7z<F>(archive: &Path, destination: &Path, on_progress: &mut F) -> io::Result<()>
+where
+    F: FnMut(usize, usize) -> bool,
+{
+    let mut reader = SevenZReader::open(archive, Password::empty()).map_err(to_io_error)?;
+    let total = reader.archive().files.len();
+    let mut count = 0;
+    let mut is_cancelled = false;
+    let mut write_error: Option<io::Error> = None;
+    reader
+        .for_each_entries(|entry, data| {
+            if !on_progress(count, total) {
+                is_cancelled = true;
+                return Ok(false);
+            }
+            count += 1;
+            let Some(relative) = get_enclosed_path(entry.name()) else {
+                // the entries of a solid archive are one stream, a skipped entry still has to be read
+                io::copy(data, &mut io::sink())?;
+                return Ok(true);
+            };
+            match write_entry(&destination.join(relative), entry.is_directory(), data) {
+                Ok(_) => Ok(true),
+                Err(err) => {
+                    write_error = Some(err);
+                    Ok(false)
+                }
+            }
+        })
+        .map_err(to_io_error)?;
+
+    if is_cancelled {
+        return Err(cancelled());
+    }
+    if let Some(err) = write_error {
+        return Err(err);
+    }
+    on_progress(total, total);
+    Ok(())
+}
+
+/// Creates a folder or writes a file and its parent folders
+fn write_entry(path: &Path, is_dir: bool, data: &mut dyn Read) -> io::Result<()> {
+    if is_dir {
+        return fs::create_dir_all(path);
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = File::create(path)?;
+    io::copy(data, &mut file)?;
+    Ok(())
+}
+
+/// Returns a relative archive path only if it stays inside the destination
+pub fn get_enclosed_path(name: &str) -> Option<PathBuf> {
+    let path = PathBuf::from(name.replace('\\', "/"));
+    if path
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// Computes the lowercase hex sha256 of a file.
+/// on_progress is called with the number of read and total bytes, return false to cancel.
+pub fn hash_file<F>(path: &Path, mut on_progress: F) -> io::Result<String>
+where
+    F: FnMut(u64, u64) -> bool,
+{
+    let mut file = File::open(path)?;
+    let total = file.metadata()?.len();
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0_u8; 1 << 20];
+    let mut read = 0;
+    loop {
+        if !on_progress(read, total) {
+            return Err(cancelled());
+        }
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+        read += n as u64;
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn cancelled() -> io::Error {
+    io::Error::new(io::ErrorKind::Interrupted, "cancelled")
+}
+
+fn to_io_error(err: sevenz_rust::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
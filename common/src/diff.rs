@@ -0,0 +1,116 @@
+use std::path::{Path, PathBuf};
+
+use crate::{parse_cfg, profile::get_external_entries};
+
+/// How an entry of a list changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EDiffKind {
+    Unchanged,
+    Added,
+    Removed,
+    /// the index in the old and in the new list
+    Moved {
+        from: usize,
+        to: usize,
+    },
+}
+
+/// An entry of a list and how it changed
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffEntry<T> {
+    pub entry: T,
+    pub kind: EDiffKind,
+}
+
+/// The changes of the data= and content= entries of a cfg
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CfgDiff {
+    pub data: Vec<DiffEntry<PathBuf>>,
+    pub content: Vec<DiffEntry<String>>,
+}
+
+impl CfgDiff {
+    /// compares the entries of two cfgs, in load order
+    pub fn new(
+        old_data: &[PathBuf],
+        old_content: &[String],
+        data: &[PathBuf],
+        content: &[String],
+    ) -> Self {
+        Self {
+            data: diff_lists(old_data, data),
+            content: diff_lists(old_content, content),
+        }
+    }
+
+    /// compares the entries of a cfg on disk with the entries it gets
+    /// a missing cfg has no entries
+    pub fn from_cfg(cfg_path: &Path, data: &[PathBuf], content: &[String]) -> Self {
+        let (old_data, old_content) = match parse_cfg(cfg_path.to_path_buf()) {
+            Some(info) => (info.data, info.plugins),
+            None => (vec![], vec![]),
+        };
+        Self::new(&old_data, &old_content, data, content)
+    }
+
+    /// if no entry changed
+    pub fn is_empty(&self) -> bool {
+        self.data.iter().all(|e| e.kind == EDiffKind::Unchanged)
+            && self.content.iter().all(|e| e.kind == EDiffKind::Unchanged)
+    }
+}
+
+/// the entries of the new list in order, with the removed entries after the entry that preceded them
+/// entries of both lists that are not in their longest common subsequence are moved
+pub fn diff_lists<T: PartialEq + Clone>(old: &[T], new: &[T]) -> Vec<DiffEntry<T>> {
+    // lengths of the longest common subsequences of the list ends
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let mut is_kept = vec![false; new.len()];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            is_kept[j] = true;
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    let mut entries: Vec<DiffEntry<T>> = new
+        .iter()
+        .enumerate()
+        .map(|(to, entry)| {
+            let kind = match old.iter().position(|e| e == entry) {
+                None => EDiffKind::Added,
+                Some(_) if is_kept[to] => EDiffKind::Unchanged,
+                Some(from) => EDiffKind::Moved { from, to },
+            };
+            DiffEntry {
+                entry: entry.clone(),
+                kind,
+            }
+        })
+        .collect();
+    for (i, entry) in get_external_entries(new, old.to_vec()) {
+        entries.insert(
+            i,
+            DiffEntry {
+                entry,
+                kind: EDiffKind::Removed,
+            },
+        );
+    }
+    entries
+}
@@ -0,0 +1,197 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::read_lines;
+
+/// MO2 marks separators in the mod list with this suffix
+const MO2_SEPARATOR_SUFFIX: &str = "_separator";
+/// MO2 replaces this in the folders of ModOrganizer.ini with the base directory
+const MO2_BASE_DIR: &str = "%BASE_DIR%";
+
+/// A Mod Organizer 2 profile in load order
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Mo2Profile {
+    pub name: String,
+    /// mod folder names and if they are enabled, the first mod loads first
+    pub mods: Vec<(String, bool)>,
+    /// plugin names and if they are enabled, in load order
+    pub plugins: Vec<(String, bool)>,
+}
+
+/// A Mod Organizer 2 instance
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Mo2Instance {
+    /// the folder that contains one folder per mod
+    pub mods_dir: PathBuf,
+    pub profiles: Vec<Mo2Profile>,
+}
+
+/// Reads the mods and profiles of an MO2 instance folder
+/// the folders can be moved in the settings of the instance, they are read from its ModOrganizer.ini
+pub fn read_mo2_instance(instance_dir: &Path) -> io::Result<Mo2Instance> {
+    let settings = read_mo2_settings(&instance_dir.join("ModOrganizer.ini"));
+    let get_setting = |key: &str| settings.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+    let base_dir = match get_setting("base_directory") {
+        Some(dir) => instance_dir.join(dir),
+        None => instance_dir.to_path_buf(),
+    };
+    let get_dir = |key: &str, default: &str| match get_setting(key) {
+        Some(dir) => match dir.strip_prefix(MO2_BASE_DIR) {
+            Some(relative) => base_dir.join(relative.trim_start_matches(['/', '\\'])),
+            None => base_dir.join(dir),
+        },
+        None => base_dir.join(default),
+    };
+    let mods_dir = get_dir("mod_directory", "mods");
+    let profiles_dir = get_dir("profiles_directory", "profiles");
+    if !mods_dir.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} is not an MO2 instance", instance_dir.display()),
+        ));
+    }
+
+    let mut profiles = vec![];
+    for entry in fs::read_dir(profiles_dir)?.flatten() {
+        let profile_dir = entry.path();
+        if !profile_dir.is_dir() {
+            continue;
+        }
+        profiles.push(Mo2Profile {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            mods: read_mo2_modlist(&profile_dir.join("modlist.txt")),
+            plugins: read_mo2_plugins(
+                &profile_dir.join("plugins.txt"),
+                &profile_dir.join("loadorder.txt"),
+            ),
+        });
+    }
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(Mo2Instance { mods_dir, profiles })
+}
+
+/// Reads an MO2 modlist.txt in load order.
+/// MO2 lists the highest priority first, separators and unmanaged (*) entries are skipped.
+pub fn read_mo2_modlist(path: &Path) -> Vec<(String, bool)> {
+    let mut mods = vec![];
+    if let Ok(lines) = read_lines(path) {
+        for line in lines.map_while(Result::ok) {
+            let line = line.trim();
+            let (name, enabled) = if let Some(name) = line.strip_prefix('+') {
+                (name, true)
+            } else if let Some(name) = line.strip_prefix('-') {
+                (name, false)
+            } else {
+                continue;
+            };
+            if name.ends_with(MO2_SEPARATOR_SUFFIX) {
+                continue;
+            }
+            mods.push((name.to_owned(), enabled));
+        }
+    }
+    mods.reverse();
+    mods
+}
+
+/// Reads the MO2 plugin order.
+/// loadorder.txt lists all plugins in order. plugins.txt lists the active plugins, either all of them or
+/// all plugins with a * prefix for the active ones.
+pub fn read_mo2_plugins(plugins_path: &Path, loadorder_path: &Path) -> Vec<(String, bool)> {
+    let active_lines = read_list(plugins_path);
+    let has_prefix = active_lines.iter().any(|l| l.starts_with('*'));
+    let mut active: Vec<(String, bool)> = active_lines
+        .iter()
+        .map(|l| match l.strip_prefix('*') {
+            Some(name) => (name.to_owned(), true),
+            None => (l.to_owned(), !has_prefix),
+        })
+        .collect();
+
+    let mut plugins: Vec<(String, bool)> = vec![];
+    for name in read_list(loadorder_path) {
+        let enabled = active
+            .iter()
+            .any(|(p, enabled)| *enabled && p.eq_ignore_ascii_case(&name));
+        plugins.push((name, enabled));
+    }
+    // active plugins that are missing in the load order are loaded last
+    active.retain(|(p, _)| !plugins.iter().any(|(n, _)| n.eq_ignore_ascii_case(p)));
+    plugins.extend(active);
+    plugins
+}
+
+/// Reads the keys and values of the [Settings] section of a ModOrganizer.ini
+/// paths with special characters are written as @ByteArray(path)
+fn read_mo2_settings(path: &Path) -> Vec<(String, String)> {
+    let mut settings = vec![];
+    let mut is_settings = false;
+    let Ok(lines) = read_lines(path) else {
+        return settings;
+    };
+    for line in lines.map_while(Result::ok) {
+        let line = line.trim();
+        if line.starts_with('[') {
+            is_settings = line.eq_ignore_ascii_case("[Settings]");
+            continue;
+        }
+        if !is_settings {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else { continue };
+        let value = value.trim();
+        let value = value
+            .strip_prefix("@ByteArray(")
+            .and_then(|v| v.strip_suffix(')'))
+            .unwrap_or(value);
+        if !value.is_empty() {
+            settings.push((key.trim().to_owned(), value.to_owned()));
+        }
+    }
+    settings
+}
+
+/// Reads the active plugins of a Morrowind.ini (as managed by Wrye Mash) in load order
+pub fn read_morrowind_ini_plugins(path: &Path) -> io::Result<Vec<String>> {
+    let mut game_files: Vec<(usize, String)> = vec![];
+    let mut is_game_files = false;
+    for line in read_lines(path)?.map_while(Result::ok) {
+        let line = line.trim();
+        if line.starts_with('[') {
+            is_game_files = line.eq_ignore_ascii_case("[Game Files]");
+            continue;
+        }
+        if !is_game_files {
+            continue;
+        }
+        // GameFile0=Morrowind.esm
+        let Some((key, value)) = line.split_once('=') else { continue };
+        let Some(index) = key
+            .trim()
+            .strip_prefix("GameFile")
+            .and_then(|i| i.parse().ok())
+        else {
+            continue;
+        };
+        game_files.push((index, value.trim().to_owned()));
+    }
+    game_files.sort_by_key(|(i, _)| *i);
+    Ok(game_files.into_iter().map(|(_, name)| name).collect())
+}
+
+/// non-empty lines without comments
+fn read_list(path: &Path) -> Vec<String> {
+    let mut entries = vec![];
+    if let Ok(lines) = read_lines(path) {
+        for line in lines.map_while(Result::ok) {
+            let line = line.trim();
+            if !line.is_empty() && !line.starts_with('#') {
+                entries.push(line.to_owned());
+            }
+        }
+    }
+    entries
+}
@@ -1,10 +1,21 @@
 use std::{
+    collections::HashMap,
     fs::{self, File},
     io::{self, BufRead},
     path::{Path, PathBuf},
 };
 
-use log::{error, info};
+use log::{error, info, warn};
+
+pub mod archive;
+pub mod diff;
+pub mod import;
+pub mod meta;
+pub mod modlist;
+pub mod profile;
+pub mod tes3;
+pub mod trash;
+pub mod vfs;
 
 pub struct ConfigInfo {
     pub data: Vec<PathBuf>,
@@ -94,6 +105,50 @@ where
     results
 }
 
+/// Get all bsa archives in a folder
+pub fn get_archives_in_folder<P>(path: &P) -> Vec<PathBuf>
+where
+    P: AsRef<Path>,
+{
+    let mut results: Vec<PathBuf> = vec![];
+    if let Ok(archives) = fs::read_dir(path) {
+        for file in archives.flatten() {
+            let file_path = file.path();
+            if file_path.is_file() {
+                if let Some(ext_os) = file_path.extension() {
+                    if ext_os.eq_ignore_ascii_case("bsa") {
+                        results.push(file_path);
+                    }
+                }
+            }
+        }
+    }
+    results
+}
+
+/// Resolves content names to plugin paths in load order.
+/// Names are matched case-insensitively and later data directories win.
+pub fn resolve_plugins(data_dirs: &[PathBuf], plugin_names: &[String]) -> Vec<PathBuf> {
+    let mut paths: HashMap<String, PathBuf> = HashMap::new();
+    for data_dir in data_dirs {
+        for plugin in get_plugins_in_folder(data_dir, true) {
+            if let Some(file_name) = plugin.file_name() {
+                paths.insert(file_name.to_string_lossy().to_lowercase(), plugin);
+            }
+        }
+    }
+
+    let mut results: Vec<PathBuf> = vec![];
+    for name in plugin_names {
+        if let Some(path) = paths.get(&name.to_lowercase()) {
+            results.push(path.to_owned());
+        } else {
+            warn!("Plugin {} not found in the data directories", name);
+        }
+    }
+    results
+}
+
 /// Parses the omwcfg and returns the data directories and content files
 pub fn parse_cfg(cfg_path: PathBuf) -> Option<ConfigInfo> {
     let mut data_dirs: Vec<PathBuf> = vec![];
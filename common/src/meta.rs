@@ -0,0 +1,220 @@
+use std::{
+    cmp::Ordering,
+    io,
+    path::{Path, PathBuf},
+};
+
+use ini::{EscapePolicy, Ini, LineSeparator, ParseOption, Properties, WriteOption};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+/// MO2 keeps all metadata in this section
+const GENERAL_SECTION: &str = "General";
+
+/// Nexus metadata of a mod archive (MO2 `.meta` next to a download) or an installed mod (MO2 `meta.ini`)
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModNexusMetaData {
+    pub game_name: Option<String>,
+    pub mod_id: Option<usize>,
+    pub file_id: Option<usize>,
+    pub url: Option<String>,
+    /// the name of the download
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub mod_name: Option<String>,
+    pub version: Option<String>,
+    pub newest_version: Option<String>,
+    /// the archive a mod was installed from
+    pub installation_file: Option<String>,
+    pub installed: Option<bool>,
+    pub uninstalled: Option<bool>,
+    pub nexus_file_status: Option<usize>,
+}
+
+impl ModNexusMetaData {
+    /// reads the [General] section, keys are compared case-insensitively
+    fn from_properties(properties: &Properties) -> Self {
+        let get = |key: &str| -> Option<String> {
+            properties
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v.to_owned())
+                .filter(|v| !v.is_empty())
+        };
+        let get_number = |key: &str| get(key).and_then(|v| v.parse().ok());
+        let get_bool = |key: &str| get(key).and_then(|v| v.to_lowercase().parse().ok());
+
+        Self {
+            game_name: get("gameName"),
+            mod_id: get_number("modID"),
+            file_id: get_number("fileID"),
+            url: get("url"),
+            name: get("name"),
+            description: get("description"),
+            mod_name: get("modName"),
+            version: get("version"),
+            newest_version: get("newestVersion"),
+            installation_file: get("installationFile"),
+            installed: get_bool("installed"),
+            uninstalled: get_bool("uninstalled"),
+            nexus_file_status: get_number("nexusFileStatus"),
+        }
+    }
+
+    /// all set values with their MO2 keys
+    fn to_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = vec![];
+        let mut push = |key: &'static str, value: Option<String>| {
+            if let Some(value) = value {
+                pairs.push((key, value));
+            }
+        };
+        push("gameName", self.game_name.clone());
+        push("modID", self.mod_id.map(|v| v.to_string()));
+        push("fileID", self.file_id.map(|v| v.to_string()));
+        push("url", self.url.clone());
+        push("name", self.name.clone());
+        push("description", self.description.clone());
+        push("modName", self.mod_name.clone());
+        push("version", self.version.clone());
+        push("newestVersion", self.newest_version.clone());
+        push("installationFile", self.installation_file.clone());
+        push("installed", self.installed.map(|v| v.to_string()));
+        push("uninstalled", self.uninstalled.map(|v| v.to_string()));
+        push(
+            "nexusFileStatus",
+            self.nexus_file_status.map(|v| v.to_string()),
+        );
+        pairs
+    }
+}
+
+/// MO2 keeps the metadata of a download in a file next to it: mod.7z -> mod.7z.meta
+pub fn get_meta_path(archive: &Path) -> PathBuf {
+    let mut meta_path = archive.as_os_str().to_owned();
+    meta_path.push(".meta");
+    PathBuf::from(meta_path)
+}
+
+/// Parses an MO2 meta file, quoted and escaped values are supported
+pub fn read_meta<P>(path: P) -> io::Result<ModNexusMetaData>
+where
+    P: AsRef<Path>,
+{
+    let ini = Ini::load_from_file_opt(path, ParseOption::default())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+    Ok(ini
+        .section(Some(GENERAL_SECTION))
+        .map(ModNexusMetaData::from_properties)
+        .unwrap_or_default())
+}
+
+/// Writes an MO2 meta file.
+/// An existing file is updated so keys and sections omwmm does not know are kept.
+pub fn write_meta<P>(path: P, meta: &ModNexusMetaData) -> io::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let mut ini = if path.exists() {
+        Ini::load_from_file_opt(path, ParseOption::default())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?
+    } else {
+        Ini::new()
+    };
+
+    let section = ini
+        .entry(Some(GENERAL_SECTION.to_owned()))
+        .or_insert_with(Properties::new);
+    for (key, value) in meta.to_pairs() {
+        // replace keys with any casing
+        let existing: Vec<String> = section
+            .iter()
+            .filter(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(k, _)| k.to_owned())
+            .collect();
+        for k in existing {
+            section.remove_all(k).for_each(drop);
+        }
+        section.insert(key, value);
+    }
+
+    ini.write_to_file_opt(
+        path,
+        WriteOption {
+            // MO2 (QSettings) reads backslash escapes but not escaped separators
+            escape_policy: EscapePolicy::Basics,
+            line_separator: LineSeparator::SystemDefault,
+            kv_separator: "=",
+        },
+    )
+}
+
+/// marks an archive as installed or uninstalled in its MO2 .meta file and returns the metadata of the mod
+/// the .meta file is created if the archive has none
+pub fn update_archive_meta(archive: &Path, installed: bool) -> ModNexusMetaData {
+    let meta_path = get_meta_path(archive);
+    let mut meta = if meta_path.exists() {
+        read_meta(&meta_path).unwrap_or_else(|err| {
+            warn!("Could not read meta file {}: {}", meta_path.display(), err);
+            ModNexusMetaData::default()
+        })
+    } else {
+        ModNexusMetaData::default()
+    };
+
+    let file_name = archive
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned());
+    if meta.game_name.is_none() {
+        meta.game_name = Some("morrowind".to_owned());
+    }
+    if meta.name.is_none() {
+        meta.name = file_name.clone();
+    }
+    meta.installed = Some(installed);
+    meta.uninstalled = Some(!installed);
+    if let Err(err) = write_meta(&meta_path, &meta) {
+        warn!("Could not write meta file {}: {}", meta_path.display(), err);
+    }
+
+    meta.installation_file = file_name;
+    meta
+}
+
+/// Compares two version strings part by part, numeric parts are compared as numbers: 1.10 > 1.9
+/// the digits and letters of a part are separate parts: 10 > 2beta
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let get_parts = |version: &str| -> Vec<String> {
+        let mut parts: Vec<String> = vec![];
+        let mut last_is_digit: Option<bool> = None;
+        for c in version.trim().trim_start_matches(['v', 'V']).chars() {
+            if !c.is_ascii_alphanumeric() {
+                last_is_digit = None;
+                continue;
+            }
+            let is_digit = c.is_ascii_digit();
+            match parts.last_mut() {
+                Some(part) if last_is_digit == Some(is_digit) => part.push(c.to_ascii_lowercase()),
+                _ => parts.push(c.to_ascii_lowercase().to_string()),
+            }
+            last_is_digit = Some(is_digit);
+        }
+        parts
+    };
+    let (a, b) = (get_parts(a), get_parts(b));
+    for i in 0..a.len().max(b.len()) {
+        // missing parts count as 0: 1.0 == 1.0.0
+        let x = a.get(i).map_or("0", |p| p.as_str());
+        let y = b.get(i).map_or("0", |p| p.as_str());
+        let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            _ => x.cmp(y),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
@@ -0,0 +1,711 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    archive::{extract_archive, get_enclosed_path, get_part_path, hash_file},
+    get_openmwcfg,
+    meta::{get_meta_path, read_meta, update_archive_meta},
+    parse_cfg,
+    profile::{is_base_game_dir, ProfileLists},
+};
+
+/// A profile that can be shared: the mods in load order with the archives they are installed from and the plugin order
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Modlist {
+    pub name: String,
+    pub mods: Vec<ModlistMod>,
+    pub plugins: Vec<ModlistPlugin>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModlistMod {
+    /// the folder name in the mods library
+    pub name: String,
+    pub enabled: bool,
+    /// base game data is taken from the machine the modlist is imported on
+    pub base_game: bool,
+    /// data directories outside of the mods library keep their path
+    pub path: Option<PathBuf>,
+    /// the archive the mod is installed from, None for mods that were added by hand
+    pub archive: Option<ModlistArchive>,
+    #[serde(skip_serializing_if = "InstallerOptions::is_default")]
+    pub installer: InstallerOptions,
+    /// changes to the files of the archive
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub patches: Vec<FilePatch>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModlistArchive {
+    pub file_name: String,
+    pub size: u64,
+    pub sha256: Option<String>,
+    pub game: Option<String>,
+    pub mod_id: Option<usize>,
+    pub file_id: Option<usize>,
+    pub version: Option<String>,
+}
+
+impl ModlistArchive {
+    /// the nexus page to download the archive from
+    pub fn get_url(&self) -> Option<String> {
+        let mod_id = self.mod_id?;
+        let game = self.game.as_deref().unwrap_or("morrowind");
+        Some(format!(
+            "https://www.nexusmods.com/{}/mods/{}",
+            game, mod_id
+        ))
+    }
+}
+
+/// The choices made when a mod was installed from its archive
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InstallerOptions {
+    /// the folder of the archive that is installed, the whole archive if None
+    pub root: Option<String>,
+}
+
+impl InstallerOptions {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// A file of a mod that differs from its archive
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilePatch {
+    /// the path in the mod folder, separated by /
+    pub path: String,
+    /// the hex encoded content of the file, None if it is deleted
+    pub data: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModlistPlugin {
+    pub name: String,
+    pub enabled: bool,
+}
+
+/// A modlist reconstructed on this machine
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ModlistImport {
+    pub lists: ProfileLists,
+    /// archives of the modlist that are not in the downloads library
+    pub missing_archives: Vec<ModlistArchive>,
+    /// mods that are neither installed nor have an archive
+    pub missing_mods: Vec<String>,
+    /// what could not be installed as recorded
+    pub mismatches: Vec<String>,
+}
+
+impl Modlist {
+    /// Creates the modlist of a profile
+    /// archives maps mod folders to the archive they were installed from, the archives are hashed
+    /// and compared with the mod folders to record the installer options and changed files.
+    /// on_progress is called with the number of done and total mods, return false to cancel.
+    pub fn new<F>(
+        name: &str,
+        lists: &ProfileLists,
+        mods_library: Option<&Path>,
+        archives: &HashMap<PathBuf, PathBuf>,
+        mut on_progress: F,
+    ) -> io::Result<Self>
+    where
+        F: FnMut(usize, usize) -> bool,
+    {
+        let mut mods = vec![];
+        for (i, (path, enabled)) in lists.mods.iter().enumerate() {
+            if !on_progress(i, lists.mods.len()) {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+            }
+            let is_managed = mods_library.is_some_and(|library| path.starts_with(library));
+            let base_game = !is_managed && is_base_game_dir(path);
+            let mut installer = InstallerOptions::default();
+            let mut patches = vec![];
+            let archive = match archives.get(path) {
+                Some(archive) => {
+                    match get_install_diff(archive, path) {
+                        Ok(diff) => (installer, patches) = diff,
+                        Err(err) => warn!("Could not compare {}: {}", archive.display(), err),
+                    }
+                    Some(get_archive_info(archive)?)
+                }
+                None => None,
+            };
+            mods.push(ModlistMod {
+                name: path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                enabled: *enabled,
+                base_game,
+                path: (!is_managed && !base_game).then(|| path.to_owned()),
+                archive,
+                installer,
+                patches,
+            });
+        }
+        on_progress(lists.mods.len(), lists.mods.len());
+
+        let plugins = lists
+            .plugins
+            .iter()
+            .map(|(name, enabled)| ModlistPlugin {
+                name: name.to_owned(),
+                enabled: *enabled,
+            })
+            .collect();
+        Ok(Self {
+            name: name.to_owned(),
+            mods,
+            plugins,
+        })
+    }
+
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let toml = fs::read_to_string(path)?;
+        let modlist: Self =
+            toml::from_str(&toml).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        // archive names come from the modlist file and must not leave the downloads library
+        if let Some(archive) = modlist
+            .mods
+            .iter()
+            .filter_map(|m| m.archive.as_ref())
+            .find(|a| !is_valid_file_name(&a.file_name))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid archive file name {}", archive.file_name),
+            ));
+        }
+        Ok(modlist)
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let toml = toml::to_string_pretty(self)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        fs::write(path, toml)
+    }
+
+    /// Reconstructs the profile lists on this machine
+    /// mods are expected in the mods library by their name, base game data is replaced with base_game_dirs.
+    /// mods that are not installed yet keep their position in the list.
+    pub fn import(
+        &self,
+        mods_library: Option<&Path>,
+        downloads_library: Option<&Path>,
+        base_game_dirs: &[PathBuf],
+    ) -> ModlistImport {
+        let mut result = ModlistImport::default();
+        for m in self.mods.iter() {
+            if m.base_game {
+                for dir in base_game_dirs {
+                    if !result.lists.mods.iter().any(|(p, _)| p == dir) {
+                        result.lists.mods.push((dir.to_owned(), true));
+                    }
+                }
+                continue;
+            }
+
+            // the name comes from the modlist file and must not leave the mods library
+            if m.path.is_none() && !is_valid_mod_name(&m.name) {
+                warn!("Invalid mod name {}", m.name);
+                result
+                    .mismatches
+                    .push(format!("{}: invalid mod name", m.name));
+                continue;
+            }
+            let path = match (&m.path, mods_library) {
+                (Some(path), _) => Some(path.to_owned()),
+                (None, Some(library)) => Some(library.join(&m.name)),
+                (None, None) => None,
+            };
+            let is_installed = path.as_ref().is_some_and(|p| p.exists());
+            match &m.archive {
+                Some(archive) if !is_valid_file_name(&archive.file_name) => {
+                    warn!("Invalid archive file name {}", archive.file_name);
+                    result
+                        .mismatches
+                        .push(format!("{}: invalid archive file name", m.name));
+                }
+                Some(archive) => {
+                    let is_downloaded = downloads_library
+                        .is_some_and(|downloads| downloads.join(&archive.file_name).exists());
+                    if !is_downloaded {
+                        result.missing_archives.push(archive.clone());
+                    }
+                }
+                None if !is_installed => result.missing_mods.push(m.name.to_owned()),
+                None => {}
+            }
+            if let Some(path) = path {
+                result.lists.mods.push((path, m.enabled));
+            }
+        }
+        result.lists.plugins = self
+            .plugins
+            .iter()
+            .map(|p| (p.name.to_owned(), p.enabled))
+            .collect();
+        result
+    }
+
+    /// the mods of the modlist with an archive that are already in the mods library
+    pub fn get_installed_mods(&self, mods_library: &Path) -> Vec<String> {
+        self.mods
+            .iter()
+            .filter(|m| {
+                m.archive
+                    .as_ref()
+                    .is_some_and(|a| is_valid_file_name(&a.file_name))
+                    && is_valid_mod_name(&m.name)
+            })
+            .filter(|m| mods_library.join(&m.name).exists())
+            .map(|m| m.name.to_owned())
+            .collect()
+    }
+
+    /// Installs the mods of the modlist from the downloads library into the mods library
+    /// archives are verified against their size and hash, installed mods are only replaced if replace_installed is set
+    /// and are otherwise compared with the modlist.
+    /// on_progress is called with the number of done and total mods, return false to cancel.
+    pub fn install<F>(
+        &self,
+        mods_library: &Path,
+        downloads_library: &Path,
+        base_game_dirs: &[PathBuf],
+        replace_installed: bool,
+        mut on_progress: F,
+    ) -> io::Result<ModlistImport>
+    where
+        F: FnMut(usize, usize) -> bool,
+    {
+        let mut mismatches = vec![];
+        let total = self.mods.len();
+        for (i, m) in self.mods.iter().enumerate() {
+            if !on_progress(i, total) {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+            }
+            let Some(archive) = &m.archive else { continue };
+            if !is_valid_mod_name(&m.name) || !is_valid_file_name(&archive.file_name) {
+                // reported by import
+                continue;
+            }
+            let install_path = mods_library.join(&m.name);
+            if install_path.exists() && !replace_installed {
+                info!("Keeping installed {}", m.name);
+                mismatches.extend(compare_installed(
+                    &install_path,
+                    downloads_library,
+                    &m.name,
+                    archive,
+                )?);
+                continue;
+            }
+            let archive_path = downloads_library.join(&archive.file_name);
+            if !archive_path.exists() {
+                // reported as a missing archive
+                continue;
+            }
+
+            // the archive must be the one the modlist was made with
+            let size = fs::metadata(&archive_path)?.len();
+            if size != archive.size {
+                mismatches.push(format!(
+                    "{}: {} has {} bytes instead of {}",
+                    m.name, archive.file_name, size, archive.size
+                ));
+                continue;
+            }
+            if let Some(sha256) = &archive.sha256 {
+                let hash = hash_file(&archive_path, |_, _| true)?;
+                if hash != *sha256 {
+                    mismatches.push(format!(
+                        "{}: sha256 of {} is {} instead of {}",
+                        m.name, archive.file_name, hash, sha256
+                    ));
+                    continue;
+                }
+            }
+
+            info!("Installing {} from {}", m.name, archive.file_name);
+            match install_mod(&archive_path, &install_path, &m.installer, &m.patches) {
+                Ok(_) => {
+                    update_archive_meta(&archive_path, true);
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => return Err(err),
+                Err(err) => mismatches.push(format!("{}: {}", m.name, err)),
+            }
+        }
+        on_progress(total, total);
+
+        let mut result = self.import(Some(mods_library), Some(downloads_library), base_game_dirs);
+        result.mismatches.extend(mismatches);
+        Ok(result)
+    }
+}
+
+/// the differences of a kept mod to its archive in the modlist
+/// the mod is compared with the archive of the downloads library it was installed from
+fn compare_installed(
+    install_path: &Path,
+    downloads_library: &Path,
+    name: &str,
+    archive: &ModlistArchive,
+) -> io::Result<Vec<String>> {
+    let archives = find_mod_archives(&[install_path.to_owned()], downloads_library);
+    let Some(installed) = archives.get(install_path) else {
+        return Ok(vec![format!(
+            "{}: the installed mod was not installed from {}",
+            name, archive.file_name
+        )]);
+    };
+
+    let mut mismatches = vec![];
+    let file_name = installed
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if file_name != archive.file_name {
+        mismatches.push(format!(
+            "{}: installed from {} instead of {}",
+            name, file_name, archive.file_name
+        ));
+    }
+    let meta_path = get_meta_path(installed);
+    let version = meta_path
+        .exists()
+        .then(|| read_meta(&meta_path).ok())
+        .flatten()
+        .and_then(|meta| meta.version);
+    if let (Some(version), Some(expected)) = (&version, &archive.version) {
+        if version != expected {
+            mismatches.push(format!(
+                "{}: installed version {} instead of {}",
+                name, version, expected
+            ));
+        }
+    }
+    let is_same_file = fs::metadata(installed)?.len() == archive.size
+        && match &archive.sha256 {
+            Some(sha256) => hash_file(installed, |_, _| true)? == *sha256,
+            None => true,
+        };
+    if !is_same_file {
+        mismatches.push(format!(
+            "{}: installed {} is not the archive of the modlist",
+            name, file_name
+        ));
+    }
+    Ok(mismatches)
+}
+
+/// a mod name is a single folder of the mods library
+pub fn is_valid_mod_name(name: &str) -> bool {
+    is_single_component(name)
+}
+
+/// an archive file name is a single file of the downloads library
+pub fn is_valid_file_name(name: &str) -> bool {
+    is_single_component(name)
+}
+
+fn is_single_component(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    )
+}
+
+/// Extracts an archive into a mod folder with the installer options and patches of a modlist
+/// an existing folder is replaced
+pub fn install_mod(
+    archive: &Path,
+    install_path: &Path,
+    installer: &InstallerOptions,
+    patches: &[FilePatch],
+) -> io::Result<()> {
+    extract_mod(
+        archive,
+        install_path,
+        |_, _| true,
+        |part_path| match &installer.root {
+            Some(root) => Ok(part_path.join(get_enclosed_path(root).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("invalid root {}", root))
+            })?)),
+            None => Ok(part_path.to_owned()),
+        },
+        patches,
+    )
+}
+
+/// Extracts a newer archive of a mod over its folder
+/// the folder of the archive that matches the installed files is installed, e.g. without a Data Files folder
+/// on_progress is called with the number of extracted and total entries, return false to cancel.
+pub fn update_mod<F>(archive: &Path, install_path: &Path, on_progress: F) -> io::Result<()>
+where
+    F: FnMut(usize, usize) -> bool,
+{
+    extract_mod(
+        archive,
+        install_path,
+        on_progress,
+        |part_path| {
+            let root = install_path
+                .exists()
+                .then(|| find_root(part_path, install_path))
+                .flatten();
+            if let Some(root) = &root {
+                info!("Installing the folder {} of {}", root, archive.display());
+            }
+            Ok(root.map_or(part_path.to_owned(), |r| part_path.join(r)))
+        },
+        &[],
+    )
+}
+
+/// extracts an archive next to the mod folder and replaces the folder with the root of the archive
+fn extract_mod<F, R>(
+    archive: &Path,
+    install_path: &Path,
+    on_progress: F,
+    get_root: R,
+    patches: &[FilePatch],
+) -> io::Result<()>
+where
+    F: FnMut(usize, usize) -> bool,
+    R: FnOnce(&Path) -> io::Result<PathBuf>,
+{
+    let Some(part_path) = get_part_path(install_path) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "invalid mod name",
+        ));
+    };
+    let result = extract_archive(archive, &part_path, on_progress).and_then(|_| {
+        let root = get_root(&part_path)?;
+        if !root.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("the archive has no folder {}", root.display()),
+            ));
+        }
+        apply_patches(&root, patches)?;
+
+        if install_path.exists() {
+            fs::remove_dir_all(install_path)?;
+        }
+        fs::rename(&root, install_path)
+    });
+    if part_path.exists() {
+        if let Err(err) = fs::remove_dir_all(&part_path) {
+            warn!("Could not clean up {}: {}", part_path.display(), err);
+        }
+    }
+    result
+}
+
+/// Compares an archive with the mod folder it was installed to
+/// returns the folder of the archive that was installed and the files that were changed, added or deleted
+pub fn get_install_diff(
+    archive: &Path,
+    mod_dir: &Path,
+) -> io::Result<(InstallerOptions, Vec<FilePatch>)> {
+    let extracted = std::env::temp_dir().join(format!(
+        "omwmm-{}-{}",
+        std::process::id(),
+        mod_dir
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    ));
+    if extracted.exists() {
+        fs::remove_dir_all(&extracted)?;
+    }
+    let result = extract_archive(archive, &extracted, |_, _| true).and_then(|_| {
+        let installer = InstallerOptions {
+            root: find_root(&extracted, mod_dir),
+        };
+        let root = match &installer.root {
+            Some(root) => extracted.join(root),
+            None => extracted.to_owned(),
+        };
+
+        let mut patches = vec![];
+        let archive_files = get_files(&root);
+        for file in get_files(mod_dir) {
+            let data = fs::read(mod_dir.join(&file))?;
+            let is_changed = !archive_files.contains(&file)
+                || fs::read(root.join(&file)).map_or(true, |original| original != data);
+            if is_changed {
+                patches.push(FilePatch {
+                    path: file,
+                    data: Some(to_hex(&data)),
+                });
+            }
+        }
+        let mod_files = get_files(mod_dir);
+        for file in archive_files {
+            if !mod_files.contains(&file) {
+                patches.push(FilePatch {
+                    path: file,
+                    data: None,
+                });
+            }
+        }
+        Ok((installer, patches))
+    });
+    if let Err(err) = fs::remove_dir_all(&extracted) {
+        warn!("Could not clean up {}: {}", extracted.display(), err);
+    }
+    result
+}
+
+/// the folder of an extracted archive that a mod was installed from
+/// archives often have a single folder, e.g. Data Files, that the mod folder does not have
+fn find_root(extracted: &Path, mod_dir: &Path) -> Option<String> {
+    let mut root: Vec<String> = vec![];
+    loop {
+        let dir = root.iter().fold(extracted.to_path_buf(), |p, r| p.join(r));
+        let entries: Vec<fs::DirEntry> = fs::read_dir(&dir).ok()?.flatten().collect();
+        let [entry] = &entries[..] else { break };
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !entry.path().is_dir() || mod_dir.join(&name).exists() {
+            break;
+        }
+        root.push(name);
+    }
+    (!root.is_empty()).then(|| root.join("/"))
+}
+
+/// the relative paths of all files in a folder, separated by /
+fn get_files(dir: &Path) -> Vec<String> {
+    let mut files = vec![];
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(current) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(&current) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if let Ok(relative) = path.strip_prefix(dir) {
+                let parts: Vec<String> = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                    .collect();
+                files.push(parts.join("/"));
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// writes or deletes the patched files of a mod folder
+fn apply_patches(dir: &Path, patches: &[FilePatch]) -> io::Result<()> {
+    for patch in patches {
+        let Some(relative) = get_enclosed_path(&patch.path) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid patch path {}", patch.path),
+            ));
+        };
+        let path = dir.join(relative);
+        match &patch.data {
+            Some(data) => {
+                let Some(data) = from_hex(data) else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid patch data for {}", patch.path),
+                    ));
+                };
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&path, data)?;
+            }
+            None if path.exists() => fs::remove_file(&path)?,
+            None => warn!("Patched file {} does not exist", patch.path),
+        }
+    }
+    Ok(())
+}
+
+fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// odd lengths fail on the last byte
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// the mod folders of a mods library that are named like an archive of the downloads library
+/// an archive is installed to a folder with the name of the archive without extension
+pub fn find_mod_archives(mods: &[PathBuf], downloads_library: &Path) -> HashMap<PathBuf, PathBuf> {
+    let mut archives = HashMap::new();
+    let Ok(entries) = fs::read_dir(downloads_library) else {
+        return archives;
+    };
+    for entry in entries.flatten() {
+        let archive = entry.path();
+        if !archive.is_file() || !crate::archive::is_supported_archive(&archive) {
+            continue;
+        }
+        let Some(stem) = archive.file_stem() else { continue };
+        if let Some(path) = mods.iter().find(|m| m.file_name() == Some(stem)) {
+            archives.insert(path.to_owned(), archive);
+        }
+    }
+    archives
+}
+
+/// the base game data directories of the default openmw.cfg
+pub fn get_base_game_dirs() -> Vec<PathBuf> {
+    get_openmwcfg()
+        .and_then(parse_cfg)
+        .map(|info| {
+            info.data
+                .into_iter()
+                .filter(|dir| is_base_game_dir(dir))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// the size, hash and nexus info of an archive
+fn get_archive_info(archive: &Path) -> io::Result<ModlistArchive> {
+    let meta_path = get_meta_path(archive);
+    let meta = if meta_path.exists() {
+        read_meta(&meta_path).ok()
+    } else {
+        None
+    };
+    let meta = meta.unwrap_or_default();
+    Ok(ModlistArchive {
+        file_name: archive
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        size: fs::metadata(archive)?.len(),
+        sha256: Some(hash_file(archive, |_, _| true)?),
+        game: meta.game_name,
+        mod_id: meta.mod_id,
+        file_id: meta.file_id,
+        version: meta.version,
+    })
+}
@@ -0,0 +1,477 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{get_archives_in_folder, get_openmwcfg, get_plugins_in_folder, parse_cfg, read_lines};
+
+pub const MODS_LIST: &str = "mods.txt";
+pub const PLUGINS_LIST: &str = "plugins.txt";
+pub const OPENMW_CFG: &str = "openmw.cfg";
+pub const SETTINGS_CFG: &str = "settings.cfg";
+const MANAGER_SETTINGS: &str = "omwmm.toml";
+
+/// The omwmm settings that are shared with omw-util
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ManagerSettings {
+    /// the name of the active profile
+    pub current_profile: String,
+    /// the folder where mods are extracted to
+    pub mods_library: Option<PathBuf>,
+    /// the folder where mod archives are stored
+    pub downloads_library: Option<PathBuf>,
+}
+
+impl Default for ManagerSettings {
+    fn default() -> Self {
+        Self {
+            current_profile: "default".to_owned(),
+            mods_library: None,
+            downloads_library: None,
+        }
+    }
+}
+
+impl ManagerSettings {
+    /// reads the settings of an omwmm folder, returns None if there are none yet
+    pub fn read(manager_dir: &Path) -> Option<Self> {
+        let path = manager_dir.join(MANAGER_SETTINGS);
+        if !path.exists() {
+            return None;
+        }
+        match fs::read_to_string(&path).map(|s| toml::from_str(&s)) {
+            Ok(Ok(settings)) => Some(settings),
+            Ok(Err(err)) => {
+                warn!("Could not parse {}: {}", path.display(), err);
+                None
+            }
+            Err(err) => {
+                warn!("Could not read {}: {}", path.display(), err);
+                None
+            }
+        }
+    }
+
+    pub fn write(&self, manager_dir: &Path) -> io::Result<()> {
+        let toml = toml::to_string_pretty(self)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        fs::create_dir_all(manager_dir)?;
+        fs::write(manager_dir.join(MANAGER_SETTINGS), toml)
+    }
+}
+
+/// The mod and plugin order of a profile with the enabled state of each entry
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ProfileLists {
+    pub mods: Vec<(PathBuf, bool)>,
+    pub plugins: Vec<(String, bool)>,
+}
+
+impl ProfileLists {
+    /// loads the lists of a profile the way omwmm shows them
+    /// mods that no longer exist are dropped, new mods of the library are appended disabled
+    /// plugins are those of the mods, new ones are appended disabled unless they belong to the base game
+    pub fn load(profile_dir: &Path, mods_library: Option<&Path>) -> Self {
+        Self::load_with(profile_dir, mods_library, is_base_game_dir)
+    }
+
+    /// loads the lists of a profile, is_base_game decides which data directories are base game data
+    pub fn load_with<F>(profile_dir: &Path, mods_library: Option<&Path>, is_base_game: F) -> Self
+    where
+        F: Fn(&Path) -> bool,
+    {
+        let mut mods: Vec<(PathBuf, bool)> = read_profile_list(&profile_dir.join(MODS_LIST))
+            .into_iter()
+            .map(|(name, enabled)| (PathBuf::from(name), enabled))
+            .filter(|(path, _)| path.exists())
+            .collect();
+        if let Some(library) = mods_library {
+            for path in get_library_mods(library) {
+                if !mods.iter().any(|(p, _)| *p == path) {
+                    mods.push((path, false));
+                }
+            }
+        }
+        // base game data is always enabled
+        for (path, enabled) in mods.iter_mut() {
+            *enabled |= is_base_game(path);
+        }
+
+        let mut lists = Self {
+            mods,
+            plugins: read_profile_list(&profile_dir.join(PLUGINS_LIST)),
+        };
+        lists.update_plugins_with(is_base_game);
+        lists
+    }
+
+    /// drops plugins that no mod provides and appends new ones
+    pub fn update_plugins(&mut self) {
+        self.update_plugins_with(is_base_game_dir);
+    }
+
+    /// drops plugins that no mod provides and appends new ones, enabled if is_base_game is true for their mod
+    pub fn update_plugins_with<F>(&mut self, is_base_game: F)
+    where
+        F: Fn(&Path) -> bool,
+    {
+        let mut provided: Vec<String> = vec![];
+        let mut base_game_plugins: Vec<String> = vec![];
+        for (path, _) in self.mods.iter() {
+            let is_base_game = is_base_game(path);
+            for plugin in get_plugins_in_folder(path, true) {
+                let Some(name) = plugin.file_name() else { continue };
+                let name = name.to_string_lossy().into_owned();
+                if is_base_game {
+                    base_game_plugins.push(name.to_owned());
+                }
+                if !provided.contains(&name) {
+                    provided.push(name);
+                }
+            }
+        }
+
+        self.plugins.retain(|(name, _)| provided.contains(name));
+        for name in provided {
+            if !self.plugins.iter().any(|(p, _)| *p == name) {
+                let enabled = base_game_plugins.contains(&name);
+                self.plugins.push((name, enabled));
+            }
+        }
+    }
+
+    /// the data directories and content files the cfg of the profile gets, in load order
+    /// plugins are only loaded if an enabled mod provides them
+    pub fn get_cfg_entries(&self) -> (Vec<PathBuf>, Vec<String>) {
+        let data: Vec<PathBuf> = self
+            .mods
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(path, _)| path.to_owned())
+            .collect();
+        let provided = self.get_provided_plugins();
+        let content: Vec<String> = self
+            .plugins
+            .iter()
+            .filter(|(name, enabled)| *enabled && provided.contains(name))
+            .map(|(name, _)| name.to_owned())
+            .collect();
+        (data, content)
+    }
+
+    /// the plugins of the enabled mods
+    pub fn get_provided_plugins(&self) -> HashSet<String> {
+        self.mods
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .flat_map(|(path, _)| get_plugins_in_folder(path, true))
+            .filter_map(|plugin| {
+                plugin
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+            .collect()
+    }
+
+    /// writes the lists to the profile
+    pub fn save(&self, profile_dir: &Path) {
+        write_profile_list(
+            &profile_dir.join(MODS_LIST),
+            self.mods
+                .iter()
+                .map(|(path, enabled)| (path.to_string_lossy().into_owned(), *enabled)),
+        );
+        write_profile_list(
+            &profile_dir.join(PLUGINS_LIST),
+            self.plugins.iter().cloned(),
+        );
+    }
+
+    /// the data directories and content files apply_cfg writes to a cfg, in load order
+    /// entries of the cfg that omwmm does not manage are kept at their positions,
+    /// external data dirs that no longer exist are dropped
+    pub fn get_merged_cfg_entries(&self, cfg_path: &Path) -> (Vec<PathBuf>, Vec<String>) {
+        let (data, content) = self.get_cfg_entries();
+        let mut mods: Vec<(PathBuf, bool)> = self
+            .mods
+            .iter()
+            .map(|(path, _)| (path.to_owned(), data.contains(path)))
+            .collect();
+        let mut plugins: Vec<(String, bool)> = self
+            .plugins
+            .iter()
+            .map(|(name, _)| (name.to_owned(), content.contains(name)))
+            .collect();
+        if let Some(info) = parse_cfg(cfg_path.to_path_buf()) {
+            let known: Vec<PathBuf> = mods.iter().map(|(p, _)| p.to_owned()).collect();
+            let cfg_data = info.data.into_iter().filter(|p| p.exists()).collect();
+            for (i, path) in get_external_entries(&known, cfg_data) {
+                info!("Keeping external data dir {}", path.display());
+                mods.insert(i, (path, true));
+            }
+            let known: Vec<String> = plugins.iter().map(|(p, _)| p.to_owned()).collect();
+            for (i, name) in get_external_entries(&known, info.plugins) {
+                info!("Keeping external content file {}", name);
+                plugins.insert(i, (name, true));
+            }
+        }
+        let data: Vec<PathBuf> = mods.into_iter().filter(|m| m.1).map(|m| m.0).collect();
+        let content: Vec<String> = plugins.into_iter().filter(|p| p.1).map(|p| p.0).collect();
+        (data, content)
+    }
+
+    /// writes the enabled mods and plugins of the lists to a cfg
+    /// entries of the cfg that omwmm does not manage are kept at their positions
+    pub fn apply_cfg(&self, cfg_path: &Path) -> bool {
+        let (data, content) = self.get_merged_cfg_entries(cfg_path);
+        let base_game_dirs: Vec<PathBuf> = self
+            .mods
+            .iter()
+            .map(|(p, _)| p.to_owned())
+            .filter(|p| is_base_game_dir(p))
+            .collect();
+        write_cfg(cfg_path, &data, &content, &base_game_dirs)
+    }
+}
+
+/// Gets the folder of the omwmm settings and profiles
+/// None if the system has no config folder
+pub fn get_manager_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("omwmm"))
+}
+
+/// Gets the folder where all profiles are stored
+pub fn get_profiles_dir(manager_dir: &Path) -> PathBuf {
+    manager_dir.join("profiles")
+}
+
+/// the names of all profiles, sorted
+pub fn get_profiles(profiles_dir: &Path) -> Vec<String> {
+    let mut profiles = vec![];
+    if let Ok(entries) = fs::read_dir(profiles_dir) {
+        for entry in entries.flatten() {
+            if entry.path().is_dir() {
+                profiles.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+    }
+    profiles.sort();
+    profiles
+}
+
+/// checks a new profile name and returns the profile dir
+pub fn get_new_profile_dir(profiles_dir: &Path, name: &str) -> Option<PathBuf> {
+    if name.trim().is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+        error!("Invalid profile name: {}", name);
+        return None;
+    }
+    let profile_dir = profiles_dir.join(name);
+    if profile_dir.exists() {
+        error!("Profile {} already exists", name);
+        return None;
+    }
+    Some(profile_dir)
+}
+
+/// Gets the openmw.cfg of a profile
+/// if it doesn't exist yet it is created from the default openmw.cfg
+pub fn get_profile_cfg(profile_dir: &Path) -> PathBuf {
+    let cfg_path = profile_dir.join(OPENMW_CFG);
+    if !cfg_path.exists() {
+        if let Some(default_cfg) = get_openmwcfg() {
+            info!("Creating profile cfg from {}", default_cfg.display());
+            if let Err(err) = fs::copy(&default_cfg, &cfg_path) {
+                error!("Could not copy cfg {}: {}", default_cfg.display(), err);
+            }
+        } else if let Err(err) = File::create(&cfg_path) {
+            error!("Could not create cfg {}: {}", cfg_path.display(), err);
+        }
+    }
+    cfg_path
+}
+
+/// the mod folders of a mods library
+/// hidden folders are mods that are still being installed and the trash
+pub fn get_library_mods(library: &Path) -> Vec<PathBuf> {
+    let mut mods = vec![];
+    if let Ok(entries) = fs::read_dir(library) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() && !entry.file_name().to_string_lossy().starts_with('.') {
+                mods.push(path);
+            }
+        }
+    }
+    mods.sort();
+    mods
+}
+
+/// checks if a data directory has the vanilla masters or archives
+pub fn is_base_game_dir(data_path: &Path) -> bool {
+    const BASE_GAME_FILES: [&str; 4] = [
+        "morrowind.esm",
+        "tribunal.esm",
+        "bloodmoon.esm",
+        "morrowind.bsa",
+    ];
+    if let Ok(entries) = fs::read_dir(data_path) {
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_lowercase();
+            if BASE_GAME_FILES.contains(&file_name.as_str()) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// finds the cfg entries that are missing in a list
+/// returns each with the index it is inserted at, after the entry that precedes it in the cfg
+/// the indices are valid when the entries are inserted in order
+pub fn get_external_entries<T: PartialEq>(list: &[T], cfg_entries: Vec<T>) -> Vec<(usize, T)> {
+    // the merged order, None for external entries
+    let mut merged: Vec<Option<usize>> = (0..list.len()).map(Some).collect();
+    let mut external: Vec<(usize, T)> = vec![];
+    let mut pos = 0;
+    for entry in cfg_entries {
+        if let Some(i) = list.iter().position(|e| *e == entry) {
+            pos = merged.iter().position(|m| *m == Some(i)).unwrap_or(pos) + 1;
+        } else {
+            merged.insert(pos, None);
+            external.push((pos, entry));
+            pos += 1;
+        }
+    }
+    external
+}
+
+/// updates the content= and data= entries of a cfg
+/// all other lines are kept and missing fallback archives of the base game are added
+pub fn write_cfg(
+    cfg_path: &Path,
+    data_dirs: &[PathBuf],
+    plugins: &[String],
+    base_game_dirs: &[PathBuf],
+) -> bool {
+    // get everything that is not a data or content line
+    info!("Parsing cfg {} ...", cfg_path.display());
+    let mut lines: Vec<String> = vec![];
+    if let Ok(cfg_lines) = read_lines(cfg_path) {
+        for line in cfg_lines.map_while(Result::ok) {
+            if !line.starts_with("data=") && !line.starts_with("content=") {
+                lines.push(line);
+            }
+        }
+    } else {
+        error!("Could not parse cfg file {}", cfg_path.display());
+        return false;
+    }
+
+    // add the archives of the base game
+    let mut archives: Vec<String> = lines
+        .iter()
+        .filter_map(|l| l.strip_prefix("fallback-archive="))
+        .map(|a| a.to_lowercase())
+        .collect();
+    for dir in base_game_dirs {
+        for archive in get_archives_in_folder(dir) {
+            let Some(archive_name) = archive.file_name() else { continue };
+            let archive_name = archive_name.to_string_lossy();
+            if !archives.contains(&archive_name.to_lowercase()) {
+                archives.push(archive_name.to_lowercase());
+                lines.push(format!("fallback-archive={}", archive_name));
+            }
+        }
+    }
+
+    for dir in data_dirs {
+        lines.push(format!("data=\"{}\"", dir.to_string_lossy()));
+    }
+    for plugin in plugins {
+        lines.push(format!("content={}", plugin));
+    }
+
+    // reassemble cfg
+    if let Ok(mut file) = File::create(cfg_path) {
+        for line in lines {
+            // TODO proper eol
+            if let Err(err) = writeln!(file, "{}", line) {
+                warn!("Error writing line {}: {}", line, err);
+            }
+        }
+    } else {
+        error!("Could not write cfg file {}", cfg_path.display());
+        return false;
+    }
+    true
+}
+
+/// reads an ordered profile list: one entry per line, prefixed with + if enabled and - if disabled
+/// entries without a prefix are enabled
+pub fn read_profile_list(path: &Path) -> Vec<(String, bool)> {
+    let mut entries = vec![];
+    if !path.exists() {
+        return entries;
+    }
+    if let Ok(lines) = read_lines(path) {
+        for line in lines.map_while(Result::ok) {
+            if let Some(name) = line.strip_prefix('+') {
+                entries.push((name.to_owned(), true));
+            } else if let Some(name) = line.strip_prefix('-') {
+                entries.push((name.to_owned(), false));
+            } else if !line.is_empty() {
+                entries.push((line, true));
+            }
+        }
+    } else {
+        warn!("Could not read file: {}", path.display());
+    }
+    entries
+}
+
+/// writes an ordered profile list
+pub fn write_profile_list(path: &Path, entries: impl Iterator<Item = (String, bool)>) {
+    if let Ok(mut f) = File::create(path) {
+        for (name, enabled) in entries {
+            let line = format!("{}{}\n", if enabled { '+' } else { '-' }, name);
+            if f.write_all(line.as_bytes()).is_err() {
+                warn!("Could not write line: {}", name);
+            }
+        }
+    } else {
+        warn!("Could not create file: {}", path.display());
+    }
+}
+
+/// copies all files of a folder to a new folder
+pub fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)?.flatten() {
+        let path = entry.path();
+        if path.is_file() {
+            fs::copy(&path, to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+/// copies a folder with all of its subfolders to a new folder
+pub fn copy_dir_all(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&path, &to.join(entry.file_name()))?;
+        } else {
+            fs::copy(&path, to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
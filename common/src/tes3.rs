@@ -0,0 +1,363 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::File,
+    io::{self, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+use log::warn;
+
+/// Uniquely identifies a record across plugins: the record tag and its lowercase id
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RecordId {
+    pub tag: String,
+    pub id: String,
+}
+
+impl fmt::Display for RecordId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.tag, self.id)
+    }
+}
+
+/// The parts of a TES3 plugin that are relevant for conflict detection
+#[derive(Default, Debug, Clone)]
+pub struct PluginInfo {
+    /// masters from the TES3 header
+    pub masters: Vec<String>,
+    /// ids of all records in the plugin, in file order
+    pub records: Vec<RecordId>,
+}
+
+/// Record-level conflicts of a single plugin.
+/// Records a plugin changes from one of its own masters are intended overrides and not listed.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct PluginConflicts {
+    pub plugin: String,
+    /// records this plugin wins over earlier plugins
+    pub overrides: Vec<RecordId>,
+    /// records of this plugin that are replaced by later plugins
+    pub overridden: Vec<RecordId>,
+}
+
+impl PluginConflicts {
+    pub fn is_empty(&self) -> bool {
+        self.overrides.is_empty() && self.overridden.is_empty()
+    }
+}
+
+/// A record that is changed by more than one plugin
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordConflict {
+    pub record: RecordId,
+    /// plugins that contain the record, in load order
+    pub plugins: Vec<String>,
+}
+
+impl RecordConflict {
+    /// The last plugin in the load order wins
+    pub fn get_winner(&self) -> &String {
+        self.plugins
+            .last()
+            .expect("a conflict has at least two plugins")
+    }
+}
+
+/// An index of the record ids of all plugins in the effective load order
+#[derive(Default, Debug)]
+pub struct RecordIndex {
+    plugins: Vec<String>,
+    /// masters of each plugin
+    masters: Vec<Vec<String>>,
+    /// record id -> indices of the plugins that contain the record, in load order
+    records: BTreeMap<RecordId, Vec<usize>>,
+}
+
+impl RecordIndex {
+    /// Reads all plugin files in load order and indexes their records
+    pub fn new(plugin_paths: &[PathBuf]) -> Self {
+        let mut index = Self::default();
+        for path in plugin_paths {
+            let Some(name) = path.file_name() else { continue };
+            // omwscripts are not TES3 files
+            if let Some(ext) = path.extension() {
+                if ext.eq_ignore_ascii_case("omwscripts") {
+                    continue;
+                }
+            }
+            match read_plugin(path) {
+                Ok(info) => index.add_plugin(name.to_string_lossy().into_owned(), info),
+                Err(err) => warn!("Could not read plugin {}: {}", path.display(), err),
+            }
+        }
+        index
+    }
+
+    /// Adds a plugin at the end of the load order
+    pub fn add_plugin(&mut self, name: String, info: PluginInfo) {
+        let i = self.plugins.len();
+        self.plugins.push(name);
+        self.masters.push(info.masters);
+        for record in info.records {
+            let providers = self.records.entry(record).or_default();
+            if !providers.contains(&i) {
+                providers.push(i);
+            }
+        }
+    }
+
+    /// checks if a plugin lists another plugin as master
+    fn is_master_of(&self, master: usize, plugin: usize) -> bool {
+        self.masters[plugin]
+            .iter()
+            .any(|m| m.eq_ignore_ascii_case(&self.plugins[master]))
+    }
+
+    /// Returns the masters that are not loaded before each plugin, for plugins that miss any
+    pub fn get_missing_masters(&self) -> Vec<(String, Vec<String>)> {
+        self.plugins
+            .iter()
+            .enumerate()
+            .filter_map(|(i, plugin)| {
+                let missing: Vec<String> = self.masters[i]
+                    .iter()
+                    .filter(|m| !self.plugins[..i].iter().any(|p| p.eq_ignore_ascii_case(m)))
+                    .cloned()
+                    .collect();
+                (!missing.is_empty()).then(|| (plugin.to_owned(), missing))
+            })
+            .collect()
+    }
+
+    /// Returns all records that are changed by more than one plugin that do not depend on each other
+    pub fn get_record_conflicts(&self) -> Vec<RecordConflict> {
+        let mut conflicts = vec![];
+        for (record, providers) in self.records.iter().filter(|(_, p)| p.len() > 1) {
+            let clashing: Vec<usize> = providers
+                .iter()
+                .copied()
+                .filter(|i| self.clashes(*i, providers))
+                .collect();
+            if clashing.len() > 1 {
+                conflicts.push(RecordConflict {
+                    record: record.to_owned(),
+                    plugins: clashing
+                        .iter()
+                        .map(|i| self.plugins[*i].to_owned())
+                        .collect(),
+                });
+            }
+        }
+        conflicts
+    }
+
+    /// Returns the record conflicts of each plugin, in load order
+    pub fn get_conflicts(&self) -> Vec<PluginConflicts> {
+        let mut conflicts: Vec<PluginConflicts> = self
+            .plugins
+            .iter()
+            .map(|p| PluginConflicts {
+                plugin: p.to_owned(),
+                ..Default::default()
+            })
+            .collect();
+
+        for (record, providers) in self.records.iter().filter(|(_, p)| p.len() > 1) {
+            for (n, i) in providers.iter().enumerate() {
+                // wins over an earlier plugin that is not one of its masters
+                if providers[..n].iter().any(|j| !self.is_master_of(*j, *i)) {
+                    conflicts[*i].overrides.push(record.to_owned());
+                }
+                // loses to a later plugin that does not depend on it
+                if providers[n + 1..]
+                    .iter()
+                    .any(|j| !self.is_master_of(*i, *j))
+                {
+                    conflicts[*i].overridden.push(record.to_owned());
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// a plugin clashes on a record if another plugin changes it without a master relationship
+    fn clashes(&self, i: usize, providers: &[usize]) -> bool {
+        providers
+            .iter()
+            .filter(|j| **j != i)
+            .any(|j| !self.is_master_of(*j, i) && !self.is_master_of(i, *j))
+    }
+}
+
+/// Reads the masters and record ids of a TES3 plugin (esm, esp, omwaddon)
+pub fn read_plugin<P>(path: P) -> io::Result<PluginInfo>
+where
+    P: AsRef<Path>,
+{
+    let file = File::open(path)?;
+    // record sizes are checked against the rest of the file before the record is read
+    let mut remaining = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+    let mut info = PluginInfo::default();
+    // INFO records belong to the preceding DIAL record
+    let mut current_dialogue = String::new();
+
+    let mut header = [0_u8; 16];
+    loop {
+        match reader.read_exact(&mut header) {
+            Ok(_) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err),
+        }
+        let tag = String::from_utf8_lossy(&header[0..4]).into_owned();
+        let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        remaining = remaining.saturating_sub(header.len() as u64);
+        if u64::from(size) > remaining {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("record {} of {} bytes is larger than the file", tag, size),
+            ));
+        }
+        remaining -= u64::from(size);
+        let mut data = vec![0_u8; size as usize];
+        reader.read_exact(&mut data)?;
+        let subrecords = read_subrecords(&data)?;
+
+        match tag.as_str() {
+            "TES3" => {
+                info.masters = subrecords
+                    .iter()
+                    .filter(|(t, _)| t == b"MAST")
+                    .map(|(_, d)| read_zstring(d))
+                    .collect();
+            }
+            "DIAL" => {
+                current_dialogue = get_subrecord_string(&subrecords, b"NAME").unwrap_or_default();
+                info.records.push(new_record_id(&tag, &current_dialogue));
+            }
+            "INFO" => {
+                if let Some(id) = get_subrecord_string(&subrecords, b"INAM") {
+                    let info_id = format!("{}::{}", current_dialogue, id);
+                    info.records.push(new_record_id(&tag, &info_id));
+                }
+            }
+            _ => {
+                if let Some(id) = get_record_id(&tag, &subrecords) {
+                    info.records.push(new_record_id(&tag, &id));
+                }
+            }
+        }
+    }
+
+    Ok(info)
+}
+
+fn new_record_id(tag: &str, id: &str) -> RecordId {
+    RecordId {
+        tag: tag.to_owned(),
+        id: id.to_lowercase(),
+    }
+}
+
+/// Returns the id of a record, most records are identified by their NAME subrecord
+fn get_record_id(tag: &str, subrecords: &[([u8; 4], &[u8])]) -> Option<String> {
+    match tag {
+        "CELL" => {
+            // exterior cells are identified by their grid position
+            let name = get_subrecord_string(subrecords, b"NAME").unwrap_or_default();
+            let data = get_subrecord(subrecords, b"DATA")?;
+            if data.len() < 12 {
+                return Some(name);
+            }
+            let flags = read_u32(&data[0..4]);
+            if flags & 0x01 != 0 {
+                Some(name)
+            } else {
+                Some(format!(
+                    "{},{}",
+                    read_i32(&data[4..8]),
+                    read_i32(&data[8..12])
+                ))
+            }
+        }
+        "LAND" => {
+            let grid = get_subrecord(subrecords, b"INTV")?;
+            if grid.len() < 8 {
+                return None;
+            }
+            Some(format!(
+                "{},{}",
+                read_i32(&grid[0..4]),
+                read_i32(&grid[4..8])
+            ))
+        }
+        "PGRD" => {
+            let name = get_subrecord_string(subrecords, b"NAME").unwrap_or_default();
+            let data = get_subrecord(subrecords, b"DATA")?;
+            if data.len() < 8 {
+                return Some(name);
+            }
+            Some(format!(
+                "{} ({},{})",
+                name,
+                read_i32(&data[0..4]),
+                read_i32(&data[4..8])
+            ))
+        }
+        "SKIL" | "MGEF" => {
+            let index = get_subrecord(subrecords, b"INDX")?;
+            if index.len() < 4 {
+                return None;
+            }
+            Some(read_u32(&index[0..4]).to_string())
+        }
+        "SCPT" => {
+            let header = get_subrecord(subrecords, b"SCHD")?;
+            Some(read_zstring(&header[..header.len().min(32)]))
+        }
+        _ => get_subrecord_string(subrecords, b"NAME"),
+    }
+}
+
+/// Splits record data into (tag, data) subrecords
+fn read_subrecords(data: &[u8]) -> io::Result<Vec<([u8; 4], &[u8])>> {
+    let mut subrecords = vec![];
+    let mut pos = 0;
+    while pos + 8 <= data.len() {
+        let tag = [data[pos], data[pos + 1], data[pos + 2], data[pos + 3]];
+        let size = read_u32(&data[pos + 4..pos + 8]) as usize;
+        pos += 8;
+        if pos + size > data.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("subrecord {} exceeds record", String::from_utf8_lossy(&tag)),
+            ));
+        }
+        subrecords.push((tag, &data[pos..pos + size]));
+        pos += size;
+    }
+    Ok(subrecords)
+}
+
+fn get_subrecord<'a>(subrecords: &[([u8; 4], &'a [u8])], tag: &[u8; 4]) -> Option<&'a [u8]> {
+    subrecords.iter().find(|(t, _)| t == tag).map(|(_, d)| *d)
+}
+
+fn get_subrecord_string(subrecords: &[([u8; 4], &[u8])], tag: &[u8; 4]) -> Option<String> {
+    get_subrecord(subrecords, tag).map(read_zstring)
+}
+
+/// Reads a null-terminated (or fixed size) string
+fn read_zstring(data: &[u8]) -> String {
+    let end = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).into_owned()
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+    u32::from_le_bytes([data[0], data[1], data[2], data[3]])
+}
+
+fn read_i32(data: &[u8]) -> i32 {
+    i32::from_le_bytes([data[0], data[1], data[2], data[3]])
+}
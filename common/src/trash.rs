@@ -0,0 +1,173 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::{info, warn};
+
+use crate::{
+    meta::{get_meta_path, read_meta, write_meta, ModNexusMetaData},
+    profile::copy_dir_all,
+};
+
+/// hidden, so the trash is not listed as a mod of the library
+pub const TRASH_DIR: &str = ".trash";
+
+/// A mod folder in the trash of a mods library
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrashedMod {
+    /// the mod folder in the trash
+    pub path: PathBuf,
+    pub name: String,
+    /// when the mod was moved to the trash
+    pub trashed: SystemTime,
+    /// nexus metadata the mod had when it was uninstalled
+    pub meta_data: Option<ModNexusMetaData>,
+}
+
+pub fn get_trash_dir(mods_library: &Path) -> PathBuf {
+    mods_library.join(TRASH_DIR)
+}
+
+/// Moves a mod folder into the trash of the mods library
+/// each mod gets a folder named after the time it was trashed, so mods with the same name can be trashed more than once.
+/// returns the path of the mod in the trash
+pub fn move_to_trash(
+    mods_library: &Path,
+    mod_dir: &Path,
+    meta_data: Option<&ModNexusMetaData>,
+) -> io::Result<PathBuf> {
+    let Some(name) = mod_dir.file_name() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "invalid mod folder",
+        ));
+    };
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis());
+    let mut entry_dir = get_trash_dir(mods_library).join(millis.to_string());
+    let mut n = 1;
+    while entry_dir.exists() {
+        entry_dir = get_trash_dir(mods_library).join(format!("{}-{}", millis, n));
+        n += 1;
+    }
+    fs::create_dir_all(&entry_dir)?;
+
+    let trashed = entry_dir.join(name);
+    move_dir(mod_dir, &trashed)?;
+    if let Some(meta) = meta_data {
+        if let Err(err) = write_meta(get_meta_path(&trashed), meta) {
+            warn!("Could not save metadata of {}: {}", trashed.display(), err);
+        }
+    }
+    info!("Moved {} to {}", mod_dir.display(), trashed.display());
+    Ok(trashed)
+}
+
+/// the mods in the trash of a mods library, newest first
+pub fn get_trashed_mods(mods_library: &Path) -> Vec<TrashedMod> {
+    let mut mods = vec![];
+    let Ok(entries) = fs::read_dir(get_trash_dir(mods_library)) else {
+        return mods;
+    };
+    for entry in entries.flatten() {
+        let trashed = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .unwrap_or(UNIX_EPOCH);
+        let Ok(files) = fs::read_dir(entry.path()) else { continue };
+        for file in files.flatten() {
+            let path = file.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let meta_path = get_meta_path(&path);
+            mods.push(TrashedMod {
+                name: file.file_name().to_string_lossy().into_owned(),
+                trashed,
+                meta_data: meta_path
+                    .exists()
+                    .then(|| read_meta(&meta_path).ok())
+                    .flatten(),
+                path,
+            });
+        }
+    }
+    mods.sort_by_key(|m| std::cmp::Reverse(m.trashed));
+    mods
+}
+
+/// Moves a mod from the trash back into the mods library
+/// fails if the library has a mod with the same name
+pub fn restore_from_trash(mods_library: &Path, trashed: &TrashedMod) -> io::Result<PathBuf> {
+    let mod_dir = mods_library.join(&trashed.name);
+    if mod_dir.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} is installed", trashed.name),
+        ));
+    }
+    move_dir(&trashed.path, &mod_dir)?;
+    remove_trash_entry(&trashed.path)?;
+    info!("Restored {}", mod_dir.display());
+    Ok(mod_dir)
+}
+
+/// Deletes a mod in the trash for good
+pub fn delete_from_trash(trashed: &TrashedMod) -> io::Result<()> {
+    fs::remove_dir_all(&trashed.path)?;
+    remove_trash_entry(&trashed.path)
+}
+
+/// Deletes all mods in the trash for good
+pub fn empty_trash(mods_library: &Path) -> io::Result<()> {
+    let trash_dir = get_trash_dir(mods_library);
+    if trash_dir.exists() {
+        fs::remove_dir_all(trash_dir)?;
+    }
+    Ok(())
+}
+
+/// removes the metadata and the folder of a trashed mod once the mod is gone
+fn remove_trash_entry(trashed: &Path) -> io::Result<()> {
+    let meta_path = get_meta_path(trashed);
+    if meta_path.exists() {
+        fs::remove_file(meta_path)?;
+    }
+    if let Some(entry_dir) = trashed.parent() {
+        if fs::read_dir(entry_dir)?.next().is_none() {
+            fs::remove_dir(entry_dir)?;
+        }
+    }
+    Ok(())
+}
+
+/// renames a folder, or copies it if it is on another drive
+fn move_dir(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    copy_and_remove_dir(from, to)
+}
+
+/// copies a folder with its subfolders and removes it once the copy is complete
+/// if the copy fails the folder is kept and the partial copy is removed
+pub fn copy_and_remove_dir(from: &Path, to: &Path) -> io::Result<()> {
+    if to.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} exists", to.display()),
+        ));
+    }
+    if let Err(err) = copy_dir_all(from, to) {
+        if to.is_dir() {
+            if let Err(err) = fs::remove_dir_all(to) {
+                warn!("Could not clean up {}: {}", to.display(), err);
+            }
+        }
+        return Err(err);
+    }
+    fs::remove_dir_all(from)
+}
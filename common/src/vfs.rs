@@ -0,0 +1,104 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::warn;
+
+/// File-level conflicts of a single data directory
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct DataDirConflicts {
+    pub data_dir: PathBuf,
+    /// files this data directory wins over earlier data directories
+    pub overrides: Vec<String>,
+    /// files of this data directory that are replaced by later data directories
+    pub overridden: Vec<String>,
+}
+
+impl DataDirConflicts {
+    pub fn is_empty(&self) -> bool {
+        self.overrides.is_empty() && self.overridden.is_empty()
+    }
+}
+
+/// An index of the virtual file system OpenMW assembles from the data directories.
+/// Later data directories override files of earlier ones, paths are compared case-insensitively.
+#[derive(Default, Debug)]
+pub struct VfsIndex {
+    data_dirs: Vec<PathBuf>,
+    /// normalized relative file path -> indices of the providing data directories in load order
+    files: BTreeMap<String, Vec<usize>>,
+}
+
+impl VfsIndex {
+    /// Walks all data directories in data= order and indexes their files
+    pub fn new(data_dirs: &[PathBuf]) -> Self {
+        let mut files: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (i, data_dir) in data_dirs.iter().enumerate() {
+            if !data_dir.exists() {
+                warn!("data path {} does not exist", data_dir.display());
+                continue;
+            }
+            let mut dir_files: Vec<String> = vec![];
+            walk_files(data_dir, data_dir, &mut dir_files);
+            for file in dir_files {
+                let providers = files.entry(file).or_default();
+                // a data dir may contain the same file twice with different casing
+                if !providers.contains(&i) {
+                    providers.push(i);
+                }
+            }
+        }
+
+        Self {
+            data_dirs: data_dirs.to_vec(),
+            files,
+        }
+    }
+
+    /// Returns the conflicts of each data directory, in data= order
+    pub fn get_conflicts(&self) -> Vec<DataDirConflicts> {
+        let mut conflicts: Vec<DataDirConflicts> = self
+            .data_dirs
+            .iter()
+            .map(|d| DataDirConflicts {
+                data_dir: d.to_owned(),
+                ..Default::default()
+            })
+            .collect();
+
+        for (file, providers) in self.files.iter().filter(|(_, p)| p.len() > 1) {
+            for (n, i) in providers.iter().enumerate() {
+                if n > 0 {
+                    conflicts[*i].overrides.push(file.to_owned());
+                }
+                if n < providers.len() - 1 {
+                    conflicts[*i].overridden.push(file.to_owned());
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+/// Normalizes a relative vfs path: lowercase with forward slashes
+pub fn normalize_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/").to_lowercase()
+}
+
+/// Recursively collects all files in a folder as normalized paths relative to root
+fn walk_files(root: &Path, dir: &Path, files: &mut Vec<String>) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                walk_files(root, &path, files);
+            } else if let Ok(relative) = path.strip_prefix(root) {
+                files.push(normalize_path(relative));
+            }
+        }
+    } else {
+        warn!("Could not read directory {}", dir.display());
+    }
+}
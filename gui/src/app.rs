@@ -1,15 +1,39 @@
 use std::{
-    fs::{self, File},
-    io::Write,
-    path::PathBuf,
+    cmp::Ordering,
+    collections::{HashMap, HashSet, VecDeque},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
 };
 
 use egui_dnd::DragDropUi;
 use egui_notify::Toasts;
-use log::{error, info, warn};
-
-use crate::{ArchiveViewModel, EScale, ModNexusMetaData, ModViewModel, PluginViewModel};
-use common::{get_openmwcfg, get_plugins_in_folder};
+use log::{info, warn};
+
+use crate::{
+    downloader::NxmLink,
+    filters::EListFilter,
+    history::EditHistory,
+    ipc::{EIpcCommand, IpcServer},
+    jobs::Job,
+    launcher::RunningProcess,
+    metadata::ModInfo,
+    mods::{assign_mod_ids, get_broken_mods, insert_mod, push_mod, EConfirmAction},
+    profiles::EProfileDialog,
+    watcher::LibraryWatcher,
+    ArchiveViewModel, EInstallState, EModTag, EScale, ModNexusMetaData, ModSeparator, ModViewModel,
+    PluginViewModel,
+};
+use common::{
+    archive::is_supported_archive,
+    diff::CfgDiff,
+    meta::{compare_versions, get_meta_path, read_meta},
+    modlist::ModlistImport,
+    profile::{get_external_entries, is_base_game_dir, write_cfg, ProfileLists},
+    tes3::PluginConflicts,
+    trash::TrashedMod,
+    vfs::DataDirConflicts,
+};
 
 /// Tab Views
 #[derive(PartialEq)]
@@ -18,6 +42,18 @@ pub enum ETabView {
     Downloads,
     Properties,
     Settings,
+    Log,
+}
+
+/// Columns of the downloads table
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum EDownloadsColumn {
+    Name,
+    Version,
+    Size,
+    Date,
+    NexusId,
+    State,
 }
 
 /// Catpuccino themes
@@ -53,18 +89,124 @@ pub struct TemplateApp {
     /// runtime cache of mod archive paths
     #[serde(skip)]
     pub downloads: Vec<ArchiveViewModel>,
+    /// only archives containing this text are listed
+    #[serde(skip)]
+    pub downloads_filter: String,
+    #[serde(skip)]
+    pub downloads_sort: EDownloadsColumn,
+    #[serde(skip)]
+    pub downloads_sort_ascending: bool,
+    /// files of nxm links waiting to be downloaded
+    #[serde(skip)]
+    pub download_queue: VecDeque<NxmLink>,
 
     /// the folder where mods are extracted to
     pub mods_library: Option<PathBuf>,
     /// info which mods are available
     pub mods: Vec<ModViewModel>,
+    /// groups of the mod list, placed before a mod
+    pub mod_separators: Vec<ModSeparator>,
+    /// the tags of the mods were detected, mods of older versions have none
+    pub are_mod_tags_detected: bool,
+    /// only mods of this category are listed
+    #[serde(skip)]
+    pub mods_category_filter: Option<String>,
+    /// only mods matching this text are listed
+    #[serde(skip)]
+    pub mods_filter: String,
+    #[serde(skip)]
+    pub mods_state_filter: EListFilter,
+    /// mods selected for bulk actions
+    #[serde(skip)]
+    pub mods_selection: HashSet<PathBuf>,
+    /// the mod shown in the properties tab
+    #[serde(skip)]
+    pub selected_mod: Option<PathBuf>,
+    /// runtime cache of the online info of each mod with a nexus id
+    #[serde(skip)]
+    pub mod_infos: HashMap<PathBuf, ModInfo>,
+    /// used to check for updates and get mod info from Nexus Mods
+    pub nexus_api_key: String,
     /// all plugins. should be populated on start
     #[serde(skip)]
     pub plugins: Vec<PluginViewModel>,
+    /// only plugins matching this text are listed
+    #[serde(skip)]
+    pub plugins_filter: String,
+    #[serde(skip)]
+    pub plugins_state_filter: EListFilter,
+    /// plugins selected for bulk actions
+    #[serde(skip)]
+    pub plugins_selection: HashSet<String>,
+    /// where a shift click selects from
+    #[serde(skip)]
+    pub plugins_last_clicked: Option<String>,
+    /// runtime cache of the file conflicts of each enabled mod
+    #[serde(skip)]
+    pub file_conflicts: HashMap<PathBuf, DataDirConflicts>,
+    /// runtime cache of the record conflicts of each enabled plugin
+    #[serde(skip)]
+    pub record_conflicts: HashMap<String, PluginConflicts>,
+    /// runtime cache of the masters each enabled plugin misses
+    #[serde(skip)]
+    pub missing_masters: HashMap<String, Vec<String>>,
     #[serde(skip)]
     pub init: bool,
-    ///
+    /// the name of the active profile
     pub current_profile: String,
+    /// runtime cache of all profile names
+    #[serde(skip)]
+    pub profiles: Vec<String>,
+    #[serde(skip)]
+    pub profile_dialog: Option<EProfileDialog>,
+    #[serde(skip)]
+    pub profile_name_input: String,
+    /// what is missing of the last imported modlist
+    #[serde(skip)]
+    pub modlist_report: Option<ModlistImport>,
+    /// the edits of the mod and plugin lists that can be undone
+    #[serde(skip)]
+    pub history: EditHistory,
+    /// the lists were edited since the cfg was written
+    #[serde(skip)]
+    pub is_cfg_outdated: bool,
+    /// the changes of the cfg entries, shown while set
+    #[serde(skip)]
+    pub cfg_diff: Option<CfgDiff>,
+    /// the profile the current one is compared with, None compares with the cfg on disk
+    #[serde(skip)]
+    pub cfg_diff_with: Option<String>,
+    #[serde(skip)]
+    pub cfg_diff_show_unchanged: bool,
+    /// a destructive action the user has to confirm
+    #[serde(skip)]
+    pub pending_confirmation: Option<EConfirmAction>,
+    #[serde(skip)]
+    pub show_trash: bool,
+    /// runtime cache of the mods in the trash of the mods library
+    #[serde(skip)]
+    pub trash: Vec<TrashedMod>,
+    /// keep a separate settings.cfg per profile instead of syncing the default one
+    pub use_profile_settings: bool,
+    /// the folder of the OpenMW executables, overrides the automatic search
+    pub openmw_dir: Option<PathBuf>,
+    /// captured output of launched processes
+    #[serde(skip)]
+    pub game_log: Arc<Mutex<Vec<String>>>,
+    #[serde(skip)]
+    pub running_processes: Vec<RunningProcess>,
+    /// picks up changes to the libraries made outside of omwmm
+    #[serde(skip)]
+    pub watcher: Option<LibraryWatcher>,
+    /// running background jobs
+    #[serde(skip)]
+    pub jobs: Vec<Job>,
+    /// receives commands from other omwmm processes
+    #[serde(skip)]
+    pub ipc: Option<IpcServer>,
+    /// commands from the command line and other processes, applied once the profile is loaded
+    #[serde(skip)]
+    pub pending_commands: Vec<EIpcCommand>,
 }
 
 impl Default for TemplateApp {
@@ -78,11 +220,51 @@ impl Default for TemplateApp {
             current_tab_view: ETabView::Plugins,
             downloads_library: None,
             downloads: vec![],
+            downloads_filter: String::new(),
+            downloads_sort: EDownloadsColumn::Name,
+            downloads_sort_ascending: true,
+            download_queue: VecDeque::new(),
             mods_library: None,
             mods: vec![],
+            mod_separators: vec![],
+            are_mod_tags_detected: false,
+            mods_category_filter: None,
+            mods_filter: String::new(),
+            mods_state_filter: EListFilter::All,
+            mods_selection: HashSet::new(),
+            selected_mod: None,
+            mod_infos: HashMap::new(),
+            nexus_api_key: String::new(),
             plugins: vec![],
+            plugins_filter: String::new(),
+            plugins_state_filter: EListFilter::All,
+            plugins_selection: HashSet::new(),
+            plugins_last_clicked: None,
+            file_conflicts: HashMap::new(),
+            record_conflicts: HashMap::new(),
+            missing_masters: HashMap::new(),
             init: false,
             current_profile: "default".to_owned(),
+            profiles: vec![],
+            profile_dialog: None,
+            profile_name_input: String::new(),
+            modlist_report: None,
+            history: EditHistory::default(),
+            is_cfg_outdated: false,
+            cfg_diff: None,
+            cfg_diff_with: None,
+            cfg_diff_show_unchanged: false,
+            pending_confirmation: None,
+            show_trash: false,
+            trash: vec![],
+            use_profile_settings: false,
+            openmw_dir: None,
+            game_log: Arc::new(Mutex::new(vec![])),
+            running_processes: vec![],
+            watcher: None,
+            jobs: vec![],
+            ipc: None,
+            pending_commands: vec![],
         }
     }
 }
@@ -98,10 +280,14 @@ impl TemplateApp {
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
-        }
-        Default::default()
+        let mut app: TemplateApp = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+            .unwrap_or_default();
+        app.load_manager_settings();
+        app.watcher = LibraryWatcher::new(&cc.egui_ctx);
+        app.ipc = IpcServer::new(&cc.egui_ctx);
+        app
     }
 
     // UI methods
@@ -128,103 +314,77 @@ impl TemplateApp {
 
     // Logic
 
-    /// updates the openmw.cfg's content= and data= entries
-    /// goes through the enabled mods and adds data= entries for each
-    /// goes through the enbaled plugins and adds content= entries for each
-    ///
+    /// updates the current profile's openmw.cfg content= and data= entries
+    /// goes through the enabled mods and adds data= entries for each, base game data is always enabled
+    /// goes through the enabled plugins and adds content= entries for each
+    /// external entries are part of the lists, so they are written back at their positions
+    /// all other lines are kept and missing fallback archives of the base game are added
     pub fn update_cfg(&mut self) -> bool {
-        // TODO use per-profile configs?
-
-        // find omw cfg
-        if let Some(cfg_path) = get_openmwcfg() {
-            // get everything that is not a content line
-            info!("Parsing cfg {} ...", cfg_path.display());
-            let mut original_cfg: Vec<String> = vec![];
-            if let Ok(lines) = common::read_lines(&cfg_path) {
-                for line in lines.flatten() {
-                    // parse each line
-                    if line.starts_with("data=") {
-                        // TODO can we check this better?
-                        if line.ends_with("Data Files\"") {
-                            original_cfg.push(line);
-                        }
-                    } else if line.starts_with("content=") {
-                        // ignore vanilla esms
-                        if line.ends_with("Morrowind.esm")
-                            || line.ends_with("Bloodmoon.esm")
-                            || line.ends_with("Tribunal.esm")
-                        {
-                            original_cfg.push(line);
-                        }
-                    } else {
-                        original_cfg.push(line);
-                    }
-                }
-            } else {
-                error!("Could not parse cfg file {}", cfg_path.display());
-                return false;
-            }
-            // reassemble cfg
-            if let Ok(mut file) = File::create(&cfg_path) {
-                // write original lines
-                for line in original_cfg {
-                    // TODO proper eol
-                    let line_with_eol = format!("{}\n", line);
-                    match file.write(line_with_eol.as_bytes()) {
-                        Ok(_) => {}
-                        Err(err) => warn!("Error writing line {}: {}", line, err),
-                    }
-                }
+        let (data_dirs, plugins) = self.get_cfg_entries();
+        let base_game_dirs: Vec<PathBuf> = self
+            .mods
+            .iter()
+            .filter(|m| m.tag == EModTag::BaseGame)
+            .map(|m| m.full_name.to_owned())
+            .collect();
+        let is_written = write_cfg(
+            &self.get_profile_cfg(),
+            &data_dirs,
+            &plugins,
+            &base_game_dirs,
+        );
+        if is_written {
+            self.is_cfg_outdated = false;
+        }
+        is_written
+    }
 
-                // write data
-                if let Some(_library) = self.mods_library.clone() {
-                    for m in &self.mods.iter().filter(|p| p.enabled).collect::<Vec<_>>() {
-                        // TODO proper eol
-                        // get full path
-                        let data_line = format!("data=\"{}\"\n", m.full_name.to_string_lossy());
-                        match file.write(data_line.as_bytes()) {
-                            Ok(_) => {}
-                            Err(err) => {
-                                warn!("Error writing plugin {}: {}", m.full_name.display(), err)
-                            }
-                        }
-                    }
-                }
+    /// the data directories and content files update_cfg writes, in load order
+    pub(crate) fn get_cfg_entries(&self) -> (Vec<PathBuf>, Vec<String>) {
+        self.get_profile_lists().get_cfg_entries()
+    }
 
-                // write plugins
-                for p in &self
-                    .plugins
-                    .iter()
-                    .filter(|p| p.enabled)
-                    .collect::<Vec<_>>()
-                {
-                    // TODO proper eol
-                    let content_line = format!("content={}\n", p.name);
-                    match file.write(content_line.as_bytes()) {
-                        Ok(_) => {}
-                        Err(err) => warn!("Error writing plugin {}: {}", p.name, err),
-                    }
-                }
-            } else {
-                error!("Could not write cfg file {}", cfg_path.display());
-                return false;
-            }
+    /// inserts the data= entries of the profile cfg that are not in the mod list as external mods
+    /// an external mod is placed after the entry that precedes it in the cfg
+    /// the default openmw.cfg is only read when the profile cfg is created from it,
+    /// merging it later would bring back the mods that were removed from the profile
+    fn load_external_mods(&mut self) {
+        let Some(info) = common::parse_cfg(self.get_profile_cfg()) else { return };
+        let known: Vec<PathBuf> = self.mods.iter().map(|m| m.full_name.to_owned()).collect();
+        for (i, data_path) in get_external_entries(&known, info.data) {
+            info!("Found external data dir {}", data_path.display());
+            insert_mod(
+                &mut self.mods,
+                i,
+                ModViewModel {
+                    full_name: data_path,
+                    enabled: true,
+                    tag: EModTag::Unmanaged,
+                    external: true,
+                    meta_data: None,
+                    ..Default::default()
+                },
+            );
         }
-
-        false
     }
 
-    /// Gets a path to the current profile dir and creates it if it doesn't exist
-    pub fn get_current_profile_dir(&self) -> PathBuf {
-        let current_profile_dir = dirs::config_dir()
-            .unwrap()
-            .join("omwmm")
-            .join("profiles")
-            .join(self.current_profile.as_str());
-        if !current_profile_dir.exists() {
-            fs::create_dir_all(&current_profile_dir).expect("Failed to create current profile dir");
+    /// inserts the content= entries of the profile cfg that are not in the plugin list as external plugins
+    /// an external plugin is placed after the entry that precedes it in the cfg
+    fn load_external_plugins(&mut self) {
+        let Some(info) = common::parse_cfg(self.get_profile_cfg()) else { return };
+        let known: Vec<String> = self.plugins.iter().map(|p| p.name.to_owned()).collect();
+        for (i, name) in get_external_entries(&known, info.plugins) {
+            info!("Found external content file {}", name);
+            self.plugins.insert(
+                i,
+                PluginViewModel {
+                    name,
+                    enabled: true,
+                    external: true,
+                    ..Default::default()
+                },
+            );
         }
-        current_profile_dir
     }
 
     /// initialize enabled mods and plugins from the current profile
@@ -234,153 +394,252 @@ impl TemplateApp {
             return;
         }
 
+        // edits of another profile can not be undone
+        self.history.clear();
+        self.is_cfg_outdated = false;
+
+        // mods of older versions have no ids
+        assign_mod_ids(&mut self.mods);
+        // external entries are read from the profile cfg again
+        self.mods.retain(|m| !m.external);
+
         // fix broken links
-        if !self.mods.is_empty() {
-            let mut to_delete: Vec<_> = vec![];
-            for (i, m) in self.mods.iter().enumerate() {
-                if !m.full_name.exists() {
-                    to_delete.push(i);
-                }
+        let broken = get_broken_mods(&self.mods);
+        for id in broken.iter() {
+            if let Some(mod_info) = self.get_mod(*id) {
+                warn!("Mod {} does not exist", mod_info.full_name.display());
             }
-            for (j, _d) in to_delete.iter().enumerate() {
-                self.mods.remove(j);
+        }
+        self.remove_mods(&broken);
+        // mods of older versions were stored without a tag, detect it once
+        if !self.are_mod_tags_detected {
+            for mod_info in self.mods.iter_mut() {
+                mod_info.tag = get_mod_tag(&mod_info.full_name, &self.mods_library);
             }
+            self.are_mod_tags_detected = true;
         }
         // if the app mods are empty, we import the openmw.cfg
+        let mut imported_plugins: Option<Vec<String>> = None;
         if self.mods.is_empty() {
             if let Some(cfg_path) = common::get_openmwcfg() {
                 if let Some(info) = common::parse_cfg(cfg_path) {
                     for data_path in info.data {
-                        // TODO handle vanilla dirs and special tags
                         if data_path.exists() {
-                            self.mods.push(ModViewModel {
-                                full_name: data_path,
-                                enabled: false,
-                            });
+                            let tag = get_mod_tag(&data_path, &self.mods_library);
+                            push_mod(
+                                &mut self.mods,
+                                ModViewModel {
+                                    full_name: data_path,
+                                    enabled: true,
+                                    tag,
+                                    external: false,
+                                    meta_data: None,
+                                    ..Default::default()
+                                },
+                            );
                         }
                     }
+                    // the imported cfg is the initial state of the profile
+                    self.update_profile_mods();
+                    imported_plugins = Some(info.plugins);
                 }
             }
         }
 
-        // load the mod list and plugin list from the profiles folder
-        let current_profile_dir = self.get_current_profile_dir();
-        let mods_list_path = current_profile_dir.join("mods.txt");
-        if mods_list_path.exists() {
-            // load
-            if let Ok(lines) = common::read_lines(&mods_list_path) {
-                for mod_name in lines.flatten() {
-                    // update mods enabled state
-                    if let Some(info) = self
-                        .mods
-                        .iter_mut()
-                        .find(|p| p.full_name.to_string_lossy() == mod_name)
-                    {
-                        info.enabled = true;
-                    }
-                }
-            }
-        }
+        // load the mod order and enabled state from the profiles folder
+        self.load_profile_mods();
+        self.load_external_mods();
 
         // populate downloads
-        self.downloads.clear();
-        if let Some(downloads_path) = self.downloads_library.clone() {
-            refresh_downloads(downloads_path, &mut self.downloads);
-        }
+        self.scan_downloads();
 
         // populate plugins
         self.plugins.clear();
-        for mod_info in self.mods.iter() {
-            let plugins = get_plugins_in_folder(&mod_info.full_name, true);
-            for p in plugins {
-                if let Some(plugin_name) = p.file_name() {
-                    let vm = PluginViewModel {
-                        name: plugin_name.to_string_lossy().into(),
-                        enabled: false,
-                    };
-                    if !self.plugins.contains(&vm) {
-                        self.plugins.push(vm);
-                    }
-                } else {
-                    warn!("Invalid filename: {}", p.display());
-                }
+        self.update_plugins();
+
+        // the imported content files are the initial plugin state of the profile
+        if let Some(content) = imported_plugins {
+            self.plugins.sort_by_key(|p| {
+                content
+                    .iter()
+                    .position(|c| *c == p.name)
+                    .unwrap_or(usize::MAX)
+            });
+            for p in self.plugins.iter_mut() {
+                p.enabled = content.contains(&p.name);
             }
+            self.update_profile_plugins();
         }
 
-        // update plugins enabled state
-        let plugins_list_path = current_profile_dir.join("plugins.txt");
-        if plugins_list_path.exists() {
-            // load
-            if let Ok(lines) = common::read_lines(&plugins_list_path) {
-                for plugin_name in lines.flatten() {
-                    if let Some(info) = self.plugins.iter_mut().find(|p| p.name == plugin_name) {
-                        info.enabled = true;
-                    }
-                }
-            } else {
-                warn!("Could not read file: {}", plugins_list_path.display());
+        // load the plugin order and enabled state from the profiles folder
+        self.load_profile_plugins();
+        self.load_external_plugins();
+
+        self.update_profile_settings();
+        self.refresh_profiles();
+        self.update_file_conflicts();
+        self.update_record_conflicts();
+        self.update_watched_paths();
+        self.load_cached_mod_infos();
+        // the cfg may have been changed by another tool
+        self.is_cfg_outdated = !self.get_cfg_diff(None).is_empty();
+
+        self.init = true;
+    }
+
+    /// recomputes the plugin list from the mods and keeps the user's order
+    /// new plugins are appended disabled unless they are base game plugins, plugins of removed mods are dropped
+    /// plugins of disabled mods stay in the list and are marked
+    pub(crate) fn update_plugins(&mut self) {
+        let mut lists = ProfileLists {
+            mods: self
+                .mods
+                .iter()
+                .filter(|m| !m.external)
+                .map(|m| (m.full_name.to_owned(), m.enabled))
+                .collect(),
+            plugins: self
+                .plugins
+                .iter()
+                .filter(|p| !p.external)
+                .map(|p| (p.name.to_owned(), p.enabled))
+                .collect(),
+        };
+        lists.update_plugins_with(|path| self.is_base_game(path));
+        let provided = lists.get_provided_plugins();
+
+        self.plugins
+            .retain(|p| p.external || lists.plugins.iter().any(|(name, _)| *name == p.name));
+        for (name, enabled) in lists.plugins {
+            if !self.plugins.iter().any(|p| p.name == name) {
+                self.plugins.push(PluginViewModel {
+                    name,
+                    enabled,
+                    ..Default::default()
+                });
             }
         }
+        for p in self.plugins.iter_mut().filter(|p| !p.external) {
+            p.provider_disabled = !provided.contains(&p.name);
+        }
+    }
 
-        self.init = true;
+    /// the mod an archive is installed as and if it is the same version
+    pub(crate) fn get_install_state(
+        &self,
+        archive: &ArchiveViewModel,
+    ) -> (EInstallState, Option<PathBuf>) {
+        // installed from this archive, or by hand into the default folder
+        let default_path = self.mods_library.as_ref().map(|library| {
+            let mut path = library.join(&archive.file_name);
+            path.set_extension("");
+            path
+        });
+        if let Some(mod_info) = self.mods.iter().find(|m| {
+            m.meta_data
+                .as_ref()
+                .and_then(|meta| meta.installation_file.as_ref())
+                .is_some_and(|file| *file == archive.file_name)
+                || Some(&m.full_name) == default_path.as_ref()
+        }) {
+            return (
+                EInstallState::Installed,
+                Some(mod_info.full_name.to_owned()),
+            );
+        }
+
+        // another file of the same nexus mod with an older version
+        let Some(meta) = &archive.meta_data else {
+            return (EInstallState::NotInstalled, None);
+        };
+        let (Some(mod_id), Some(version)) = (meta.mod_id, &meta.version) else {
+            return (EInstallState::NotInstalled, None);
+        };
+        let older = self.mods.iter().find(|m| {
+            m.meta_data.as_ref().is_some_and(|m| {
+                m.mod_id == Some(mod_id)
+                    && m.version
+                        .as_ref()
+                        .is_some_and(|v| compare_versions(v, version) == Ordering::Less)
+            })
+        });
+        match older {
+            Some(mod_info) => (
+                EInstallState::InstalledOlder,
+                Some(mod_info.full_name.to_owned()),
+            ),
+            None => (EInstallState::NotInstalled, None),
+        }
     }
 
-    /// serializes the mods to the profile
-    pub(crate) fn update_profile_mods(&self) {
-        let mods_paths_list: Vec<_> = self
-            .mods
-            .iter()
-            .filter(|f| f.enabled)
-            .map(|e| format!("{}\n", e.full_name.to_string_lossy()))
-            .collect();
-        // save the list to file
-        let current_profile_dir = self.get_current_profile_dir();
-        let mods_list_path = current_profile_dir.join("mods.txt");
-        if let Ok(mut f) = fs::File::create(&mods_list_path) {
-            for mod_name in mods_paths_list {
-                match f.write(mod_name.as_bytes()) {
-                    Ok(_) => {}
-                    Err(_err) => {
-                        warn!("Could not write line: {}", mod_name);
-                    }
+    /// the newest archive of the same nexus mod for each mod with an older version installed
+    /// returns the archive file name and its version by mod path
+    pub(crate) fn get_mod_updates(&self) -> HashMap<PathBuf, (String, String)> {
+        let mut updates: HashMap<PathBuf, (String, String)> = HashMap::new();
+        for mod_info in self.mods.iter() {
+            let Some(meta) = &mod_info.meta_data else { continue };
+            let (Some(mod_id), Some(installed_version)) = (meta.mod_id, &meta.version) else { continue };
+            for archive in self.downloads.iter() {
+                let Some(archive_meta) = &archive.meta_data else { continue };
+                let Some(version) = &archive_meta.version else { continue };
+                if archive_meta.mod_id != Some(mod_id)
+                    || compare_versions(version, installed_version) != Ordering::Greater
+                {
+                    continue;
+                }
+                let is_newest = updates.get(&mod_info.full_name).map_or(true, |(_, v)| {
+                    compare_versions(version, v) == Ordering::Greater
+                });
+                if is_newest {
+                    updates.insert(
+                        mod_info.full_name.to_owned(),
+                        (archive.file_name.to_owned(), version.to_owned()),
+                    );
                 }
             }
-        } else {
-            warn!("Could not create file: {}", mods_list_path.display());
         }
+        updates
+    }
+}
+
+/// detects how a data directory is managed
+/// folders with the vanilla masters or archives are base game data, folders in the mod library are managed
+pub(crate) fn get_mod_tag(data_path: &Path, mods_library: &Option<PathBuf>) -> EModTag {
+    if is_base_game_dir(data_path) {
+        return EModTag::BaseGame;
+    }
+    match mods_library {
+        Some(library) if data_path.starts_with(library) => EModTag::Managed,
+        _ => EModTag::Unmanaged,
     }
 }
 
 /// refreshes the downloads list by walking the downloads library
-fn refresh_downloads(library_path: PathBuf, downloads: &mut Vec<ArchiveViewModel>) {
-    // TODO make proper viewmodels
-    // get all plugins
+pub(crate) fn refresh_downloads(library_path: PathBuf, downloads: &mut Vec<ArchiveViewModel>) {
+    // get all archives
     if let Ok(archives) = fs::read_dir(library_path) {
         archives.for_each(|p| {
             if let Ok(file) = p {
                 let file_path = file.path();
-                if file_path.is_file() {
-                    if let Some(ext_os) = file_path.extension() {
-                        let ext = ext_os.to_ascii_lowercase();
-                        if ext == "zip" || ext == "7z" || ext == "rar" {
-                            // check metadata
-                            let mut meta_path = file_path.clone();
-                            let meta_ext = format!("{}.meta", ext_os.to_str().unwrap());
-                            meta_path.set_extension(meta_ext);
-
-                            let mut meta_data: Option<ModNexusMetaData> = None;
-                            if meta_path.exists() {
-                                meta_data = parse_mod_meta(meta_path);
-                            }
-
-                            let vm = ArchiveViewModel {
-                                file_name: file_path.file_name().unwrap().to_string_lossy().into(),
-                                //installed: false,
-                                meta_data,
-                            };
-                            downloads.push(vm);
-                        }
+                // only archives that can be installed, e.g. no rar
+                if file_path.is_file() && is_supported_archive(&file_path) {
+                    // check metadata
+                    let meta_path = get_meta_path(&file_path);
+
+                    let mut meta_data: Option<ModNexusMetaData> = None;
+                    if meta_path.exists() {
+                        meta_data = parse_mod_meta(meta_path);
                     }
+
+                    let metadata = file.metadata().ok();
+                    let vm = ArchiveViewModel {
+                        file_name: file_path.file_name().unwrap().to_string_lossy().into(),
+                        size: metadata.as_ref().map_or(0, |m| m.len()),
+                        modified: metadata.and_then(|m| m.modified().ok()),
+                        meta_data,
+                        hash: None,
+                    };
+                    downloads.push(vm);
                 }
             }
         });
@@ -389,28 +648,12 @@ fn refresh_downloads(library_path: PathBuf, downloads: &mut Vec<ArchiveViewModel
 
 /// parses an MO2 mod meta file
 /// TODO cache this
-fn parse_mod_meta(meta_path: PathBuf) -> Option<ModNexusMetaData> {
-    if let Ok(lines) = common::read_lines(&meta_path) {
-        let mut meta = ModNexusMetaData::default();
-        for line in lines.flatten() {
-            if let Some(mod_id) = line.strip_prefix("modID=") {
-                if let Ok(id) = mod_id.parse() {
-                    meta.mod_id = Some(id);
-                }
-            }
-            if let Some(description) = line.strip_prefix("description=") {
-                meta.description = Some(description.to_owned());
-            }
-            if let Some(mod_name) = line.strip_prefix("modName=") {
-                meta.mod_name = Some(mod_name.to_owned());
-            }
-            if let Some(version) = line.strip_prefix("version=") {
-                meta.version = Some(version.to_owned());
-            }
+pub(crate) fn parse_mod_meta(meta_path: PathBuf) -> Option<ModNexusMetaData> {
+    match read_meta(&meta_path) {
+        Ok(meta) => Some(meta),
+        Err(err) => {
+            warn!("Could not read meta file {}: {}", meta_path.display(), err);
+            None
         }
-        return Some(meta);
-    } else {
-        warn!("Could not read meta file: {}", meta_path.display());
     }
-    None
 }
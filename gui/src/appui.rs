@@ -7,6 +7,7 @@ impl eframe::App for TemplateApp {
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
         // general storage save
         eframe::set_value(storage, eframe::APP_KEY, self);
+        self.save_manager_settings();
     }
 
     /// Called each time the UI needs repainting, which may be many times per second.
@@ -19,6 +20,45 @@ impl eframe::App for TemplateApp {
 
         self.init_profile();
 
+        // changes to the libraries
+        self.poll_watcher();
+
+        // launched processes
+        if !self.running_processes.is_empty() {
+            self.update_running_processes();
+            // keep polling the captured output
+            ctx.request_repaint_after(std::time::Duration::from_millis(500));
+        }
+
+        // commands of other instances and queued downloads
+        self.update_commands(frame);
+        self.update_downloads();
+
+        // background jobs
+        if !self.jobs.is_empty() {
+            self.update_jobs();
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+        }
+
+        // undo and redo list edits, text fields have their own undo
+        if !ctx.wants_keyboard_input() {
+            let (undo, redo) = ctx.input_mut(|i| {
+                (
+                    i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z),
+                    i.consume_key(egui::Modifiers::COMMAND, egui::Key::Y)
+                        || i.consume_key(
+                            egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                            egui::Key::Z,
+                        ),
+                )
+            });
+            if undo {
+                self.undo();
+            } else if redo {
+                self.redo();
+            }
+        }
+
         // Top Panel
         self.update_top_panel(ctx, frame);
 
@@ -39,12 +79,28 @@ impl eframe::App for TemplateApp {
             });
         });
 
+        // progress of background jobs
+        egui::TopBottomPanel::bottom("jobs_panel").show_animated(
+            ctx,
+            !self.jobs.is_empty(),
+            |ui| {
+                self.jobs_view(ui);
+            },
+        );
+
         // Side Panel
         self.update_right_side_panel(ctx);
 
         // Central Panel
         self.update_central_panel(ctx);
 
+        // dialogs
+        self.profile_dialog_view(ctx);
+        self.modlist_report_view(ctx);
+        self.trash_view(ctx);
+        self.cfg_diff_view(ctx);
+        self.confirm_dialog_view(ctx);
+
         // notifications
         self.toasts.show(ctx);
     }
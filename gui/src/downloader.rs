@@ -0,0 +1,261 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use common::meta::{get_meta_path, write_meta};
+use log::{info, warn};
+
+use crate::{
+    jobs::{get_job_error, EJobOutput},
+    metadata::{is_valid_game_name, NexusProvider},
+    ModNexusMetaData, TemplateApp,
+};
+
+/// download jobs are named like this, only one runs at a time
+const DOWNLOAD_JOB: &str = "Downloading";
+
+/// A Nexus Mods download link as opened from the website
+/// nxm://morrowind/mods/123/files/456?key=abc&expires=1700000000&user_id=1
+#[derive(Debug, Clone, PartialEq)]
+pub struct NxmLink {
+    pub game: String,
+    pub mod_id: usize,
+    pub file_id: usize,
+    /// free accounts can only download with the key and expiry of the link
+    pub key: Option<String>,
+    pub expires: Option<u64>,
+}
+
+impl NxmLink {
+    pub fn parse(url: &str) -> Option<Self> {
+        let rest = url.trim().strip_prefix("nxm://")?;
+        let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let parts: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+        let [game, "mods", mod_id, "files", file_id] = parts[..] else { return None; };
+        let game = game.to_lowercase();
+        if !is_valid_game_name(&game) {
+            return None;
+        }
+
+        let mut link = Self {
+            game,
+            mod_id: mod_id.parse().ok()?,
+            file_id: file_id.parse().ok()?,
+            key: None,
+            expires: None,
+        };
+        for pair in query.split('&') {
+            match pair.split_once('=') {
+                Some(("key", key)) => link.key = Some(key.to_owned()),
+                Some(("expires", expires)) => link.expires = expires.parse().ok(),
+                _ => {}
+            }
+        }
+        Some(link)
+    }
+}
+
+/// the file a download is written to until it is complete: mod.7z -> mod.7z.part
+pub fn get_download_part_path(destination: &Path) -> PathBuf {
+    let mut part_path = destination.as_os_str().to_owned();
+    part_path.push(".part");
+    PathBuf::from(part_path)
+}
+
+/// Downloads a url to a file.
+/// The transfer is written to a .part file, an existing .part file is resumed if the server supports it.
+/// The progress callback gets the downloaded and total bytes, returning false cancels the download.
+pub fn download_file<F>(
+    agent: &ureq::Agent,
+    url: &str,
+    destination: &Path,
+    mut on_progress: F,
+) -> io::Result<()>
+where
+    F: FnMut(u64, u64) -> bool,
+{
+    let part_path = get_download_part_path(destination);
+    let offset = fs::metadata(&part_path).map_or(0, |m| m.len());
+
+    let mut request = agent.get(url);
+    if offset > 0 {
+        request = request.set("Range", &format!("bytes={}-", offset));
+    }
+    let response = match request.call() {
+        Ok(response) => response,
+        // the part file is broken or the file changed, start over
+        Err(ureq::Error::Status(416, _)) => {
+            fs::remove_file(&part_path)?;
+            return download_file(agent, url, destination, on_progress);
+        }
+        Err(err) => return Err(io::Error::new(io::ErrorKind::Other, err.to_string())),
+    };
+
+    // servers without range support send the whole file
+    let is_resumed = response.status() == 206;
+    let mut done = if is_resumed { offset } else { 0 };
+    let total = response
+        .header("Content-Length")
+        .and_then(|l| l.parse::<u64>().ok())
+        .map_or(0, |l| l + done);
+    if is_resumed {
+        info!("Resuming download of {} at {} bytes", url, offset);
+    }
+    let mut file = if is_resumed {
+        OpenOptions::new().append(true).open(&part_path)?
+    } else {
+        File::create(&part_path)?
+    };
+
+    let mut reader = response.into_reader();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..read])?;
+        done += read as u64;
+        if !on_progress(done, total) {
+            // the part file is kept to resume later
+            return Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "download cancelled",
+            ));
+        }
+    }
+    file.flush()?;
+    drop(file);
+
+    if total > 0 && done != total {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("downloaded {} of {} bytes", done, total),
+        ));
+    }
+    fs::rename(&part_path, destination)
+}
+
+/// an agent for large transfers, only connecting and stalled reads time out
+fn get_download_agent() -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout_connect(Duration::from_secs(30))
+        .timeout_read(Duration::from_secs(60))
+        .build()
+}
+
+/// Registers omwmm as the handler of nxm:// links for the current user
+#[cfg(target_os = "linux")]
+pub fn register_nxm_handler() -> io::Result<()> {
+    const DESKTOP_FILE: &str = "omwmm-nxm.desktop";
+
+    let exe = std::env::current_exe()?;
+    let Some(applications) = dirs::data_dir().map(|d| d.join("applications")) else {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no data directory"));
+    };
+    fs::create_dir_all(&applications)?;
+    let desktop_entry = format!(
+        "[Desktop Entry]\nType=Application\nName=omwmm\nComment=Download with the OpenMW mod manager\nExec=\"{}\" %u\nTerminal=false\nNoDisplay=true\nMimeType=x-scheme-handler/nxm;\n",
+        exe.display()
+    );
+    fs::write(applications.join(DESKTOP_FILE), desktop_entry)?;
+
+    let status = std::process::Command::new("xdg-mime")
+        .args(["default", DESKTOP_FILE, "x-scheme-handler/nxm"])
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("xdg-mime failed: {}", status),
+        ));
+    }
+    info!("Registered nxm handler {}", exe.display());
+    Ok(())
+}
+
+impl TemplateApp {
+    /// queues a file of an nxm:// link for download
+    pub fn queue_nxm_link(&mut self, url: &str) {
+        let Some(link) = NxmLink::parse(url) else {
+            warn!("Invalid nxm link: {}", url);
+            self.toasts.error(format!("Invalid nxm link: {}", url));
+            return;
+        };
+        if self.downloads_library.is_none() {
+            self.toasts.error("Choose a downloads library first");
+            return;
+        }
+        if self.download_queue.contains(&link) {
+            return;
+        }
+        info!(
+            "Queued download of mod {} file {}",
+            link.mod_id, link.file_id
+        );
+        self.toasts
+            .info(format!("Queued download of mod {}", link.mod_id));
+        self.download_queue.push_back(link);
+    }
+
+    /// starts the next queued download
+    pub(crate) fn update_downloads(&mut self) {
+        if self.download_queue.is_empty()
+            || self.jobs.iter().any(|j| j.name.starts_with(DOWNLOAD_JOB))
+        {
+            return;
+        }
+        let Some(downloads) = self.downloads_library.clone() else { return };
+        let Some(link) = self.download_queue.pop_front() else { return };
+
+        let provider = NexusProvider::new(&self.nexus_api_key);
+        let name = format!("{} mod {} file {}", DOWNLOAD_JOB, link.mod_id, link.file_id);
+        self.spawn_job(&name, move |job| {
+            job.set_progress(0.0, "Requesting download");
+            let file_info = provider.get_file_info(&link.game, link.mod_id, link.file_id)?;
+            let Some(file_name) = file_info
+                .file_name
+                .as_deref()
+                .and_then(|f| Path::new(f).file_name())
+            else {
+                return Err("the file has no name".to_owned());
+            };
+            let destination = downloads.join(file_name);
+            let urls = provider.get_download_links(&link)?;
+            let Some(url) = urls.first() else {
+                return Err("no download server".to_owned());
+            };
+
+            download_file(&get_download_agent(), url, &destination, |done, total| {
+                job.set_progress(
+                    done as f32 / total.max(1) as f32,
+                    format!("{} / {} MB", done / 1_000_000, total / 1_000_000),
+                );
+                !job.is_cancelled()
+            })
+            .map_err(get_job_error)?;
+
+            // MO2 compatible metadata for update checks
+            let meta = ModNexusMetaData {
+                game_name: Some(link.game.to_owned()),
+                mod_id: Some(link.mod_id),
+                file_id: Some(link.file_id),
+                url: Some(format!(
+                    "https://www.nexusmods.com/{}/mods/{}",
+                    link.game, link.mod_id
+                )),
+                name: file_info.name,
+                version: file_info.version,
+                installed: Some(false),
+                uninstalled: Some(false),
+                ..Default::default()
+            };
+            if let Err(err) = write_meta(get_meta_path(&destination), &meta) {
+                warn!("Could not write meta file: {}", err);
+            }
+            Ok(EJobOutput::Downloaded(destination))
+        });
+    }
+}
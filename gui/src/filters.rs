@@ -0,0 +1,80 @@
+/// States the mods and plugins lists can be filtered by
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum EListFilter {
+    #[default]
+    All,
+    Enabled,
+    Disabled,
+    /// mods with file conflicts, plugins with record conflicts
+    Conflicting,
+    /// plugins whose masters are not loaded before them
+    MissingMasters,
+    /// mods with a newer version in the downloads library or online
+    Updatable,
+}
+
+impl EListFilter {
+    pub const MODS: [EListFilter; 5] = [
+        EListFilter::All,
+        EListFilter::Enabled,
+        EListFilter::Disabled,
+        EListFilter::Conflicting,
+        EListFilter::Updatable,
+    ];
+    pub const PLUGINS: [EListFilter; 5] = [
+        EListFilter::All,
+        EListFilter::Enabled,
+        EListFilter::Disabled,
+        EListFilter::Conflicting,
+        EListFilter::MissingMasters,
+    ];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            EListFilter::All => "All",
+            EListFilter::Enabled => "Enabled",
+            EListFilter::Disabled => "Disabled",
+            EListFilter::Conflicting => "Conflicting",
+            EListFilter::MissingMasters => "Missing masters",
+            EListFilter::Updatable => "Updatable",
+        }
+    }
+}
+
+/// case insensitive fuzzy match: the characters of the pattern appear in the text in order
+/// whitespace in the pattern is ignored, an empty pattern matches everything
+pub fn is_fuzzy_match(pattern: &str, text: &str) -> bool {
+    let mut text = text.chars().flat_map(char::to_lowercase);
+    pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .all(|p| text.any(|c| c == p))
+}
+
+/// the items from one item to another, both included, in list order
+/// empty if one of them is not in the list
+pub fn get_range<T: PartialEq + Clone>(items: &[T], from: &T, to: &T) -> Vec<T> {
+    let (Some(a), Some(b)) = (
+        items.iter().position(|i| i == from),
+        items.iter().position(|i| i == to),
+    ) else {
+        return vec![];
+    };
+    items[a.min(b)..=a.max(b)].to_vec()
+}
+
+/// moves the selected items to the start or the end of the list, keeping their order
+pub fn move_selected<T, F>(items: &mut Vec<T>, is_selected: F, to_start: bool)
+where
+    F: Fn(&T) -> bool,
+{
+    let (selected, rest): (Vec<T>, Vec<T>) = std::mem::take(items)
+        .into_iter()
+        .partition(|i| is_selected(i));
+    *items = if to_start {
+        selected.into_iter().chain(rest).collect()
+    } else {
+        rest.into_iter().chain(selected).collect()
+    };
+}
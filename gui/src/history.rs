@@ -0,0 +1,255 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+};
+
+use crate::{mods::ModId, ModSeparator, TemplateApp};
+
+/// how many edits can be undone
+pub const MAX_HISTORY: usize = 100;
+
+/// The mod order with the separators placed in it
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ModOrder {
+    pub ids: Vec<ModId>,
+    pub separators: Vec<ModSeparator>,
+}
+
+/// An edit of the mod or plugin list that can be undone
+#[derive(Debug, Clone, PartialEq)]
+pub enum EListEdit {
+    /// mods enabled or disabled: id, enabled before, enabled after
+    SetModsEnabled(Vec<(ModId, bool, bool)>),
+    /// mods or separators moved, separators that are added or removed are separate edits
+    MoveMods { before: ModOrder, after: ModOrder },
+    /// a separator was added to the separators at an index
+    AddSeparator {
+        index: usize,
+        separator: ModSeparator,
+    },
+    /// a separator was removed from the separators at an index
+    RemoveSeparator {
+        index: usize,
+        separator: ModSeparator,
+    },
+    /// plugins enabled or disabled: name, enabled before, enabled after
+    SetPluginsEnabled(Vec<(String, bool, bool)>),
+    /// plugins moved
+    MovePlugins {
+        before: Vec<String>,
+        after: Vec<String>,
+    },
+}
+
+impl EListEdit {
+    /// the edit that undoes this one
+    pub fn get_inverse(&self) -> EListEdit {
+        match self {
+            EListEdit::SetModsEnabled(changes) => {
+                EListEdit::SetModsEnabled(changes.iter().map(|(id, b, a)| (*id, *a, *b)).collect())
+            }
+            EListEdit::MoveMods { before, after } => EListEdit::MoveMods {
+                before: after.clone(),
+                after: before.clone(),
+            },
+            EListEdit::AddSeparator { index, separator } => EListEdit::RemoveSeparator {
+                index: *index,
+                separator: separator.clone(),
+            },
+            EListEdit::RemoveSeparator { index, separator } => EListEdit::AddSeparator {
+                index: *index,
+                separator: separator.clone(),
+            },
+            EListEdit::SetPluginsEnabled(changes) => EListEdit::SetPluginsEnabled(
+                changes
+                    .iter()
+                    .map(|(name, b, a)| (name.to_owned(), *a, *b))
+                    .collect(),
+            ),
+            EListEdit::MovePlugins { before, after } => EListEdit::MovePlugins {
+                before: after.clone(),
+                after: before.clone(),
+            },
+        }
+    }
+
+    /// edits that change nothing are not recorded
+    pub fn is_empty(&self) -> bool {
+        match self {
+            EListEdit::SetModsEnabled(changes) => changes.iter().all(|(_, b, a)| b == a),
+            EListEdit::MoveMods { before, after } => before == after,
+            EListEdit::AddSeparator { .. } | EListEdit::RemoveSeparator { .. } => false,
+            EListEdit::SetPluginsEnabled(changes) => changes.iter().all(|(_, b, a)| b == a),
+            EListEdit::MovePlugins { before, after } => before == after,
+        }
+    }
+
+    fn is_mods_edit(&self) -> bool {
+        matches!(
+            self,
+            EListEdit::SetModsEnabled(_) | EListEdit::MoveMods { .. }
+        )
+    }
+
+    /// separators only group the mod list, the load order stays the same
+    fn is_separator_edit(&self) -> bool {
+        matches!(
+            self,
+            EListEdit::AddSeparator { .. } | EListEdit::RemoveSeparator { .. }
+        )
+    }
+}
+
+/// The undo and redo stacks of the list edits of a profile
+#[derive(Debug, Default)]
+pub struct EditHistory {
+    undo: VecDeque<EListEdit>,
+    redo: Vec<EListEdit>,
+}
+
+impl EditHistory {
+    /// records an edit that was done, it can not be redone after a new edit
+    pub fn push(&mut self, edit: EListEdit) {
+        if edit.is_empty() {
+            return;
+        }
+        self.redo.clear();
+        self.undo.push_back(edit);
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.pop_front();
+        }
+    }
+
+    /// the edit to apply to undo the last edit
+    pub fn undo(&mut self) -> Option<EListEdit> {
+        let edit = self.undo.pop_back()?;
+        let inverse = edit.get_inverse();
+        self.redo.push(edit);
+        Some(inverse)
+    }
+
+    /// the edit to apply to redo the last undone edit
+    pub fn redo(&mut self) -> Option<EListEdit> {
+        let edit = self.redo.pop()?;
+        self.undo.push_back(edit.clone());
+        Some(edit)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+/// sorts items like the keys of an order, items that are not in it keep their place at the end
+pub fn sort_by_order<T, K, F>(items: &mut [T], order: &[K], get_key: F)
+where
+    K: Eq + Hash,
+    F: Fn(&T) -> K,
+{
+    let positions: HashMap<&K, usize> = order.iter().enumerate().map(|(i, k)| (k, i)).collect();
+    items.sort_by_key(|item| positions.get(&get_key(item)).copied().unwrap_or(usize::MAX));
+}
+
+impl TemplateApp {
+    /// the current mod order and separators
+    pub(crate) fn get_mod_order(&self) -> ModOrder {
+        ModOrder {
+            ids: self.mods.iter().map(|m| m.id).collect(),
+            separators: self.mod_separators.clone(),
+        }
+    }
+
+    /// the current plugin order
+    pub(crate) fn get_plugin_order(&self) -> Vec<String> {
+        self.plugins.iter().map(|p| p.name.to_owned()).collect()
+    }
+
+    /// records an edit the views made to the lists
+    /// the profile is saved right away, the cfg only once the changes are applied
+    pub(crate) fn record_edit(&mut self, edit: EListEdit) {
+        if edit.is_empty() {
+            return;
+        }
+        self.on_lists_changed(&edit);
+        self.history.push(edit);
+    }
+
+    /// undoes the last edit of the mod or plugin list
+    pub(crate) fn undo(&mut self) -> bool {
+        let Some(edit) = self.history.undo() else { return false };
+        self.apply_edit(&edit);
+        true
+    }
+
+    /// redoes the last undone edit of the mod or plugin list
+    pub(crate) fn redo(&mut self) -> bool {
+        let Some(edit) = self.history.redo() else { return false };
+        self.apply_edit(&edit);
+        true
+    }
+
+    /// mods and plugins that are gone since the edit are skipped
+    fn apply_edit(&mut self, edit: &EListEdit) {
+        match edit {
+            EListEdit::SetModsEnabled(changes) => {
+                for (id, _, enabled) in changes {
+                    if let Some(mod_info) = self.mods.iter_mut().find(|m| m.id == *id) {
+                        mod_info.enabled = *enabled;
+                    }
+                }
+            }
+            EListEdit::MoveMods { after, .. } => {
+                sort_by_order(&mut self.mods, &after.ids, |m| m.id);
+                self.mod_separators = after.separators.clone();
+            }
+            EListEdit::AddSeparator { index, separator } => {
+                let index = (*index).min(self.mod_separators.len());
+                self.mod_separators.insert(index, separator.clone());
+            }
+            EListEdit::RemoveSeparator { index, .. } => {
+                if *index < self.mod_separators.len() {
+                    self.mod_separators.remove(*index);
+                }
+            }
+            EListEdit::SetPluginsEnabled(changes) => {
+                for (name, _, enabled) in changes {
+                    if let Some(plugin) = self.plugins.iter_mut().find(|p| p.name == *name) {
+                        plugin.enabled = *enabled;
+                    }
+                }
+            }
+            EListEdit::MovePlugins { after, .. } => {
+                sort_by_order(&mut self.plugins, after, |p| p.name.to_owned());
+            }
+        }
+        self.on_lists_changed(edit);
+    }
+
+    /// saves the lists to the profile and marks the cfg as outdated
+    fn on_lists_changed(&mut self, edit: &EListEdit) {
+        if edit.is_separator_edit() {
+            return;
+        }
+        if edit.is_mods_edit() {
+            // update serialized mod list
+            self.update_profile_mods();
+
+            // plugins of toggled mods
+            self.update_plugins();
+            self.update_watched_paths();
+            self.update_file_conflicts();
+        }
+        self.update_profile_plugins();
+        self.update_record_conflicts();
+        self.is_cfg_outdated = true;
+    }
+}
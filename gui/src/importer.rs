@@ -0,0 +1,182 @@
+use std::{fs, path::Path};
+
+use common::{
+    import::{read_mo2_instance, read_morrowind_ini_plugins},
+    profile::{write_profile_list, MODS_LIST, PLUGINS_LIST},
+};
+use log::{error, info, warn};
+
+use crate::{
+    app::{get_mod_tag, parse_mod_meta},
+    mods::push_mod,
+    EModTag, ModViewModel, TemplateApp,
+};
+
+impl TemplateApp {
+    /// creates a profile for each profile of an MO2 instance
+    /// the mods stay in the MO2 mods folder and are added as data directories
+    /// profiles that can not be created are skipped and reported
+    pub(crate) fn import_mo2(&mut self, instance_dir: &Path) -> bool {
+        let instance = match read_mo2_instance(instance_dir) {
+            Ok(instance) => instance,
+            Err(err) => {
+                error!("Could not read MO2 instance: {}", err);
+                return false;
+            }
+        };
+
+        let mut new_mods: Vec<ModViewModel> = vec![];
+        let mut imported_profiles = vec![];
+        let mut failed_profiles = vec![];
+        for profile in instance.profiles {
+            let mut mods = vec![];
+            for (name, enabled) in profile.mods {
+                let full_name = instance.mods_dir.join(&name);
+                if !full_name.is_dir() {
+                    warn!("MO2 mod {} does not exist", full_name.display());
+                    continue;
+                }
+                let is_known = self
+                    .mods
+                    .iter()
+                    .chain(new_mods.iter())
+                    .any(|m| m.full_name == full_name);
+                if !is_known {
+                    // MO2 keeps the nexus info of each mod in a meta.ini
+                    let meta_path = full_name.join("meta.ini");
+                    let meta_data = if meta_path.exists() {
+                        parse_mod_meta(meta_path)
+                    } else {
+                        None
+                    };
+                    new_mods.push(ModViewModel {
+                        tag: get_mod_tag(&full_name, &self.mods_library),
+                        full_name: full_name.to_owned(),
+                        enabled: false,
+                        external: false,
+                        meta_data,
+                        ..Default::default()
+                    });
+                }
+                mods.push((full_name.to_string_lossy().into_owned(), enabled));
+            }
+
+            let name = self.get_import_profile_name(&profile.name, "MO2");
+            if self.write_imported_profile(&name, mods, profile.plugins) {
+                imported_profiles.push(name);
+            } else {
+                failed_profiles.push(profile.name);
+            }
+        }
+
+        if !failed_profiles.is_empty() {
+            warn!(
+                "Could not import MO2 profiles {}",
+                failed_profiles.join(", ")
+            );
+            self.toasts.warning(format!(
+                "Could not import MO2 profiles {}",
+                failed_profiles.join(", ")
+            ));
+        }
+        if imported_profiles.is_empty() {
+            warn!("No MO2 profiles imported from {}", instance_dir.display());
+            return false;
+        }
+        self.add_imported_mods(new_mods);
+        self.load_profile(&imported_profiles[0]);
+        self.refresh_profiles();
+        true
+    }
+
+    /// creates a profile from the plugin order of a Morrowind.ini as managed by Wrye Mash
+    /// Wrye Mash installs everything into Data Files, so that folder is the only data directory
+    pub(crate) fn import_morrowind_ini(&mut self, ini_path: &Path) -> bool {
+        let plugins = match read_morrowind_ini_plugins(ini_path) {
+            Ok(plugins) => plugins,
+            Err(err) => {
+                error!("Could not read {}: {}", ini_path.display(), err);
+                return false;
+            }
+        };
+        let Some(data_files) = ini_path.parent().map(|dir| dir.join("Data Files")) else { return false };
+        if !data_files.is_dir() {
+            error!("Could not find {}", data_files.display());
+            return false;
+        }
+
+        let name = self.get_import_profile_name("Wrye Mash", "Morrowind.ini");
+        let mods = vec![(data_files.to_string_lossy().into_owned(), true)];
+        let plugins = plugins.into_iter().map(|p| (p, true)).collect();
+        if !self.write_imported_profile(&name, mods, plugins) {
+            return false;
+        }
+        if !self.mods.iter().any(|m| m.full_name == data_files) {
+            self.add_imported_mods(vec![ModViewModel {
+                full_name: data_files.to_owned(),
+                enabled: true,
+                tag: EModTag::BaseGame,
+                external: false,
+                meta_data: None,
+                ..Default::default()
+            }]);
+        }
+        self.load_profile(&name);
+        self.refresh_profiles();
+        true
+    }
+
+    /// saves the current profile and adds the mods of an imported profile
+    /// the mods are only in the profile lists they were imported with
+    fn add_imported_mods(&mut self, mods: Vec<ModViewModel>) {
+        self.update_profile_mods();
+        self.update_profile_plugins();
+        for mod_info in mods {
+            push_mod(&mut self.mods, mod_info);
+        }
+    }
+
+    /// a profile name that is not taken yet
+    pub(crate) fn get_import_profile_name(&self, name: &str, source: &str) -> String {
+        let mut profile_name = name.to_owned();
+        let mut i = 1;
+        while Self::get_profiles_dir().join(&profile_name).exists() {
+            profile_name = if i == 1 {
+                format!("{} ({})", name, source)
+            } else {
+                format!("{} ({} {})", name, source, i)
+            };
+            i += 1;
+        }
+        profile_name
+    }
+
+    /// writes the mod and plugin lists of a new profile
+    /// the base game is always loaded first
+    fn write_imported_profile(
+        &self,
+        name: &str,
+        mods: Vec<(String, bool)>,
+        plugins: Vec<(String, bool)>,
+    ) -> bool {
+        let Some(profile_dir) = self.get_new_profile_dir(name) else { return false };
+        if let Err(err) = fs::create_dir_all(&profile_dir) {
+            error!("Could not create profile {}: {}", name, err);
+            return false;
+        }
+
+        let mut profile_mods: Vec<(String, bool)> = self
+            .mods
+            .iter()
+            .filter(|m| m.tag == EModTag::BaseGame)
+            .map(|m| (m.full_name.to_string_lossy().into_owned(), true))
+            .filter(|(m, _)| !mods.iter().any(|(n, _)| n == m))
+            .collect();
+        profile_mods.extend(mods);
+
+        info!("Importing profile {}", name);
+        write_profile_list(&profile_dir.join(MODS_LIST), profile_mods.into_iter());
+        write_profile_list(&profile_dir.join(PLUGINS_LIST), plugins.into_iter());
+        true
+    }
+}
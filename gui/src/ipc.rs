@@ -0,0 +1,312 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Write},
+    path::PathBuf,
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Duration,
+};
+
+use fs2::FileExt;
+use log::{info, warn};
+
+use crate::{profiles::get_omwmm_dir, TemplateApp};
+
+/// A command for the running omwmm, sent as one line over the socket
+#[derive(Debug, Clone, PartialEq)]
+pub enum EIpcCommand {
+    /// bring the window to the front
+    Focus,
+    /// download the file of an nxm:// link
+    OpenNxm(String),
+    /// install an archive
+    Install(PathBuf),
+    SwitchProfile(String),
+}
+
+impl EIpcCommand {
+    /// parses the omwmm command line: [--profile <name>] [<archive> | <nxm link>]...
+    pub fn from_args<I: Iterator<Item = String>>(mut args: I) -> Vec<Self> {
+        let mut commands = vec![];
+        while let Some(arg) = args.next() {
+            if arg == "--profile" {
+                match args.next() {
+                    Some(name) => commands.push(Self::SwitchProfile(name)),
+                    None => warn!("Missing profile name"),
+                }
+            } else if let Some(name) = arg.strip_prefix("--profile=") {
+                commands.push(Self::SwitchProfile(name.to_owned()));
+            } else if arg.starts_with("nxm://") {
+                commands.push(Self::OpenNxm(arg));
+            } else if PathBuf::from(&arg).is_file() {
+                // the running instance has another working directory
+                let path = fs::canonicalize(&arg).unwrap_or_else(|_| PathBuf::from(&arg));
+                commands.push(Self::Install(path));
+            } else {
+                warn!("Unknown argument: {}", arg);
+            }
+        }
+        commands
+    }
+
+    pub fn parse(message: &str) -> Option<Self> {
+        let (command, argument) = message.split_once(' ').unwrap_or((message, ""));
+        match command {
+            "focus" => Some(Self::Focus),
+            "nxm" => Some(Self::OpenNxm(argument.to_owned())),
+            "install" => Some(Self::Install(PathBuf::from(argument))),
+            "profile" => Some(Self::SwitchProfile(argument.to_owned())),
+            _ => None,
+        }
+    }
+
+    pub fn to_message(&self) -> String {
+        match self {
+            Self::Focus => "focus".to_owned(),
+            Self::OpenNxm(url) => format!("nxm {}", url),
+            Self::Install(path) => format!("install {}", path.display()),
+            Self::SwitchProfile(name) => format!("profile {}", name),
+        }
+    }
+}
+
+/// Held by the running omwmm so only one instance writes the app state and profiles
+pub struct InstanceLock {
+    _file: File,
+}
+
+impl InstanceLock {
+    /// returns None if another omwmm holds the lock
+    pub fn acquire() -> io::Result<Option<Self>> {
+        let dir = get_omwmm_dir();
+        fs::create_dir_all(&dir)?;
+        let file = File::create(dir.join("omwmm.lock"))?;
+        match file.try_lock_exclusive() {
+            Ok(_) => Ok(Some(Self { _file: file })),
+            Err(err) if err.kind() == fs2::lock_contended_error().kind() => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Receives commands from other omwmm processes, e.g. nxm links opened in a browser
+pub struct IpcServer {
+    messages: Receiver<String>,
+}
+
+impl IpcServer {
+    /// listens on the omwmm socket and repaints the ui when a command arrives
+    pub fn new(ctx: &egui::Context) -> Option<Self> {
+        const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+        let (listener, token) = match platform::bind() {
+            Ok(listener) => listener,
+            Err(err) => {
+                warn!("Could not listen for other instances: {}", err);
+                return None;
+            }
+        };
+
+        let (sender, messages) = mpsc::channel();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        warn!("Invalid connection: {}", err);
+                        continue;
+                    }
+                };
+                // a client that does not send anything must not block the others
+                if let Err(err) = stream.set_read_timeout(Some(READ_TIMEOUT)) {
+                    warn!("Invalid connection: {}", err);
+                    continue;
+                }
+                let (sender, ctx, token) = (sender.clone(), ctx.clone(), token.clone());
+                thread::spawn(move || {
+                    let mut lines = BufReader::new(stream).lines().map_while(Result::ok);
+                    // other users can connect to a local port, they do not know the token
+                    if let Some(token) = &token {
+                        if lines.next().as_ref() != Some(token) {
+                            warn!("Rejected a connection without the token");
+                            return;
+                        }
+                    }
+                    for line in lines {
+                        if sender.send(line).is_err() {
+                            return;
+                        }
+                        ctx.request_repaint();
+                    }
+                });
+            }
+        });
+        Some(Self { messages })
+    }
+
+    /// returns all commands received since the last call
+    pub fn get_commands(&self) -> Vec<EIpcCommand> {
+        self.messages
+            .try_iter()
+            .filter_map(|message| {
+                let command = EIpcCommand::parse(&message);
+                if command.is_none() {
+                    warn!("Unknown command: {}", message);
+                }
+                command
+            })
+            .collect()
+    }
+}
+
+/// sends commands to the running omwmm
+/// it may still be starting up, so connecting is retried for a few seconds
+pub fn send_to_running_instance(commands: &[EIpcCommand]) -> io::Result<()> {
+    const RETRIES: usize = 20;
+
+    let mut stream = platform::connect();
+    for _ in 0..RETRIES {
+        if stream.is_ok() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(250));
+        stream = platform::connect();
+    }
+    let mut stream = stream?;
+    for command in commands {
+        writeln!(stream, "{}", command.to_message())?;
+    }
+    stream.flush()?;
+    info!("Sent {} commands to the running instance", commands.len());
+    Ok(())
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::{
+        fs, io,
+        os::unix::{
+            fs::{DirBuilderExt, PermissionsExt},
+            net::{UnixListener, UnixStream},
+        },
+        path::PathBuf,
+    };
+
+    /// the socket is in a folder only the user can access: the runtime dir or the ipc folder of omwmm
+    /// setting the permissions fails if another user created the ipc folder
+    fn get_socket_path() -> io::Result<PathBuf> {
+        let dir = match dirs::runtime_dir() {
+            Some(dir) => dir,
+            None => {
+                let dir = crate::profiles::get_omwmm_dir().join("ipc");
+                fs::DirBuilder::new()
+                    .recursive(true)
+                    .mode(0o700)
+                    .create(&dir)?;
+                fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))?;
+                dir
+            }
+        };
+        Ok(dir.join("omwmm.sock"))
+    }
+
+    /// only the user can connect to the socket, so no token is needed
+    pub fn bind() -> io::Result<(UnixListener, Option<String>)> {
+        let path = get_socket_path()?;
+        let listener = match UnixListener::bind(&path) {
+            // a crashed instance leaves its socket behind
+            Err(err) if err.kind() == io::ErrorKind::AddrInUse => {
+                if UnixStream::connect(&path).is_ok() {
+                    return Err(err);
+                }
+                fs::remove_file(&path)?;
+                UnixListener::bind(&path)
+            }
+            result => result,
+        }?;
+        Ok((listener, None))
+    }
+
+    pub fn connect() -> io::Result<UnixStream> {
+        UnixStream::connect(get_socket_path()?)
+    }
+}
+
+#[cfg(not(unix))]
+mod platform {
+    use std::{
+        collections::hash_map::RandomState,
+        fs,
+        hash::{BuildHasher, Hasher},
+        io::{self, Write},
+        net::{Ipv4Addr, TcpListener, TcpStream},
+        path::PathBuf,
+    };
+
+    /// the port and token of the running instance, in the config dir of the user
+    fn get_token_path() -> PathBuf {
+        crate::profiles::get_omwmm_dir().join("ipc")
+    }
+
+    /// a random token, RandomState is seeded by the os
+    fn get_random_token() -> String {
+        (0..4)
+            .map(|_| format!("{:016x}", RandomState::new().build_hasher().finish()))
+            .collect()
+    }
+
+    /// listens on a free local port, clients have to send the token that only the user can read first
+    pub fn bind() -> io::Result<(TcpListener, Option<String>)> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let port = listener.local_addr()?.port();
+        let token = get_random_token();
+        let path = get_token_path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, format!("{} {}", port, token))?;
+        Ok((listener, Some(token)))
+    }
+
+    pub fn connect() -> io::Result<TcpStream> {
+        let text = fs::read_to_string(get_token_path())?;
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid ipc token file");
+        let (port, token) = text.trim().split_once(' ').ok_or_else(invalid)?;
+        let port: u16 = port.parse().map_err(|_| invalid())?;
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port))?;
+        writeln!(stream, "{}", token)?;
+        Ok(stream)
+    }
+}
+
+impl TemplateApp {
+    /// applies the commands of the command line and other omwmm processes
+    pub(crate) fn update_commands(&mut self, frame: &mut eframe::Frame) {
+        if let Some(ipc) = &self.ipc {
+            self.pending_commands.extend(ipc.get_commands());
+        }
+        // wait for the profile to be loaded
+        if !self.init || self.pending_commands.is_empty() {
+            return;
+        }
+
+        for command in std::mem::take(&mut self.pending_commands) {
+            info!("Command: {}", command.to_message());
+            match command {
+                EIpcCommand::Focus => frame.focus(),
+                EIpcCommand::OpenNxm(url) => self.queue_nxm_link(&url),
+                EIpcCommand::Install(path) => self.install_file(&path),
+                EIpcCommand::SwitchProfile(name) => {
+                    if self.profiles.contains(&name) {
+                        self.switch_profile(&name);
+                    } else {
+                        warn!("Profile {} does not exist", name);
+                        self.toasts
+                            .error(format!("Profile {} does not exist", name));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,543 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+use common::{
+    archive::{hash_file, install_archive, is_supported_archive},
+    meta::{get_meta_path, update_archive_meta},
+    modlist::{update_mod, ModlistImport},
+    tes3::{PluginConflicts, RecordIndex},
+    trash::move_to_trash,
+    vfs::{DataDirConflicts, VfsIndex},
+};
+use log::{error, info, warn};
+
+use crate::{
+    app::refresh_downloads,
+    metadata::ModInfo,
+    mods::{push_mod, ModId},
+    ArchiveViewModel, EModTag, ModNexusMetaData, ModViewModel, TemplateApp,
+};
+
+/// The result of a finished job, applied on the ui thread
+pub enum EJobOutput {
+    /// an archive was extracted to a mod folder
+    Installed {
+        path: PathBuf,
+        meta_data: Box<ModNexusMetaData>,
+    },
+    /// this mod folder was moved to the trash
+    Uninstalled {
+        id: ModId,
+        trashed: PathBuf,
+    },
+    /// an archive was downloaded to this path
+    Downloaded(PathBuf),
+    /// an archive was copied to the downloads library to be installed
+    Added(PathBuf),
+    /// the archives in the downloads library
+    Downloads(Vec<ArchiveViewModel>),
+    /// the sha256 of an archive in the downloads library
+    Hash {
+        file_name: String,
+        hash: String,
+    },
+    /// a file was written
+    Exported(PathBuf),
+    /// the mods of a modlist were installed, the profile is created on the ui thread
+    ModlistInstalled {
+        name: String,
+        result: Box<ModlistImport>,
+    },
+    /// online info of mods by mod path
+    ModInfos(Vec<(PathBuf, ModInfo)>),
+    FileConflicts(Vec<DataDirConflicts>),
+    RecordConflicts {
+        conflicts: Vec<PluginConflicts>,
+        /// masters that are not loaded before a plugin, by plugin name
+        missing_masters: Vec<(String, Vec<String>)>,
+    },
+}
+
+/// the error of a job that stopped because it was cancelled
+pub(crate) const CANCELLED: &str = "cancelled";
+
+/// the error message of a failed job, interrupted io means the job was cancelled
+pub(crate) fn get_job_error(err: io::Error) -> String {
+    if err.kind() == io::ErrorKind::Interrupted {
+        CANCELLED.to_owned()
+    } else {
+        err.to_string()
+    }
+}
+
+/// Cancellation flag and progress of a job, shared with its worker thread
+#[derive(Clone, Default)]
+pub struct JobContext {
+    cancelled: Arc<AtomicBool>,
+    progress: Arc<Mutex<(f32, String)>>,
+}
+
+impl JobContext {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// sets the progress from 0 to 1 and a status message
+    pub fn set_progress(&self, fraction: f32, message: impl Into<String>) {
+        if let Ok(mut progress) = self.progress.lock() {
+            *progress = (fraction, message.into());
+        }
+    }
+}
+
+/// A long running operation on a worker thread
+pub struct Job {
+    pub name: String,
+    /// scans run in the background without notifications
+    pub is_silent: bool,
+    context: JobContext,
+    handle: Option<JoinHandle<Result<EJobOutput, String>>>,
+}
+
+impl Job {
+    pub fn cancel(&self) {
+        self.context.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.context.is_cancelled()
+    }
+
+    pub fn get_progress(&self) -> (f32, String) {
+        self.context
+            .progress
+            .lock()
+            .map(|p| p.clone())
+            .unwrap_or_default()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.handle.as_ref().map_or(true, |h| h.is_finished())
+    }
+}
+
+impl TemplateApp {
+    /// runs an operation on a worker thread and notifies when it is done
+    pub(crate) fn spawn_job<F>(&mut self, name: &str, f: F)
+    where
+        F: FnOnce(&JobContext) -> Result<EJobOutput, String> + Send + 'static,
+    {
+        self.start_job(name, false, f);
+    }
+
+    /// runs a scan on a worker thread, a running scan with the same name is cancelled
+    pub(crate) fn spawn_scan<F>(&mut self, name: &str, f: F)
+    where
+        F: FnOnce(&JobContext) -> Result<EJobOutput, String> + Send + 'static,
+    {
+        for job in self.jobs.iter().filter(|j| j.name == name) {
+            job.cancel();
+        }
+        self.start_job(name, true, f);
+    }
+
+    fn start_job<F>(&mut self, name: &str, is_silent: bool, f: F)
+    where
+        F: FnOnce(&JobContext) -> Result<EJobOutput, String> + Send + 'static,
+    {
+        info!("Starting job {}", name);
+        let context = JobContext::default();
+        let worker_context = context.clone();
+        let handle = thread::spawn(move || f(&worker_context));
+        self.jobs.push(Job {
+            name: name.to_owned(),
+            is_silent,
+            context,
+            handle: Some(handle),
+        });
+    }
+
+    /// applies the results of finished jobs
+    pub(crate) fn update_jobs(&mut self) {
+        let mut finished = vec![];
+        let mut i = 0;
+        while i < self.jobs.len() {
+            if self.jobs[i].is_finished() {
+                finished.push(self.jobs.remove(i));
+            } else {
+                i += 1;
+            }
+        }
+
+        for mut job in finished {
+            let Some(handle) = job.handle.take() else { continue };
+            let result = handle
+                .join()
+                .unwrap_or_else(|_| Err("the job panicked".to_owned()));
+            // a job that finished before it saw the cancel flag did its work, so it is applied
+            match result {
+                Ok(output) => {
+                    info!("Job {} done", job.name);
+                    if !job.is_silent {
+                        self.toasts.success(format!("{} done", job.name));
+                    }
+                    self.apply_job_output(output);
+                }
+                Err(err) if err == CANCELLED => {
+                    info!("Job {} cancelled", job.name);
+                    if !job.is_silent {
+                        self.toasts.info(format!("{} cancelled", job.name));
+                    }
+                }
+                Err(err) => {
+                    error!("Job {} failed: {}", job.name, err);
+                    self.toasts.error(format!("{} failed: {}", job.name, err));
+                }
+            }
+        }
+    }
+
+    fn apply_job_output(&mut self, output: EJobOutput) {
+        match output {
+            EJobOutput::Installed { path, meta_data } => {
+                // the watcher may have picked up the new folder already
+                if let Some(mod_info) = self.mods.iter_mut().find(|m| m.full_name == path) {
+                    mod_info.meta_data = Some(*meta_data);
+                } else {
+                    push_mod(
+                        &mut self.mods,
+                        ModViewModel {
+                            full_name: path,
+                            enabled: false,
+                            tag: EModTag::Managed,
+                            external: false,
+                            meta_data: Some(*meta_data),
+                            ..Default::default()
+                        },
+                    );
+                }
+                self.update_profile_mods();
+                self.update_plugins();
+                self.update_profile_plugins();
+                self.load_cached_mod_infos();
+            }
+            EJobOutput::Uninstalled { id, trashed } => {
+                // let other tools know the archive is no longer installed
+                let installation_file = self
+                    .get_mod(id)
+                    .and_then(|m| m.meta_data.as_ref())
+                    .and_then(|m| m.installation_file.clone());
+                if let (Some(file_name), Some(downloads)) =
+                    (installation_file, &self.downloads_library)
+                {
+                    let archive = downloads.join(file_name);
+                    if archive.exists() {
+                        update_archive_meta(&archive, false);
+                    }
+                }
+                info!("Moved mod to {}", trashed.display());
+                self.remove_mods(&[id]);
+                self.update_profile_mods();
+                self.update_plugins();
+                self.update_profile_plugins();
+                self.is_cfg_outdated = true;
+                self.update_file_conflicts();
+                self.update_record_conflicts();
+                self.refresh_trash();
+            }
+            EJobOutput::Downloaded(path) => {
+                info!("Downloaded {}", path.display());
+                self.scan_downloads();
+            }
+            EJobOutput::Added(path) => {
+                self.scan_downloads();
+                if let Some(file_name) = path.file_name() {
+                    self.install_archive(&file_name.to_string_lossy(), None);
+                }
+            }
+            EJobOutput::Downloads(mut downloads) => {
+                // keep hashes that were already computed
+                for archive in downloads.iter_mut() {
+                    archive.hash = self
+                        .downloads
+                        .iter()
+                        .find(|a| a.file_name == archive.file_name)
+                        .and_then(|a| a.hash.clone());
+                }
+                self.downloads = downloads;
+                self.update_installed_versions();
+            }
+            EJobOutput::Hash { file_name, hash } => {
+                info!("sha256 {}: {}", file_name, hash);
+                if let Some(archive) = self.downloads.iter_mut().find(|a| a.file_name == file_name)
+                {
+                    archive.hash = Some(hash);
+                }
+            }
+            EJobOutput::Exported(path) => {
+                info!("Exported {}", path.display());
+            }
+            EJobOutput::ModlistInstalled { name, result } => {
+                if !self.add_modlist_profile(&name, *result) {
+                    self.toasts
+                        .error(format!("Could not create profile {}", name));
+                }
+            }
+            EJobOutput::ModInfos(infos) => {
+                for (path, info) in infos {
+                    self.set_mod_info(path, info);
+                }
+            }
+            EJobOutput::FileConflicts(conflicts) => {
+                self.file_conflicts.clear();
+                for c in conflicts.into_iter().filter(|c| !c.is_empty()) {
+                    self.file_conflicts.insert(c.data_dir.to_owned(), c);
+                }
+            }
+            EJobOutput::RecordConflicts {
+                conflicts,
+                missing_masters,
+            } => {
+                self.record_conflicts.clear();
+                for c in conflicts.into_iter().filter(|c| !c.is_empty()) {
+                    self.record_conflicts.insert(c.plugin.to_owned(), c);
+                }
+                self.missing_masters = missing_masters.into_iter().collect();
+            }
+        }
+    }
+
+    /// extracts an archive of the downloads library into the mods library
+    /// an installed mod folder can be replaced to reinstall or update it,
+    /// the mod and its plugins keep their position and enabled state
+    pub(crate) fn install_archive(&mut self, file_name: &str, replace: Option<PathBuf>) {
+        let (Some(downloads), Some(library)) =
+            (self.downloads_library.clone(), self.mods_library.clone())
+        else {
+            warn!("No mod library found.");
+            return;
+        };
+        let archive = downloads.join(file_name);
+        let is_update = replace.is_some();
+        let install_path = match replace {
+            Some(path) => path,
+            None => {
+                let mut install_path = library.join(file_name);
+                install_path.set_extension("");
+                if install_path.exists() {
+                    self.toasts
+                        .warning(format!("{} is already installed", file_name));
+                    return;
+                }
+                install_path
+            }
+        };
+
+        self.spawn_job(&format!("Installing {}", file_name), move |job| {
+            let on_progress = |done: usize, total: usize| {
+                job.set_progress(
+                    done as f32 / total.max(1) as f32,
+                    format!("{}/{} files", done, total),
+                );
+                !job.is_cancelled()
+            };
+            // extract to the hidden .name.part folder in the library first,
+            // the mod folder is only replaced once all files are written
+            if is_update {
+                // the installed mod keeps its layout, e.g. without the Data Files folder of the archive
+                update_mod(&archive, &install_path, on_progress)
+            } else {
+                install_archive(&archive, &install_path, on_progress)
+            }
+            .map_err(get_job_error)?;
+            Ok(EJobOutput::Installed {
+                path: install_path,
+                meta_data: Box::new(update_archive_meta(&archive, true)),
+            })
+        });
+    }
+
+    /// picks up the metadata of mods that were installed without it
+    /// a mod folder named like an archive was installed from that archive
+    fn update_installed_versions(&mut self) {
+        let Some(library) = &self.mods_library else { return };
+        for mod_info in self.mods.iter_mut().filter(|m| m.meta_data.is_none()) {
+            let Some(archive) = self.downloads.iter().find(|a| {
+                let mut path = library.join(&a.file_name);
+                path.set_extension("");
+                path == mod_info.full_name
+            }) else {
+                continue;
+            };
+            if let Some(meta) = &archive.meta_data {
+                info!(
+                    "Mod {} was installed from {}",
+                    mod_info.full_name.display(),
+                    archive.file_name
+                );
+                mod_info.meta_data = Some(ModNexusMetaData {
+                    installation_file: Some(archive.file_name.to_owned()),
+                    ..meta.clone()
+                });
+            }
+        }
+    }
+
+    /// installs an archive from anywhere, it is copied to the downloads library first
+    pub(crate) fn install_file(&mut self, path: &Path) {
+        let (Some(downloads), Some(file_name)) = (self.downloads_library.clone(), path.file_name())
+        else {
+            self.toasts.error("Choose a downloads library first");
+            return;
+        };
+        let file_name = file_name.to_string_lossy().into_owned();
+        if !is_supported_archive(path) {
+            self.toasts
+                .error(format!("{} is not a supported archive", file_name));
+            return;
+        }
+        let destination = downloads.join(&file_name);
+        if destination.exists() {
+            if path.parent() != Some(downloads.as_path()) {
+                info!("{} is already in the downloads library", file_name);
+            }
+            self.install_archive(&file_name, None);
+            return;
+        }
+
+        let source = path.to_path_buf();
+        self.spawn_job(&format!("Adding {}", file_name), move |job| {
+            job.set_progress(0.0, "Copying");
+            fs::copy(&source, &destination).map_err(|err| err.to_string())?;
+            // keep the nexus info of archives downloaded by other tools
+            let meta_path = get_meta_path(&source);
+            if meta_path.exists() {
+                if let Err(err) = fs::copy(&meta_path, get_meta_path(&destination)) {
+                    warn!("Could not copy {}: {}", meta_path.display(), err);
+                }
+            }
+            Ok(EJobOutput::Added(destination))
+        });
+    }
+
+    /// deletes an archive and its .meta file from the downloads library
+    pub(crate) fn remove_archive(&mut self, file_name: &str) -> bool {
+        let Some(downloads) = &self.downloads_library else { return false };
+        let archive = downloads.join(file_name);
+        if let Err(err) = fs::remove_file(&archive) {
+            error!("Could not remove {}: {}", archive.display(), err);
+            return false;
+        }
+        let meta_path = get_meta_path(&archive);
+        if meta_path.exists() {
+            if let Err(err) = fs::remove_file(&meta_path) {
+                warn!("Could not remove {}: {}", meta_path.display(), err);
+            }
+        }
+        info!("Removed archive {}", file_name);
+        self.downloads.retain(|a| a.file_name != file_name);
+        true
+    }
+
+    /// moves a mod folder to the trash of the mods library, it can be restored from there
+    pub(crate) fn uninstall_mod(&mut self, id: ModId) {
+        let (Some(library), Some(mod_info)) = (self.mods_library.clone(), self.get_mod(id)) else { return };
+        let path = mod_info.full_name.to_owned();
+        let meta_data = mod_info.meta_data.clone();
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        self.spawn_job(&format!("Uninstalling {}", name), move |job| {
+            job.set_progress(0.0, "Moving to trash");
+            match move_to_trash(&library, &path, meta_data.as_ref()) {
+                Ok(trashed) => Ok(EJobOutput::Uninstalled { id, trashed }),
+                Err(err) => Err(err.to_string()),
+            }
+        });
+    }
+
+    /// computes the sha256 of an archive in the downloads library
+    pub(crate) fn hash_archive(&mut self, file_name: &str) {
+        let Some(downloads) = self.downloads_library.clone() else { return };
+        let file_name = file_name.to_owned();
+        self.spawn_job(&format!("Hashing {}", file_name), move |job| {
+            let hash = hash_file(&downloads.join(&file_name), |done, total| {
+                job.set_progress(done as f32 / total.max(1) as f32, "");
+                !job.is_cancelled()
+            })
+            .map_err(get_job_error)?;
+            Ok(EJobOutput::Hash { file_name, hash })
+        });
+    }
+
+    /// rescans the downloads library in the background
+    pub(crate) fn scan_downloads(&mut self) {
+        let Some(downloads_path) = self.downloads_library.clone() else { return };
+        self.spawn_scan("Scanning downloads", move |job| {
+            let mut downloads = vec![];
+            refresh_downloads(downloads_path, &mut downloads);
+            // a newer scan replaced this one
+            if job.is_cancelled() {
+                return Err(CANCELLED.to_owned());
+            }
+            Ok(EJobOutput::Downloads(downloads))
+        });
+    }
+
+    /// rebuilds the vfs index of the enabled mods and updates the file conflicts
+    pub(crate) fn update_file_conflicts(&mut self) {
+        let data_dirs: Vec<PathBuf> = self
+            .mods
+            .iter()
+            .filter(|m| m.enabled)
+            .map(|m| m.full_name.to_owned())
+            .collect();
+        self.spawn_scan("Indexing files", move |job| {
+            job.set_progress(0.0, format!("{} data directories", data_dirs.len()));
+            let index = VfsIndex::new(&data_dirs);
+            if job.is_cancelled() {
+                return Err(CANCELLED.to_owned());
+            }
+            Ok(EJobOutput::FileConflicts(index.get_conflicts()))
+        });
+    }
+
+    /// re-reads the enabled plugins in load order and updates the record conflicts
+    pub(crate) fn update_record_conflicts(&mut self) {
+        let data_dirs: Vec<PathBuf> = self
+            .mods
+            .iter()
+            .filter(|m| m.enabled)
+            .map(|m| m.full_name.to_owned())
+            .collect();
+        let plugin_names: Vec<String> = self
+            .plugins
+            .iter()
+            .filter(|p| p.enabled && !p.provider_disabled)
+            .map(|p| p.name.to_owned())
+            .collect();
+        self.spawn_scan("Indexing records", move |job| {
+            job.set_progress(0.0, format!("{} plugins", plugin_names.len()));
+            let plugin_paths = common::resolve_plugins(&data_dirs, &plugin_names);
+            if job.is_cancelled() {
+                return Err(CANCELLED.to_owned());
+            }
+            let index = RecordIndex::new(&plugin_paths);
+            if job.is_cancelled() {
+                return Err(CANCELLED.to_owned());
+            }
+            Ok(EJobOutput::RecordConflicts {
+                conflicts: index.get_conflicts(),
+                missing_masters: index.get_missing_masters(),
+            })
+        });
+    }
+}
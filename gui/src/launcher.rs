@@ -0,0 +1,205 @@
+use std::{
+    env,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use log::{error, info, warn};
+
+use crate::TemplateApp;
+
+/// max lines kept in the game log
+const MAX_LOG_LINES: usize = 10_000;
+
+/// OpenMW executables that can be launched
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EExecutable {
+    OpenMW,
+    Launcher,
+    CS,
+}
+
+impl EExecutable {
+    /// the executable file name without extension
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            EExecutable::OpenMW => "openmw",
+            EExecutable::Launcher => "openmw-launcher",
+            EExecutable::CS => "openmw-cs",
+        }
+    }
+}
+
+/// A launched process whose output is captured in the game log
+pub struct RunningProcess {
+    pub executable: EExecutable,
+    pub child: Child,
+}
+
+impl TemplateApp {
+    /// writes the profile config and launches an OpenMW executable with it
+    pub(crate) fn launch(&mut self, executable: EExecutable) {
+        let Some(path) = find_executable(executable, &self.openmw_dir) else {
+            self.toasts.error(format!(
+                "Could not find {}, set the OpenMW directory in the settings",
+                executable.get_name()
+            ));
+            return;
+        };
+
+        // write the active config before launching
+        self.update_cfg();
+        self.update_profile_settings();
+
+        info!(
+            "Launching {} {}",
+            path.display(),
+            self.get_launch_args().join(" ")
+        );
+        let mut command = Command::new(&path);
+        command
+            .args(self.get_launch_args())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(dir) = path.parent() {
+            command.current_dir(dir);
+        }
+        match command.spawn() {
+            Ok(mut child) => {
+                if let Some(stdout) = child.stdout.take() {
+                    capture_output(stdout, self.game_log.clone());
+                }
+                if let Some(stderr) = child.stderr.take() {
+                    capture_output(stderr, self.game_log.clone());
+                }
+                self.running_processes
+                    .push(RunningProcess { executable, child });
+                self.toasts
+                    .info(format!("Launched {}", executable.get_name()));
+            }
+            Err(err) => {
+                error!("Could not launch {}: {}", path.display(), err);
+                self.toasts
+                    .error(format!("Could not launch {}", executable.get_name()));
+            }
+        }
+    }
+
+    /// checks if launched processes exited
+    pub(crate) fn update_running_processes(&mut self) {
+        let mut exited = vec![];
+        for (i, process) in self.running_processes.iter_mut().enumerate() {
+            match process.child.try_wait() {
+                Ok(Some(status)) => {
+                    info!("{} exited with {}", process.executable.get_name(), status);
+                    exited.push(i);
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    warn!(
+                        "Could not wait for {}: {}",
+                        process.executable.get_name(),
+                        err
+                    );
+                    exited.push(i);
+                }
+            }
+        }
+        for i in exited.into_iter().rev() {
+            let process = self.running_processes.remove(i);
+            self.toasts
+                .info(format!("{} exited", process.executable.get_name()));
+        }
+    }
+}
+
+/// reads the lines of a process output into the log on a separate thread
+fn capture_output<R>(output: R, log: Arc<Mutex<Vec<String>>>)
+where
+    R: Read + Send + 'static,
+{
+    thread::spawn(move || {
+        for line in BufReader::new(output).lines().map_while(Result::ok) {
+            if let Ok(mut log) = log.lock() {
+                log.push(line);
+                if log.len() > MAX_LOG_LINES {
+                    let overflow = log.len() - MAX_LOG_LINES;
+                    log.drain(..overflow);
+                }
+            }
+        }
+    });
+}
+
+/// Finds an OpenMW executable
+/// checks the user's OpenMW directory first, then the PATH and then common install locations
+pub fn find_executable(executable: EExecutable, openmw_dir: &Option<PathBuf>) -> Option<PathBuf> {
+    let file_name = format!("{}{}", executable.get_name(), env::consts::EXE_SUFFIX);
+
+    let mut dirs: Vec<PathBuf> = vec![];
+    if let Some(dir) = openmw_dir {
+        dirs.push(dir.to_owned());
+    }
+    if let Some(paths) = env::var_os("PATH") {
+        dirs.extend(env::split_paths(&paths));
+    }
+    dirs.extend(get_install_dirs());
+
+    dirs.into_iter()
+        .map(|dir| dir.join(&file_name))
+        .find(|path| path.is_file())
+}
+
+/// common OpenMW install locations
+fn get_install_dirs() -> Vec<PathBuf> {
+    match env::consts::OS {
+        "linux" => {
+            let mut install_dirs = vec![
+                PathBuf::from("/usr/bin"),
+                PathBuf::from("/usr/local/bin"),
+                PathBuf::from("/usr/games"),
+                PathBuf::from("/opt/openmw"),
+            ];
+            if let Some(home) = dirs::home_dir() {
+                install_dirs.push(home.join(".local").join("bin"));
+            }
+            install_dirs
+        }
+        "macos" => vec![PathBuf::from("/Applications/OpenMW.app/Contents/MacOS")],
+        "windows" => {
+            // OpenMW installs to versioned folders, e.g. C:\Program Files\OpenMW 0.48.0
+            let mut install_dirs = vec![];
+            for program_files in ["ProgramFiles", "ProgramFiles(x86)"] {
+                if let Some(root) = env::var_os(program_files) {
+                    install_dirs.extend(get_openmw_dirs(Path::new(&root)));
+                }
+            }
+            install_dirs
+        }
+        _ => vec![],
+    }
+}
+
+/// folders in root that start with OpenMW, newest version first
+fn get_openmw_dirs(root: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = vec![];
+    if let Ok(entries) = std::fs::read_dir(root) {
+        for entry in entries.flatten() {
+            if entry.path().is_dir()
+                && entry
+                    .file_name()
+                    .to_string_lossy()
+                    .to_lowercase()
+                    .starts_with("openmw")
+            {
+                dirs.push(entry.path());
+            }
+        }
+    }
+    dirs.sort();
+    dirs.reverse();
+    dirs
+}
@@ -1,10 +1,24 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, time::SystemTime};
 
 pub use app::TemplateApp;
+pub use common::meta::ModNexusMetaData;
 
 mod app;
 mod appui;
+pub mod downloader;
+pub mod filters;
+pub mod history;
+mod importer;
+pub mod ipc;
+mod jobs;
+mod launcher;
+pub mod metadata;
+mod modlist;
+pub mod mods;
+mod profiles;
+pub mod separators;
 mod views;
+mod watcher;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -29,6 +43,10 @@ impl From<EScale> for f32 {
 pub struct PluginViewModel {
     pub name: String,
     pub enabled: bool,
+    /// a content= entry of the cfg that omwmm does not manage
+    pub external: bool,
+    /// all mods that contain the plugin are disabled, it is not written to the cfg
+    pub provider_disabled: bool,
 }
 // We need this to uniquely identify items. You can also implement the Hash trait.
 impl egui_dnd::DragDropItem for PluginViewModel {
@@ -37,9 +55,23 @@ impl egui_dnd::DragDropItem for PluginViewModel {
     }
 }
 
+/// How omwmm manages the data directory of a mod
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum EModTag {
+    /// vanilla game data, always enabled and never uninstalled
+    BaseGame,
+    /// a data directory omwmm lists but does not own
+    #[default]
+    Unmanaged,
+    /// a mod omwmm installed into the mods library
+    Managed,
+}
+
 #[derive(Default, serde::Deserialize, serde::Serialize)]
 #[serde(default)]
 pub struct ModViewModel {
+    /// stable identity of the mod in the list
+    pub id: mods::ModId,
     /// Mod name, to get the full path join this with the mod library
     pub full_name: PathBuf,
 
@@ -47,8 +79,17 @@ pub struct ModViewModel {
     // do not serialize this centrally
     #[serde(skip)]
     pub enabled: bool,
+    /// detected on import, can be changed by the user
+    pub tag: EModTag,
+    /// a data= entry of the cfg that omwmm does not manage, it is locked in the list
+    pub external: bool,
+    /// nexus metadata of the installed archive
+    pub meta_data: Option<ModNexusMetaData>,
+    /// set by the user, the Nexus Mods category is used otherwise
+    pub category: Option<String>,
+    pub color: Option<EColorTag>,
+    pub notes: String,
     // TODO files
-    // TODO vanilla tag
 }
 // We need this to uniquely identify items. You can also implement the Hash trait.
 impl egui_dnd::DragDropItem for ModViewModel {
@@ -57,19 +98,69 @@ impl egui_dnd::DragDropItem for ModViewModel {
     }
 }
 
+/// Colour to mark mods and separators with
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum EColorTag {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+impl EColorTag {
+    pub const ALL: [EColorTag; 6] = [
+        EColorTag::Red,
+        EColorTag::Orange,
+        EColorTag::Yellow,
+        EColorTag::Green,
+        EColorTag::Blue,
+        EColorTag::Purple,
+    ];
+}
+impl From<EColorTag> for egui::Color32 {
+    fn from(val: EColorTag) -> Self {
+        match val {
+            EColorTag::Red => egui::Color32::from_rgb(231, 76, 60),
+            EColorTag::Orange => egui::Color32::from_rgb(230, 126, 34),
+            EColorTag::Yellow => egui::Color32::from_rgb(241, 196, 15),
+            EColorTag::Green => egui::Color32::from_rgb(46, 204, 113),
+            EColorTag::Blue => egui::Color32::from_rgb(52, 152, 219),
+            EColorTag::Purple => egui::Color32::from_rgb(155, 89, 182),
+        }
+    }
+}
+
+/// A named group in the mod list
+/// it contains the mods from the one it is placed before up to the next separator
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModSeparator {
+    pub name: String,
+    /// the mod that follows the separator, none places it at the end of the list
+    pub before: Option<PathBuf>,
+    pub color: Option<EColorTag>,
+    pub collapsed: bool,
+}
+
+/// Install state of an archive in the downloads library
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EInstallState {
+    NotInstalled,
+    /// an older version of the same nexus mod is installed
+    InstalledOlder,
+    Installed,
+}
+
 #[derive(Default)]
 pub struct ArchiveViewModel {
     pub file_name: String,
-    //pub installed: bool,
+    /// size in bytes
+    pub size: u64,
+    pub modified: Option<SystemTime>,
 
     // nexus metadata
     pub meta_data: Option<ModNexusMetaData>,
-}
-
-#[derive(Default, serde::Serialize, serde::Deserialize, Debug)]
-pub struct ModNexusMetaData {
-    pub mod_id: Option<usize>,
-    pub description: Option<String>,
-    pub mod_name: Option<String>,
-    pub version: Option<String>,
+    /// sha256 of the archive, computed on demand
+    pub hash: Option<String>,
 }
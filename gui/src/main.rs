@@ -7,6 +7,24 @@ fn main() -> eframe::Result<()> {
     // Log to stdout (if you run with `RUST_LOG=debug`).
     tracing_subscriber::fmt::init();
 
+    // only one instance writes the app state, others forward their arguments to it
+    let commands = omwmm::ipc::EIpcCommand::from_args(std::env::args().skip(1));
+    let _lock = match omwmm::ipc::InstanceLock::acquire() {
+        Ok(Some(lock)) => Some(lock),
+        Ok(None) => {
+            let mut commands = commands;
+            commands.push(omwmm::ipc::EIpcCommand::Focus);
+            if let Err(err) = omwmm::ipc::send_to_running_instance(&commands) {
+                log::error!("Could not reach the running omwmm: {}", err);
+            }
+            return Ok(());
+        }
+        Err(err) => {
+            log::warn!("Could not lock the app state: {}", err);
+            None
+        }
+    };
+
     let native_options = eframe::NativeOptions::default();
     // let native_options = eframe::NativeOptions {
     //     drag_and_drop_support: true,
@@ -16,6 +34,10 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "omwmm",
         native_options,
-        Box::new(|cc| Box::new(omwmm::TemplateApp::new(cc))),
+        Box::new(move |cc| {
+            let mut app = omwmm::TemplateApp::new(cc);
+            app.pending_commands = commands;
+            Box::new(app)
+        }),
     )
 }
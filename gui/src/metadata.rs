@@ -0,0 +1,448 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use log::{info, warn};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{downloader::NxmLink, jobs::EJobOutput, TemplateApp};
+
+const NEXUS_API_URL: &str = "https://api.nexusmods.com";
+/// cached mod info is refreshed after a day
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// Online information about a mod
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModInfo {
+    pub mod_id: usize,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    pub category_id: Option<usize>,
+    pub category: Option<String>,
+    /// the newest main file of the mod
+    pub latest_file: Option<FileInfo>,
+}
+
+/// A downloadable file of a mod
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileInfo {
+    pub file_id: usize,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub file_name: Option<String>,
+    /// seconds since the unix epoch
+    pub uploaded: Option<u64>,
+}
+
+/// A source of online mod information
+pub trait MetadataProvider: Send {
+    /// a unique name, cached information is stored per provider
+    fn get_name(&self) -> &str;
+    fn get_mod_info(&self, game: &str, mod_id: usize) -> Result<ModInfo, String>;
+}
+
+/// Reads mod information from the Nexus Mods API v1
+pub struct NexusProvider {
+    base_url: String,
+    api_key: String,
+    agent: ureq::Agent,
+}
+
+impl NexusProvider {
+    pub fn new(api_key: &str) -> Self {
+        Self::with_base_url(NEXUS_API_URL, api_key)
+    }
+
+    /// uses another server with the same API, e.g. a local mirror
+    pub fn with_base_url(base_url: &str, api_key: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            api_key: api_key.to_owned(),
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(30))
+                .build(),
+        }
+    }
+
+    fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, String> {
+        self.get_with_query(path, &[])
+    }
+
+    /// the query pairs are encoded by ureq
+    fn get_with_query<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<T, String> {
+        let url = format!("{}/{}", self.base_url, path);
+        let response = query
+            .iter()
+            .fold(self.agent.get(&url), |request, (name, value)| {
+                request.query(name, value)
+            })
+            .set("apikey", &self.api_key)
+            .set("accept", "application/json")
+            .call()
+            .map_err(|err| format!("{}: {}", url, err))?;
+        response
+            .into_json()
+            .map_err(|err| format!("{}: {}", url, err))
+    }
+
+    /// name and version of a file of a mod
+    pub fn get_file_info(
+        &self,
+        game: &str,
+        mod_id: usize,
+        file_id: usize,
+    ) -> Result<FileInfo, String> {
+        check_game_name(game)?;
+        let file: NexusFile = self.get(&format!(
+            "v1/games/{}/mods/{}/files/{}.json",
+            game, mod_id, file_id
+        ))?;
+        Ok(file.into())
+    }
+
+    /// download urls of a file, the key and expiry of an nxm link are required for free accounts
+    pub fn get_download_links(&self, link: &NxmLink) -> Result<Vec<String>, String> {
+        check_game_name(&link.game)?;
+        let path = format!(
+            "v1/games/{}/mods/{}/files/{}/download_link.json",
+            link.game, link.mod_id, link.file_id
+        );
+        let links: Vec<NexusDownloadLink> = match (&link.key, link.expires) {
+            (Some(key), Some(expires)) => {
+                self.get_with_query(&path, &[("key", key), ("expires", &expires.to_string())])?
+            }
+            _ => self.get(&path)?,
+        };
+        Ok(links.into_iter().map(|l| l.uri).collect())
+    }
+}
+
+#[derive(Deserialize)]
+struct NexusMod {
+    mod_id: usize,
+    name: Option<String>,
+    version: Option<String>,
+    summary: Option<String>,
+    description: Option<String>,
+    category_id: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct NexusFiles {
+    files: Vec<NexusFile>,
+}
+
+#[derive(Deserialize)]
+struct NexusFile {
+    file_id: usize,
+    name: Option<String>,
+    version: Option<String>,
+    category_name: Option<String>,
+    file_name: Option<String>,
+    uploaded_timestamp: Option<u64>,
+}
+
+impl From<NexusFile> for FileInfo {
+    fn from(file: NexusFile) -> Self {
+        Self {
+            file_id: file.file_id,
+            name: file.name,
+            version: file.version,
+            file_name: file.file_name,
+            uploaded: file.uploaded_timestamp,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct NexusDownloadLink {
+    #[serde(rename = "URI")]
+    uri: String,
+}
+
+#[derive(Deserialize)]
+struct NexusGame {
+    categories: Vec<NexusCategory>,
+}
+
+#[derive(Deserialize)]
+struct NexusCategory {
+    category_id: usize,
+    name: String,
+}
+
+impl MetadataProvider for NexusProvider {
+    fn get_name(&self) -> &str {
+        "nexus"
+    }
+
+    fn get_mod_info(&self, game: &str, mod_id: usize) -> Result<ModInfo, String> {
+        if self.api_key.is_empty() {
+            return Err("no Nexus Mods API key set".to_owned());
+        }
+        check_game_name(game)?;
+        let nexus_mod: NexusMod = self.get(&format!("v1/games/{}/mods/{}.json", game, mod_id))?;
+
+        // the newest main file, old versions and optional files are listed too
+        let files: NexusFiles =
+            self.get(&format!("v1/games/{}/mods/{}/files.json", game, mod_id))?;
+        let latest_file = files
+            .files
+            .into_iter()
+            .filter(|f| f.category_name.as_deref() == Some("MAIN"))
+            .max_by_key(|f| f.uploaded_timestamp)
+            .map(FileInfo::from);
+
+        // category names are optional
+        let category = nexus_mod.category_id.and_then(|id| {
+            match self.get::<NexusGame>(&format!("v1/games/{}.json", game)) {
+                Ok(game) => game
+                    .categories
+                    .into_iter()
+                    .find(|c| c.category_id == id)
+                    .map(|c| c.name),
+                Err(err) => {
+                    warn!("Could not get categories: {}", err);
+                    None
+                }
+            }
+        });
+
+        Ok(ModInfo {
+            mod_id: nexus_mod.mod_id,
+            name: nexus_mod.name,
+            version: nexus_mod.version,
+            summary: nexus_mod.summary,
+            description: nexus_mod.description,
+            category_id: nexus_mod.category_id,
+            category,
+            latest_file,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    /// seconds since the unix epoch
+    fetched: u64,
+    info: ModInfo,
+}
+
+/// Keeps the results of another provider on disk.
+/// Outdated entries are refreshed, they are still used when the provider fails, e.g. offline.
+pub struct CachedProvider {
+    provider: Box<dyn MetadataProvider>,
+    cache_dir: PathBuf,
+    max_age: Duration,
+}
+
+impl CachedProvider {
+    pub fn new(provider: Box<dyn MetadataProvider>, cache_dir: PathBuf) -> Self {
+        Self {
+            provider,
+            cache_dir,
+            max_age: DEFAULT_MAX_AGE,
+        }
+    }
+
+    /// entries older than this are refreshed
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// cached information without going online
+    pub fn get_cached(&self, game: &str, mod_id: usize) -> Option<ModInfo> {
+        self.read_entry(game, mod_id).map(|e| e.info)
+    }
+
+    /// None if the game name could leave the cache folder
+    fn get_cache_path(&self, game: &str, mod_id: usize) -> Option<PathBuf> {
+        is_valid_game_name(game).then(|| {
+            self.cache_dir
+                .join(self.provider.get_name())
+                .join(game)
+                .join(format!("{}.json", mod_id))
+        })
+    }
+
+    fn read_entry(&self, game: &str, mod_id: usize) -> Option<CacheEntry> {
+        let path = self.get_cache_path(game, mod_id)?;
+        let text = fs::read_to_string(&path).ok()?;
+        match serde_json::from_str(&text) {
+            Ok(entry) => Some(entry),
+            Err(err) => {
+                warn!("Invalid cache entry {}: {}", path.display(), err);
+                None
+            }
+        }
+    }
+
+    fn write_entry(&self, game: &str, mod_id: usize, info: &ModInfo) {
+        let Some(path) = self.get_cache_path(game, mod_id) else { return };
+        let entry = CacheEntry {
+            fetched: get_timestamp(),
+            info: info.clone(),
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| {
+                let text = serde_json::to_string_pretty(&entry)?;
+                fs::write(&path, text)
+            });
+        if let Err(err) = result {
+            warn!("Could not write cache entry {}: {}", path.display(), err);
+        }
+    }
+}
+
+impl MetadataProvider for CachedProvider {
+    fn get_name(&self) -> &str {
+        self.provider.get_name()
+    }
+
+    fn get_mod_info(&self, game: &str, mod_id: usize) -> Result<ModInfo, String> {
+        check_game_name(game)?;
+        let cached = self.read_entry(game, mod_id);
+        if let Some(entry) = &cached {
+            if get_timestamp().saturating_sub(entry.fetched) < self.max_age.as_secs() {
+                return Ok(entry.info.clone());
+            }
+        }
+
+        match self.provider.get_mod_info(game, mod_id) {
+            Ok(info) => {
+                self.write_entry(game, mod_id, &info);
+                Ok(info)
+            }
+            Err(err) => match cached {
+                Some(entry) => {
+                    info!("Using cached info for mod {}: {}", mod_id, err);
+                    Ok(entry.info)
+                }
+                None => Err(err),
+            },
+        }
+    }
+}
+
+/// Nexus Mods game names like morrowind are used in api urls and cache paths
+pub fn is_valid_game_name(game: &str) -> bool {
+    !game.is_empty()
+        && game
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+fn check_game_name(game: &str) -> Result<(), String> {
+    if is_valid_game_name(game) {
+        Ok(())
+    } else {
+        Err(format!("invalid game name {}", game))
+    }
+}
+
+fn get_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+impl TemplateApp {
+    /// the Nexus Mods API behind the on-disk cache
+    pub(crate) fn get_metadata_provider(&self) -> CachedProvider {
+        let cache_dir = dirs::cache_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("omwmm")
+            .join("metadata");
+        CachedProvider::new(Box::new(NexusProvider::new(&self.nexus_api_key)), cache_dir)
+    }
+
+    /// mods with a nexus id: mod path, game and mod id
+    fn get_nexus_mods(&self) -> Vec<(PathBuf, String, usize)> {
+        self.mods
+            .iter()
+            .filter_map(|m| {
+                let meta = m.meta_data.as_ref()?;
+                let game = meta.game_name.clone().unwrap_or("morrowind".to_owned());
+                Some((m.full_name.to_owned(), game, meta.mod_id?))
+            })
+            .collect()
+    }
+
+    /// reads the online info of all mods from the cache
+    pub(crate) fn load_cached_mod_infos(&mut self) {
+        let provider = self.get_metadata_provider();
+        for (path, game, mod_id) in self.get_nexus_mods() {
+            if let Some(info) = provider.get_cached(&game, mod_id) {
+                self.set_mod_info(path, info);
+            }
+        }
+    }
+
+    /// gets the online info of one or all mods in the background, cached info is used when offline
+    pub(crate) fn fetch_mod_infos(&mut self, path: Option<PathBuf>) {
+        let mut mods = self.get_nexus_mods();
+        if let Some(path) = &path {
+            mods.retain(|(p, _, _)| p == path);
+        }
+        if mods.is_empty() {
+            self.toasts.info("No mods with a Nexus Mods id");
+            return;
+        }
+
+        let provider = self.get_metadata_provider();
+        self.spawn_job("Checking for updates", move |job| {
+            let mut infos = vec![];
+            let mut last_error = None;
+            for (i, (path, game, mod_id)) in mods.iter().enumerate() {
+                if job.is_cancelled() {
+                    break;
+                }
+                job.set_progress(i as f32 / mods.len() as f32, format!("mod {}", mod_id));
+                match provider.get_mod_info(game, *mod_id) {
+                    Ok(info) => infos.push((path.to_owned(), info)),
+                    Err(err) => {
+                        warn!("Could not get info of mod {}: {}", mod_id, err);
+                        last_error = Some(err);
+                    }
+                }
+            }
+            match last_error {
+                Some(err) if infos.is_empty() => Err(err),
+                _ => Ok(EJobOutput::ModInfos(infos)),
+            }
+        });
+    }
+
+    /// stores the online info of a mod and its newest version
+    pub(crate) fn set_mod_info(&mut self, path: PathBuf, info: ModInfo) {
+        if let Some(meta) = self
+            .mods
+            .iter_mut()
+            .find(|m| m.full_name == path)
+            .and_then(|m| m.meta_data.as_mut())
+        {
+            let newest_version = info
+                .latest_file
+                .as_ref()
+                .and_then(|f| f.version.clone())
+                .or_else(|| info.version.clone());
+            if newest_version.is_some() {
+                meta.newest_version = newest_version;
+            }
+        }
+        self.mod_infos.insert(path, info);
+    }
+}
@@ -0,0 +1,192 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use common::{
+    modlist::{find_mod_archives, Modlist, ModlistImport},
+    profile::ProfileLists,
+};
+use log::{error, info, warn};
+
+use crate::{
+    jobs::{get_job_error, EJobOutput},
+    mods::EConfirmAction,
+    EModTag, TemplateApp,
+};
+
+impl TemplateApp {
+    /// the archive each mod was installed from, as recorded in its metadata or named like the mod
+    fn get_mod_archives(&self) -> HashMap<PathBuf, PathBuf> {
+        let Some(downloads) = &self.downloads_library else {
+            return HashMap::new();
+        };
+        let mods: Vec<PathBuf> = self.mods.iter().map(|m| m.full_name.to_owned()).collect();
+        let mut archives = find_mod_archives(&mods, downloads);
+        for m in self.mods.iter() {
+            let Some(file_name) = m
+                .meta_data
+                .as_ref()
+                .and_then(|meta| meta.installation_file.as_ref())
+            else {
+                continue;
+            };
+            let archive = downloads.join(file_name);
+            if archive.exists() {
+                archives.insert(m.full_name.to_owned(), archive);
+            }
+        }
+        archives
+    }
+
+    /// writes the current profile with the archives and hashes of its mods to a modlist file
+    pub(crate) fn export_modlist(&mut self, file: PathBuf) {
+        let lists = ProfileLists {
+            mods: self
+                .mods
+                .iter()
+                .filter(|m| !m.external)
+                .map(|m| (m.full_name.to_owned(), m.enabled))
+                .collect(),
+            plugins: self
+                .plugins
+                .iter()
+                .filter(|p| !p.external)
+                .map(|p| (p.name.to_owned(), p.enabled))
+                .collect(),
+        };
+        let archives = self.get_mod_archives();
+        let library = self.mods_library.clone();
+        let name = self.current_profile.to_owned();
+        self.spawn_job("Exporting modlist", move |job| {
+            let modlist = Modlist::new(
+                &name,
+                &lists,
+                library.as_deref(),
+                &archives,
+                |done, total| {
+                    job.set_progress(
+                        done as f32 / total.max(1) as f32,
+                        format!("{}/{} mods", done, total),
+                    );
+                    !job.is_cancelled()
+                },
+            )
+            .map_err(get_job_error)?;
+            modlist.write(&file).map_err(|err| err.to_string())?;
+            Ok(EJobOutput::Exported(file))
+        });
+    }
+
+    /// creates a profile from a modlist file and switches to it
+    /// the archives that are missing in the downloads library are shown in a report
+    pub(crate) fn import_modlist(&mut self, file: &Path) -> bool {
+        let modlist = match Modlist::read(file) {
+            Ok(modlist) => modlist,
+            Err(err) => {
+                error!("Could not read modlist {}: {}", file.display(), err);
+                return false;
+            }
+        };
+        let base_game_dirs = self.get_base_game_dirs();
+        let result = modlist.import(
+            self.mods_library.as_deref(),
+            self.downloads_library.as_deref(),
+            &base_game_dirs,
+        );
+
+        let name = self.get_import_profile_name(&modlist.name, "modlist");
+        self.add_modlist_profile(&name, result)
+    }
+
+    /// installs the mods of a modlist file from the downloads library in the background
+    /// a profile is created with them once they are installed
+    /// the user confirms replacing mods that are already installed first
+    pub(crate) fn install_modlist(&mut self, file: &Path, replace_installed: bool) {
+        let (Some(library), Some(downloads)) =
+            (self.mods_library.clone(), self.downloads_library.clone())
+        else {
+            self.toasts
+                .error("Choose a mods library and a downloads library first");
+            return;
+        };
+        let modlist = match Modlist::read(file) {
+            Ok(modlist) => modlist,
+            Err(err) => {
+                error!("Could not read modlist {}: {}", file.display(), err);
+                self.toasts.error("Could not read modlist");
+                return;
+            }
+        };
+        let installed = modlist.get_installed_mods(&library);
+        if !installed.is_empty() && !replace_installed {
+            self.pending_confirmation = Some(EConfirmAction::ReplaceMods {
+                modlist: file.to_path_buf(),
+                names: installed,
+            });
+            return;
+        }
+        let base_game_dirs = self.get_base_game_dirs();
+        let name = self.get_import_profile_name(&modlist.name, "modlist");
+        self.spawn_job(&format!("Installing modlist {}", name), move |job| {
+            let result = modlist
+                .install(
+                    &library,
+                    &downloads,
+                    &base_game_dirs,
+                    replace_installed,
+                    |done, total| {
+                        job.set_progress(
+                            done as f32 / total.max(1) as f32,
+                            format!("{}/{} mods", done, total),
+                        );
+                        !job.is_cancelled()
+                    },
+                )
+                .map_err(get_job_error)?;
+            Ok(EJobOutput::ModlistInstalled {
+                name,
+                result: Box::new(result),
+            })
+        });
+    }
+
+    fn get_base_game_dirs(&self) -> Vec<PathBuf> {
+        self.mods
+            .iter()
+            .filter(|m| m.tag == EModTag::BaseGame)
+            .map(|m| m.full_name.to_owned())
+            .collect()
+    }
+
+    /// creates a profile from an imported modlist and switches to it
+    /// what is missing is shown in a report
+    pub(crate) fn add_modlist_profile(&mut self, name: &str, result: ModlistImport) -> bool {
+        let Some(profile_dir) = self.get_new_profile_dir(name) else { return false };
+        if let Err(err) = fs::create_dir_all(&profile_dir) {
+            error!("Could not create profile {}: {}", name, err);
+            return false;
+        }
+        info!("Importing profile {}", name);
+        result.lists.save(&profile_dir);
+        // the profile order applies to the installed mods
+        self.sync_mods_library();
+        self.switch_profile(name);
+        self.refresh_profiles();
+
+        if !result.missing_archives.is_empty()
+            || !result.missing_mods.is_empty()
+            || !result.mismatches.is_empty()
+        {
+            warn!(
+                "{} archives and {} mods of the modlist are missing, {} mismatches",
+                result.missing_archives.len(),
+                result.missing_mods.len(),
+                result.mismatches.len()
+            );
+            self.modlist_report = Some(result);
+        }
+        true
+    }
+}
@@ -0,0 +1,175 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use common::trash::{delete_from_trash, empty_trash, get_trashed_mods, restore_from_trash};
+use log::error;
+
+use crate::{ModViewModel, TemplateApp};
+
+/// Stable identity of a mod, it does not change when the mod list is reordered or filtered
+pub type ModId = u64;
+
+/// Destructive actions that wait for the user to confirm them
+#[derive(Debug, Clone, PartialEq)]
+pub enum EConfirmAction {
+    /// move the mod folders to the trash of the mods library
+    Uninstall(Vec<ModId>),
+    /// remove the mods from the list, their folders are kept
+    Remove(Vec<ModId>),
+    /// delete a mod in the trash for good
+    DeleteTrashed(PathBuf),
+    /// delete all mods in the trash for good
+    EmptyTrash,
+    /// install a modlist and replace the mods of it that are installed
+    ReplaceMods {
+        modlist: PathBuf,
+        names: Vec<String>,
+    },
+    /// delete an archive of the downloads library and its .meta file
+    RemoveArchive(String),
+}
+
+/// the id the next added mod gets
+pub fn get_next_mod_id(mods: &[ModViewModel]) -> ModId {
+    mods.iter().map(|m| m.id).max().unwrap_or(0) + 1
+}
+
+/// gives each mod without an id, or with the id of an earlier mod, a new one
+/// mods of older versions of omwmm have no id
+pub fn assign_mod_ids(mods: &mut [ModViewModel]) {
+    let mut next = get_next_mod_id(mods);
+    let mut ids = HashSet::new();
+    for mod_info in mods.iter_mut() {
+        if mod_info.id == 0 || !ids.insert(mod_info.id) {
+            mod_info.id = next;
+            ids.insert(next);
+            next += 1;
+        }
+    }
+}
+
+/// adds a mod at a position of the list with a new id
+pub fn insert_mod(mods: &mut Vec<ModViewModel>, index: usize, mut mod_info: ModViewModel) -> ModId {
+    let id = get_next_mod_id(mods);
+    mod_info.id = id;
+    mods.insert(index.min(mods.len()), mod_info);
+    id
+}
+
+/// adds a mod at the end of the list with a new id
+pub fn push_mod(mods: &mut Vec<ModViewModel>, mod_info: ModViewModel) -> ModId {
+    insert_mod(mods, mods.len(), mod_info)
+}
+
+pub fn get_mod_index(mods: &[ModViewModel], id: ModId) -> Option<usize> {
+    mods.iter().position(|m| m.id == id)
+}
+
+/// the mods whose folder is gone
+/// external entries are kept as long as they are in the cfg
+pub fn get_broken_mods(mods: &[ModViewModel]) -> Vec<ModId> {
+    mods.iter()
+        .filter(|m| !m.external && !m.full_name.exists())
+        .map(|m| m.id)
+        .collect()
+}
+
+impl TemplateApp {
+    pub(crate) fn get_mod(&self, id: ModId) -> Option<&ModViewModel> {
+        self.mods.iter().find(|m| m.id == id)
+    }
+
+    /// removes mods from the list, their separators move to the mod that follows them
+    pub(crate) fn remove_mods(&mut self, ids: &[ModId]) {
+        self.retain_mods(|m| !ids.contains(&m.id));
+    }
+
+    /// runs a destructive action once the user confirmed it
+    pub(crate) fn confirm_action(&mut self, action: EConfirmAction) -> bool {
+        match action {
+            EConfirmAction::Uninstall(ids) => {
+                for id in ids {
+                    self.uninstall_mod(id);
+                }
+                true
+            }
+            EConfirmAction::Remove(ids) => {
+                self.remove_mods(&ids);
+                self.update_profile_mods();
+                self.update_plugins();
+                self.update_profile_plugins();
+                self.update_watched_paths();
+                self.is_cfg_outdated = true;
+                self.update_file_conflicts();
+                self.update_record_conflicts();
+                true
+            }
+            EConfirmAction::DeleteTrashed(path) => {
+                let Some(trashed) = self.trash.iter().find(|t| t.path == path) else { return false };
+                let result = delete_from_trash(trashed);
+                self.refresh_trash();
+                match result {
+                    Ok(_) => true,
+                    Err(err) => {
+                        error!("Could not delete {}: {}", path.display(), err);
+                        false
+                    }
+                }
+            }
+            EConfirmAction::ReplaceMods { modlist, .. } => {
+                self.install_modlist(&modlist, true);
+                true
+            }
+            EConfirmAction::RemoveArchive(file_name) => self.remove_archive(&file_name),
+            EConfirmAction::EmptyTrash => {
+                let Some(library) = &self.mods_library else { return false };
+                let result = empty_trash(library);
+                self.refresh_trash();
+                match result {
+                    Ok(_) => true,
+                    Err(err) => {
+                        error!("Could not empty the trash: {}", err);
+                        false
+                    }
+                }
+            }
+        }
+    }
+
+    /// re-reads the trash of the mods library
+    pub(crate) fn refresh_trash(&mut self) {
+        self.trash = match &self.mods_library {
+            Some(library) => get_trashed_mods(library),
+            None => vec![],
+        };
+    }
+
+    /// moves a mod from the trash back into the mods library, it is added disabled with its metadata
+    pub(crate) fn restore_mod(&mut self, path: &Path) -> bool {
+        let (Some(library), Some(trashed)) = (
+            self.mods_library.clone(),
+            self.trash.iter().find(|t| t.path == path).cloned(),
+        ) else {
+            return false;
+        };
+        let result = restore_from_trash(&library, &trashed);
+        self.refresh_trash();
+        let mod_dir = match result {
+            Ok(mod_dir) => mod_dir,
+            Err(err) => {
+                error!("Could not restore {}: {}", trashed.name, err);
+                return false;
+            }
+        };
+        self.sync_mods_library();
+        if let Some(mod_info) = self.mods.iter_mut().find(|m| m.full_name == mod_dir) {
+            mod_info.meta_data = trashed.meta_data;
+        }
+        self.update_profile_mods();
+        self.update_plugins();
+        self.update_profile_plugins();
+        true
+    }
+}
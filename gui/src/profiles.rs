@@ -0,0 +1,349 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use common::{
+    diff::CfgDiff,
+    get_openmwcfg,
+    profile::{
+        copy_dir, get_manager_dir, get_profile_cfg, get_profiles, get_profiles_dir,
+        is_base_game_dir, write_profile_list, ManagerSettings, ProfileLists, MODS_LIST, OPENMW_CFG,
+        PLUGINS_LIST, SETTINGS_CFG,
+    },
+};
+use log::{error, info, warn};
+
+use crate::{EModTag, TemplateApp};
+
+/// Profile dialogs that need user input
+#[derive(PartialEq, Clone, Copy)]
+pub enum EProfileDialog {
+    Create,
+    Clone,
+    Rename,
+    Delete,
+}
+
+/// the folder of the omwmm settings and profiles
+/// without a config folder it is in the temp folder, like the instance lock
+pub(crate) fn get_omwmm_dir() -> PathBuf {
+    get_manager_dir().unwrap_or_else(|| std::env::temp_dir().join("omwmm"))
+}
+
+impl TemplateApp {
+    /// Gets the folder where all profiles are stored
+    pub fn get_profiles_dir() -> PathBuf {
+        get_profiles_dir(&get_omwmm_dir())
+    }
+
+    /// Gets a path to the current profile dir and creates it if it doesn't exist
+    /// if it can not be created the path is returned anyway, the callers report their failing file operations
+    pub fn get_current_profile_dir(&self) -> PathBuf {
+        let current_profile_dir = Self::get_profiles_dir().join(self.current_profile.as_str());
+        if !current_profile_dir.exists() {
+            if let Err(err) = fs::create_dir_all(&current_profile_dir) {
+                error!(
+                    "Could not create profile dir {}: {}",
+                    current_profile_dir.display(),
+                    err
+                );
+            }
+        }
+        current_profile_dir
+    }
+
+    /// Gets the openmw.cfg of the current profile
+    /// if it doesn't exist yet it is created from the default openmw.cfg
+    pub fn get_profile_cfg(&self) -> PathBuf {
+        get_profile_cfg(&self.get_current_profile_dir())
+    }
+
+    /// picks up the profile and libraries that omw-util changed while omwmm was closed
+    pub(crate) fn load_manager_settings(&mut self) {
+        let Some(settings) = ManagerSettings::read(&get_omwmm_dir()) else { return };
+        if settings.current_profile != self.current_profile {
+            info!("Profile was switched to {}", settings.current_profile);
+        }
+        self.current_profile = settings.current_profile;
+        self.mods_library = settings.mods_library;
+        self.downloads_library = settings.downloads_library;
+    }
+
+    /// shares the current profile and the libraries with omw-util
+    pub(crate) fn save_manager_settings(&self) {
+        let settings = ManagerSettings {
+            current_profile: self.current_profile.to_owned(),
+            mods_library: self.mods_library.clone(),
+            downloads_library: self.downloads_library.clone(),
+        };
+        if let Err(err) = settings.write(&get_omwmm_dir()) {
+            warn!("Could not save omwmm settings: {}", err);
+        }
+    }
+
+    /// copies the default settings.cfg to the current profile
+    /// with per-profile settings this only happens once, otherwise the default settings are synced every time
+    pub(crate) fn update_profile_settings(&self) {
+        let settings_path = self.get_current_profile_dir().join(SETTINGS_CFG);
+        if self.use_profile_settings && settings_path.exists() {
+            return;
+        }
+        let Some(default_settings) = get_openmwcfg()
+            .and_then(|cfg| cfg.parent().map(|dir| dir.join(SETTINGS_CFG)))
+            .filter(|p| p.exists())
+        else {
+            warn!("Could not find default settings.cfg");
+            return;
+        };
+        if let Err(err) = fs::copy(&default_settings, &settings_path) {
+            error!(
+                "Could not copy settings {}: {}",
+                default_settings.display(),
+                err
+            );
+        }
+    }
+
+    /// Command line arguments to run OpenMW with the config of the current profile
+    pub fn get_launch_args(&self) -> Vec<String> {
+        vec![
+            "--replace=config".to_owned(),
+            format!("--config={}", self.get_current_profile_dir().display()),
+        ]
+    }
+
+    /// refreshes the runtime cache of profile names from the profiles folder
+    pub(crate) fn refresh_profiles(&mut self) {
+        self.profiles = get_profiles(&Self::get_profiles_dir());
+        if !self.profiles.contains(&self.current_profile) {
+            self.profiles.push(self.current_profile.to_owned());
+        }
+        self.profiles.sort();
+    }
+
+    /// saves the current profile and loads another one
+    pub(crate) fn switch_profile(&mut self, name: &str) {
+        if name == self.current_profile {
+            return;
+        }
+
+        self.update_profile_mods();
+        self.update_profile_plugins();
+        self.load_profile(name);
+    }
+
+    /// loads another profile without saving the current one
+    pub(crate) fn load_profile(&mut self, name: &str) {
+        info!("Switching to profile {}", name);
+        self.current_profile = name.to_owned();
+        self.init = false;
+        self.init_profile();
+        // the cfg is written once the changes are applied
+        self.is_cfg_outdated = true;
+        self.save_manager_settings();
+    }
+
+    /// creates a new empty profile and switches to it
+    pub(crate) fn create_profile(&mut self, name: &str) -> bool {
+        let Some(profile_dir) = self.get_new_profile_dir(name) else { return false };
+        if let Err(err) = fs::create_dir_all(&profile_dir) {
+            error!("Could not create profile {}: {}", name, err);
+            return false;
+        }
+
+        self.switch_profile(name);
+        self.refresh_profiles();
+        true
+    }
+
+    /// copies the current profile to a new profile and switches to it
+    pub(crate) fn clone_profile(&mut self, name: &str) -> bool {
+        let Some(profile_dir) = self.get_new_profile_dir(name) else { return false };
+
+        // make sure the copied lists are up to date
+        self.update_profile_mods();
+        self.update_profile_plugins();
+
+        if let Err(err) = copy_dir(&self.get_current_profile_dir(), &profile_dir) {
+            error!("Could not clone profile {}: {}", self.current_profile, err);
+            return false;
+        }
+
+        self.switch_profile(name);
+        self.refresh_profiles();
+        true
+    }
+
+    /// renames the current profile
+    pub(crate) fn rename_profile(&mut self, name: &str) -> bool {
+        let Some(profile_dir) = self.get_new_profile_dir(name) else { return false };
+        if let Err(err) = fs::rename(self.get_current_profile_dir(), profile_dir) {
+            error!("Could not rename profile {}: {}", self.current_profile, err);
+            return false;
+        }
+
+        self.current_profile = name.to_owned();
+        self.refresh_profiles();
+        self.save_manager_settings();
+        true
+    }
+
+    /// deletes the current profile and switches to the next remaining one
+    pub(crate) fn delete_profile(&mut self) -> bool {
+        let Some(next_profile) = self
+            .profiles
+            .iter()
+            .find(|p| **p != self.current_profile)
+            .cloned()
+        else {
+            warn!("Can not delete the last profile");
+            return false;
+        };
+
+        let profile_dir = self.get_current_profile_dir();
+        if let Err(err) = fs::remove_dir_all(profile_dir) {
+            error!("Could not delete profile {}: {}", self.current_profile, err);
+            return false;
+        }
+
+        // don't save the deleted profile on switching
+        info!("Switching to profile {}", next_profile);
+        self.current_profile = next_profile;
+        self.init = false;
+        self.init_profile();
+        // the cfg is written once the changes are applied
+        self.is_cfg_outdated = true;
+        self.refresh_profiles();
+        self.save_manager_settings();
+        true
+    }
+
+    /// checks a new profile name and returns the profile dir
+    pub(crate) fn get_new_profile_dir(&self, name: &str) -> Option<PathBuf> {
+        common::profile::get_new_profile_dir(&Self::get_profiles_dir(), name)
+    }
+
+    /// base game data is decided by the tag of the mod, other data directories are detected
+    pub(crate) fn is_base_game(&self, path: &Path) -> bool {
+        match self.mods.iter().find(|m| m.full_name == path) {
+            Some(mod_info) => mod_info.tag == EModTag::BaseGame,
+            None => is_base_game_dir(path),
+        }
+    }
+
+    /// the mod and plugin lists of the current profile
+    pub(crate) fn load_profile_lists(&self) -> ProfileLists {
+        ProfileLists::load_with(
+            &self.get_current_profile_dir(),
+            self.mods_library.as_deref(),
+            |path| self.is_base_game(path),
+        )
+    }
+
+    /// the mod and plugin lists as shown, with the external entries
+    pub(crate) fn get_profile_lists(&self) -> ProfileLists {
+        ProfileLists {
+            mods: self
+                .mods
+                .iter()
+                .map(|m| (m.full_name.to_owned(), m.enabled))
+                .collect(),
+            plugins: self
+                .plugins
+                .iter()
+                .map(|p| (p.name.to_owned(), p.enabled))
+                .collect(),
+        }
+    }
+
+    /// applies the mod order and enabled state of the current profile
+    /// mods that are not in the profile are appended at the end and disabled
+    pub(crate) fn load_profile_mods(&mut self) {
+        let lists = self.load_profile_lists();
+        let order: HashMap<&PathBuf, (usize, bool)> = lists
+            .mods
+            .iter()
+            .enumerate()
+            .map(|(i, (path, enabled))| (path, (i, *enabled)))
+            .collect();
+
+        self.mods
+            .sort_by_key(|m| order.get(&m.full_name).map_or(usize::MAX, |(i, _)| *i));
+        for m in self.mods.iter_mut() {
+            // base game data is always enabled
+            m.enabled = order
+                .get(&m.full_name)
+                .map_or(m.tag == EModTag::BaseGame, |(_, enabled)| *enabled);
+        }
+    }
+
+    /// applies the plugin order and enabled state of the current profile
+    /// plugins that are not in the profile are appended at the end and disabled, except base game plugins
+    pub(crate) fn load_profile_plugins(&mut self) {
+        let lists = self.load_profile_lists();
+        let order: HashMap<&String, (usize, bool)> = lists
+            .plugins
+            .iter()
+            .enumerate()
+            .map(|(i, (name, enabled))| (name, (i, *enabled)))
+            .collect();
+
+        self.plugins
+            .sort_by_key(|p| order.get(&p.name).map_or(usize::MAX, |(i, _)| *i));
+        for p in self.plugins.iter_mut() {
+            p.enabled = order.get(&p.name).is_some_and(|(_, enabled)| *enabled);
+        }
+    }
+
+    /// compares the cfg on disk with what update_cfg writes,
+    /// or what the current profile loads with what another profile loads
+    pub(crate) fn get_cfg_diff(&self, with: Option<&str>) -> CfgDiff {
+        // the preview does not create the cfgs, a missing cfg has no entries
+        let (data, content) = self.get_cfg_entries();
+        match with {
+            None => CfgDiff::from_cfg(
+                &self.get_current_profile_dir().join(OPENMW_CFG),
+                &data,
+                &content,
+            ),
+            Some(name) => {
+                let profile_dir = Self::get_profiles_dir().join(name);
+                let lists = ProfileLists::load(&profile_dir, self.mods_library.as_deref());
+                let (other_data, other_content) =
+                    lists.get_merged_cfg_entries(&profile_dir.join(OPENMW_CFG));
+                CfgDiff::new(&data, &content, &other_data, &other_content)
+            }
+        }
+    }
+
+    /// opens the diff view or updates it
+    pub(crate) fn refresh_cfg_diff(&mut self) {
+        self.cfg_diff = Some(self.get_cfg_diff(self.cfg_diff_with.as_deref()));
+    }
+
+    /// serializes the mod order and enabled state to the profile
+    pub(crate) fn update_profile_mods(&self) {
+        let mods_list_path = self.get_current_profile_dir().join(MODS_LIST);
+        write_profile_list(
+            &mods_list_path,
+            self.mods
+                .iter()
+                .filter(|m| !m.external)
+                .map(|m| (m.full_name.to_string_lossy().into_owned(), m.enabled)),
+        );
+    }
+
+    /// serializes the plugin order and enabled state to the profile
+    pub(crate) fn update_profile_plugins(&self) {
+        let plugins_list_path = self.get_current_profile_dir().join(PLUGINS_LIST);
+        write_profile_list(
+            &plugins_list_path,
+            self.plugins
+                .iter()
+                .filter(|p| !p.external)
+                .map(|p| (p.name.to_owned(), p.enabled)),
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use std::path::PathBuf;
+
+use crate::{ModSeparator, ModViewModel, TemplateApp};
+
+/// An entry of the mod list view
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EModRow {
+    /// index into the separators
+    Separator(usize),
+    /// index into the mods
+    Mod(usize),
+}
+
+/// the mod list with each separator placed before its mod
+/// separators without a mod, or with a mod that is gone, are placed at the end
+pub fn get_mod_rows(mods: &[PathBuf], separators: &[ModSeparator]) -> Vec<EModRow> {
+    let mut rows = vec![];
+    for (i, path) in mods.iter().enumerate() {
+        for (j, _) in separators
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.before.as_ref() == Some(path))
+        {
+            rows.push(EModRow::Separator(j));
+        }
+        rows.push(EModRow::Mod(i));
+    }
+    for (j, separator) in separators.iter().enumerate() {
+        if !separator.before.as_ref().is_some_and(|p| mods.contains(p)) {
+            rows.push(EModRow::Separator(j));
+        }
+    }
+    rows
+}
+
+/// the rows without the mods of collapsed separators
+pub fn get_visible_rows(rows: &[EModRow], separators: &[ModSeparator]) -> Vec<EModRow> {
+    let mut collapsed = false;
+    rows.iter()
+        .filter(|row| match row {
+            EModRow::Separator(j) => {
+                collapsed = separators[*j].collapsed;
+                true
+            }
+            EModRow::Mod(_) => !collapsed,
+        })
+        .copied()
+        .collect()
+}
+
+/// the mods of each separator, by separator index
+pub fn get_group_mods(rows: &[EModRow], separator_count: usize) -> Vec<Vec<usize>> {
+    let mut groups = vec![vec![]; separator_count];
+    let mut current: Option<usize> = None;
+    for row in rows {
+        match row {
+            EModRow::Separator(j) => current = Some(*j),
+            EModRow::Mod(i) => {
+                if let Some(j) = current {
+                    groups[j].push(*i);
+                }
+            }
+        }
+    }
+    groups
+}
+
+/// moves a visible row before another visible row, to may be the length of the visible rows
+/// a separator moves with all of its mods and is only dropped between groups
+pub fn move_row(rows: &[EModRow], visible: &[EModRow], from: usize, to: usize) -> Vec<EModRow> {
+    let Some(moved) = visible.get(from) else {
+        return rows.to_vec();
+    };
+    let Some(start) = rows.iter().position(|r| r == moved) else {
+        return rows.to_vec();
+    };
+    let is_separator = matches!(moved, EModRow::Separator(_));
+    let end = if is_separator {
+        get_next_separator(rows, start + 1)
+    } else {
+        start + 1
+    };
+
+    let mut target = visible
+        .get(to)
+        .and_then(|row| rows.iter().position(|r| r == row))
+        .unwrap_or(rows.len());
+    if is_separator {
+        target = get_next_separator(rows, target);
+    }
+    if target >= start && target <= end {
+        return rows.to_vec();
+    }
+
+    let mut result = rows.to_vec();
+    let block: Vec<EModRow> = result.drain(start..end).collect();
+    if target > start {
+        target -= block.len();
+    }
+    result.splice(target..target, block);
+    result
+}
+
+/// moves mods before a row, keeping their order
+pub fn move_mods(rows: &[EModRow], mods: &[usize], target: usize) -> Vec<EModRow> {
+    let is_moved = |row: &EModRow| matches!(row, EModRow::Mod(i) if mods.contains(i));
+    let before = rows.iter().take(target).filter(|r| is_moved(r)).count();
+    let (block, mut result): (Vec<EModRow>, Vec<EModRow>) = rows.iter().partition(|r| is_moved(r));
+    let target = target.min(rows.len()) - before;
+    result.splice(target..target, block);
+    result
+}
+
+/// the row after the last mod of a separator
+pub fn get_group_end(rows: &[EModRow], separator: usize) -> Option<usize> {
+    let start = rows
+        .iter()
+        .position(|r| *r == EModRow::Separator(separator))?;
+    Some(get_next_separator(rows, start + 1))
+}
+
+fn get_next_separator(rows: &[EModRow], start: usize) -> usize {
+    rows.iter()
+        .skip(start)
+        .position(|r| matches!(r, EModRow::Separator(_)))
+        .map_or(rows.len(), |p| start + p)
+}
+
+/// the mod order of the rows and the separators in row order, placed before the mod that follows them
+pub fn apply_mod_rows(
+    rows: &[EModRow],
+    mods: &[PathBuf],
+    separators: &[ModSeparator],
+) -> (Vec<usize>, Vec<ModSeparator>) {
+    let mut order = vec![];
+    let mut result: Vec<ModSeparator> = vec![];
+    // separators waiting for the next mod
+    let mut pending: Vec<usize> = vec![];
+    for row in rows {
+        match row {
+            EModRow::Separator(j) => {
+                pending.push(result.len());
+                result.push(separators[*j].clone());
+            }
+            EModRow::Mod(i) => {
+                for j in pending.drain(..) {
+                    result[j].before = Some(mods[*i].to_owned());
+                }
+                order.push(*i);
+            }
+        }
+    }
+    for j in pending {
+        result[j].before = None;
+    }
+    (order, result)
+}
+
+impl TemplateApp {
+    /// the rows of the mod list view
+    pub(crate) fn get_mod_rows(&self) -> Vec<EModRow> {
+        let paths: Vec<PathBuf> = self.mods.iter().map(|m| m.full_name.to_owned()).collect();
+        get_mod_rows(&paths, &self.mod_separators)
+    }
+
+    /// reorders the mods and separators like the rows
+    pub(crate) fn set_mod_rows(&mut self, rows: &[EModRow]) {
+        let paths: Vec<PathBuf> = self.mods.iter().map(|m| m.full_name.to_owned()).collect();
+        let (order, separators) = apply_mod_rows(rows, &paths, &self.mod_separators);
+        let mut mods: Vec<Option<ModViewModel>> = std::mem::take(&mut self.mods)
+            .into_iter()
+            .map(Some)
+            .collect();
+        self.mods = order.into_iter().filter_map(|i| mods[i].take()).collect();
+        self.mod_separators = separators;
+    }
+
+    /// removes the mods that do not match, their separators move to the mod that follows them
+    pub(crate) fn retain_mods<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&ModViewModel) -> bool,
+    {
+        let rows: Vec<EModRow> = self
+            .get_mod_rows()
+            .into_iter()
+            .filter(|row| match row {
+                EModRow::Mod(i) => keep(&self.mods[*i]),
+                EModRow::Separator(_) => true,
+            })
+            .collect();
+        self.set_mod_rows(&rows);
+    }
+
+    /// the category set by the user or the Nexus Mods category of a mod
+    pub(crate) fn get_mod_category(&self, mod_info: &ModViewModel) -> Option<String> {
+        mod_info.category.clone().or_else(|| {
+            self.mod_infos
+                .get(&mod_info.full_name)
+                .and_then(|info| info.category.clone())
+        })
+    }
+
+    /// all categories of the installed mods, sorted
+    pub(crate) fn get_mod_categories(&self) -> Vec<String> {
+        let mut categories: Vec<String> = self
+            .mods
+            .iter()
+            .filter_map(|m| self.get_mod_category(m))
+            .collect();
+        categories.sort();
+        categories.dedup();
+        categories
+    }
+}
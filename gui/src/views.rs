@@ -1,3 +1,112 @@
+use std::{
+    fmt::Display,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+pub(crate) mod diff_view;
+pub(crate) mod downloads_view;
+pub(crate) mod jobs_view;
 pub(crate) mod main_view;
 pub(crate) mod menu_bar_view;
 pub(crate) mod right_side_view;
+
+use crate::EColorTag;
+
+/// max entries listed in a conflicts tooltip
+const MAX_TOOLTIP_ENTRIES: usize = 20;
+
+/// win/lose indicators for the conflicts of a mod or plugin
+pub(crate) fn conflicts_ui<T: Display>(ui: &mut egui::Ui, overrides: &[T], overridden: &[T]) {
+    if !overrides.is_empty() {
+        ui.label(egui::RichText::new(format!("+{}", overrides.len())).color(egui::Color32::GREEN))
+            .on_hover_text(format!(
+                "Overrides {}:\n{}",
+                overrides.len(),
+                conflicts_tooltip(overrides)
+            ));
+    }
+    if !overridden.is_empty() {
+        ui.label(egui::RichText::new(format!("-{}", overridden.len())).color(egui::Color32::RED))
+            .on_hover_text(format!(
+                "Loses {}:\n{}",
+                overridden.len(),
+                conflicts_tooltip(overridden)
+            ));
+    }
+}
+
+/// picks the colour tag of a mod or separator, returns true if it changed
+pub(crate) fn color_tag_ui(ui: &mut egui::Ui, color: &mut Option<EColorTag>) -> bool {
+    let mut is_changed = ui.radio_value(color, None, "None").clicked();
+    for tag in EColorTag::ALL {
+        is_changed |= ui
+            .radio_value(
+                color,
+                Some(tag),
+                egui::RichText::new(format!("{:?}", tag)).color(egui::Color32::from(tag)),
+            )
+            .clicked();
+    }
+    is_changed
+}
+
+fn conflicts_tooltip<T: Display>(entries: &[T]) -> String {
+    let mut text = entries
+        .iter()
+        .take(MAX_TOOLTIP_ENTRIES)
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+    if entries.len() > MAX_TOOLTIP_ENTRIES {
+        text.push_str(&format!(
+            "\n... and {} more",
+            entries.len() - MAX_TOOLTIP_ENTRIES
+        ));
+    }
+    text
+}
+
+/// human readable file size: 1.5 MB
+pub(crate) fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if size < 1024 {
+        return format!("{} B", size);
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// UTC date and time of a file: 2023-06-30 14:05
+pub(crate) fn format_date(time: SystemTime) -> String {
+    let Ok(duration) = time.duration_since(UNIX_EPOCH) else {
+        return String::new();
+    };
+    let seconds = duration.as_secs();
+    let days = (seconds / 86400) as i64;
+    let minutes = (seconds % 86400) / 60;
+
+    // civil date from days since 1970-01-01 (Howard Hinnant)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        minutes / 60,
+        minutes % 60
+    )
+}
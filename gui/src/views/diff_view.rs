@@ -0,0 +1,139 @@
+use common::diff::EDiffKind;
+
+use crate::TemplateApp;
+
+impl TemplateApp {
+    /// lists the data and content entries that are added, removed or moved
+    /// between the cfg on disk and the lists, or between two profiles
+    pub fn cfg_diff_view(&mut self, ctx: &egui::Context) {
+        let Some(diff) = &self.cfg_diff else { return };
+
+        let mut open = true;
+        let mut to_compare: Option<Option<String>> = None;
+        let mut to_refresh = false;
+        let mut to_apply = false;
+        egui::Window::new("Config diff")
+            .open(&mut open)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Compare with: ");
+                    egui::ComboBox::from_id_source("cfg_diff_with")
+                        .selected_text(self.cfg_diff_with.as_deref().unwrap_or("openmw.cfg"))
+                        .show_ui(ui, |ui| {
+                            if ui
+                                .selectable_label(self.cfg_diff_with.is_none(), "openmw.cfg")
+                                .clicked()
+                            {
+                                to_compare = Some(None);
+                            }
+                            for profile in
+                                self.profiles.iter().filter(|p| **p != self.current_profile)
+                            {
+                                if ui
+                                    .selectable_label(
+                                        self.cfg_diff_with.as_ref() == Some(profile),
+                                        profile,
+                                    )
+                                    .clicked()
+                                {
+                                    to_compare = Some(Some(profile.to_owned()));
+                                }
+                            }
+                        });
+                    if ui.button("Refresh").clicked() {
+                        to_refresh = true;
+                    }
+                });
+                ui.checkbox(&mut self.cfg_diff_show_unchanged, "Show unchanged");
+                match &self.cfg_diff_with {
+                    None => ui.weak("Changes Apply writes to openmw.cfg"),
+                    Some(profile) => ui.weak(format!(
+                        "Changes from {} to {}",
+                        self.current_profile, profile
+                    )),
+                };
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        if diff.is_empty() {
+                            ui.label("No changes");
+                        }
+                        ui.strong("Data");
+                        for entry in diff.data.iter() {
+                            diff_entry_ui(
+                                ui,
+                                entry.kind,
+                                &entry.entry.to_string_lossy(),
+                                self.cfg_diff_show_unchanged,
+                            );
+                        }
+                        ui.separator();
+                        ui.strong("Content");
+                        for entry in diff.content.iter() {
+                            diff_entry_ui(
+                                ui,
+                                entry.kind,
+                                &entry.entry,
+                                self.cfg_diff_show_unchanged,
+                            );
+                        }
+                    });
+
+                if self.cfg_diff_with.is_none() {
+                    ui.separator();
+                    if ui
+                        .add_enabled(!diff.is_empty(), egui::Button::new("Apply"))
+                        .clicked()
+                    {
+                        to_apply = true;
+                    }
+                }
+            });
+
+        if to_apply {
+            if self.update_cfg() {
+                self.toasts.success("openmw.cfg updated");
+            } else {
+                self.toasts.error("Could not update openmw.cfg");
+            }
+            to_refresh = true;
+        }
+        if let Some(with) = to_compare {
+            self.cfg_diff_with = with;
+            to_refresh = true;
+        }
+        if to_refresh {
+            self.refresh_cfg_diff();
+        }
+        if !open {
+            self.cfg_diff = None;
+        }
+    }
+}
+
+/// a line of the diff: + added, - removed, ~ moved with its old and new position
+fn diff_entry_ui(ui: &mut egui::Ui, kind: EDiffKind, text: &str, show_unchanged: bool) {
+    match kind {
+        EDiffKind::Unchanged => {
+            if show_unchanged {
+                ui.weak(format!("  {}", text));
+            }
+        }
+        EDiffKind::Added => {
+            ui.colored_label(egui::Color32::GREEN, format!("+ {}", text));
+        }
+        EDiffKind::Removed => {
+            ui.colored_label(egui::Color32::RED, format!("- {}", text));
+        }
+        EDiffKind::Moved { from, to } => {
+            ui.colored_label(
+                egui::Color32::YELLOW,
+                format!("~ {} ({} -> {})", text, from + 1, to + 1),
+            );
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use std::{cmp::Ordering, path::PathBuf};
+
+use common::meta::compare_versions;
+use log::warn;
+
+use crate::{
+    app::EDownloadsColumn,
+    mods::EConfirmAction,
+    views::{format_date, format_size},
+    ArchiveViewModel, EInstallState, TemplateApp,
+};
+
+/// an archive with its install state and the mod it is installed as
+type DownloadRow<'a> = (&'a ArchiveViewModel, EInstallState, Option<PathBuf>);
+
+impl TemplateApp {
+    /// table of the mod archives in the downloads library
+    pub fn downloads_view(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Archives");
+        ui.separator();
+
+        // check
+        let Some(library_path) = self.downloads_library.clone() else { return };
+
+        ui.horizontal(|ui| {
+            ui.label("Filter: ");
+            ui.text_edit_singleline(&mut self.downloads_filter);
+            if ui.button("x").on_hover_text("Clear filter").clicked() {
+                self.downloads_filter.clear();
+            }
+            if ui.button("Open on disk").clicked() && open::that(&library_path).is_err() {
+                self.toasts.error("Could not open downloads library");
+            }
+        });
+        ui.separator();
+
+        // nxm links waiting for the running download
+        if !self.download_queue.is_empty() {
+            let mut to_dequeue = None;
+            ui.label(format!("Queued downloads: {}", self.download_queue.len()));
+            for (i, link) in self.download_queue.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "{} mod {} file {}",
+                        link.game, link.mod_id, link.file_id
+                    ));
+                    if ui
+                        .small_button("x")
+                        .on_hover_text("Remove from queue")
+                        .clicked()
+                    {
+                        to_dequeue = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = to_dequeue {
+                self.download_queue.remove(i);
+            }
+            ui.separator();
+        }
+
+        // filtered and sorted rows
+        let filter = self.downloads_filter.to_lowercase();
+        let mut rows: Vec<DownloadRow> = self
+            .downloads
+            .iter()
+            .filter(|a| filter.is_empty() || matches_filter(a, &filter))
+            .map(|a| {
+                let (state, mod_path) = self.get_install_state(a);
+                (a, state, mod_path)
+            })
+            .collect();
+        let mut sort = self.downloads_sort;
+        let mut ascending = self.downloads_sort_ascending;
+        rows.sort_by(|a, b| {
+            let ordering = compare_rows(sort, a, b);
+            if ascending {
+                ordering
+            } else {
+                ordering.reverse()
+            }
+        });
+
+        let mut to_install: Option<(String, Option<PathBuf>)> = None;
+        let mut to_hash: Option<String> = None;
+        let mut to_remove: Option<String> = None;
+        egui::ScrollArea::both().show(ui, |ui| {
+            egui::Grid::new("downloads_grid")
+                .num_columns(7)
+                .striped(true)
+                .show(ui, |ui| {
+                    for (title, column) in [
+                        ("Name", EDownloadsColumn::Name),
+                        ("Version", EDownloadsColumn::Version),
+                        ("Size", EDownloadsColumn::Size),
+                        ("Date", EDownloadsColumn::Date),
+                        ("Nexus id", EDownloadsColumn::NexusId),
+                        ("State", EDownloadsColumn::State),
+                    ] {
+                        header_ui(ui, title, column, &mut sort, &mut ascending);
+                    }
+                    ui.label("");
+                    ui.end_row();
+
+                    for (archive, state, mod_path) in rows.iter() {
+                        let meta = archive.meta_data.as_ref();
+
+                        // name
+                        let mut response = ui
+                            .add(egui::Label::new(&archive.file_name).sense(egui::Sense::click()));
+                        if let Some(mod_name) = meta.and_then(|m| m.mod_name.as_ref()) {
+                            response = response.on_hover_text(mod_name);
+                        }
+                        if let Some(hash) = &archive.hash {
+                            response = response.on_hover_text(format!("sha256: {}", hash));
+                        }
+                        // install mod
+                        if response.double_clicked() && *state == EInstallState::NotInstalled {
+                            to_install = Some((archive.file_name.to_owned(), None));
+                        }
+                        response.context_menu(|ui| {
+                            if ui
+                                .add_enabled(
+                                    *state != EInstallState::Installed,
+                                    egui::Button::new("Install"),
+                                )
+                                .clicked()
+                            {
+                                to_install = Some((archive.file_name.to_owned(), None));
+                                ui.close_menu();
+                            }
+                            if *state == EInstallState::InstalledOlder
+                                && ui.button("Update installed mod").clicked()
+                            {
+                                to_install = Some((archive.file_name.to_owned(), mod_path.clone()));
+                                ui.close_menu();
+                            }
+                            if ui
+                                .add_enabled(
+                                    *state == EInstallState::Installed,
+                                    egui::Button::new("Reinstall"),
+                                )
+                                .clicked()
+                            {
+                                to_install = Some((archive.file_name.to_owned(), mod_path.clone()));
+                                ui.close_menu();
+                            }
+                            if ui.button("Compute hash").clicked() {
+                                to_hash = Some(archive.file_name.to_owned());
+                                ui.close_menu();
+                            }
+                            if ui.button("Open on disk").clicked() {
+                                if open::that(&library_path).is_err() {
+                                    warn!("Could not open {}", library_path.display());
+                                }
+                                ui.close_menu();
+                            }
+                            ui.separator();
+                            if ui.button("Remove archive").clicked() {
+                                to_remove = Some(archive.file_name.to_owned());
+                                ui.close_menu();
+                            }
+                        });
+
+                        // metadata
+                        ui.label(meta.and_then(|m| m.version.as_deref()).unwrap_or_default());
+                        ui.label(format_size(archive.size));
+                        ui.label(archive.modified.map(format_date).unwrap_or_default());
+                        match meta.and_then(|m| m.mod_id) {
+                            Some(mod_id) => {
+                                let game = meta
+                                    .and_then(|m| m.game_name.as_deref())
+                                    .unwrap_or("morrowind");
+                                ui.hyperlink_to(
+                                    mod_id.to_string(),
+                                    format!("https://www.nexusmods.com/{}/mods/{}", game, mod_id),
+                                );
+                            }
+                            None => {
+                                ui.label("");
+                            }
+                        }
+
+                        // install state
+                        match state {
+                            EInstallState::NotInstalled => {
+                                ui.weak("not installed");
+                            }
+                            EInstallState::InstalledOlder => {
+                                ui.colored_label(egui::Color32::YELLOW, "older version installed");
+                            }
+                            EInstallState::Installed => {
+                                ui.colored_label(egui::Color32::GREEN, "installed");
+                            }
+                        }
+
+                        // actions
+                        let action = match state {
+                            EInstallState::NotInstalled => "Install",
+                            EInstallState::InstalledOlder => "Update",
+                            EInstallState::Installed => "Reinstall",
+                        };
+                        if ui.button(action).clicked() {
+                            // updates replace the older version in place
+                            to_install = Some((archive.file_name.to_owned(), mod_path.clone()));
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+        self.downloads_sort = sort;
+        self.downloads_sort_ascending = ascending;
+
+        if let Some((file_name, replace)) = to_install {
+            self.install_archive(&file_name, replace);
+        }
+        if let Some(file_name) = to_hash {
+            self.hash_archive(&file_name);
+        }
+        if let Some(file_name) = to_remove {
+            self.pending_confirmation = Some(EConfirmAction::RemoveArchive(file_name));
+        }
+    }
+}
+
+/// a clickable column header, clicking the sorted column again reverses the order
+fn header_ui(
+    ui: &mut egui::Ui,
+    title: &str,
+    column: EDownloadsColumn,
+    sort: &mut EDownloadsColumn,
+    ascending: &mut bool,
+) {
+    let text = if *sort == column {
+        format!("{} {}", title, if *ascending { "⬆" } else { "⬇" })
+    } else {
+        title.to_owned()
+    };
+    if ui
+        .add(egui::Label::new(egui::RichText::new(text).strong()).sense(egui::Sense::click()))
+        .clicked()
+    {
+        if *sort == column {
+            *ascending = !*ascending;
+        } else {
+            *sort = column;
+            *ascending = true;
+        }
+    }
+}
+
+/// case-insensitive match on the file name and the nexus names
+fn matches_filter(archive: &ArchiveViewModel, filter: &str) -> bool {
+    if archive.file_name.to_lowercase().contains(filter) {
+        return true;
+    }
+    archive.meta_data.as_ref().is_some_and(|m| {
+        [&m.name, &m.mod_name]
+            .into_iter()
+            .flatten()
+            .any(|name| name.to_lowercase().contains(filter))
+    })
+}
+
+fn compare_rows(column: EDownloadsColumn, a: &DownloadRow, b: &DownloadRow) -> Ordering {
+    let (a_meta, b_meta) = (a.0.meta_data.as_ref(), b.0.meta_data.as_ref());
+    match column {
+        EDownloadsColumn::Name => {
+            a.0.file_name
+                .to_lowercase()
+                .cmp(&b.0.file_name.to_lowercase())
+        }
+        EDownloadsColumn::Version => compare_versions(
+            a_meta
+                .and_then(|m| m.version.as_deref())
+                .unwrap_or_default(),
+            b_meta
+                .and_then(|m| m.version.as_deref())
+                .unwrap_or_default(),
+        ),
+        EDownloadsColumn::Size => a.0.size.cmp(&b.0.size),
+        EDownloadsColumn::Date => a.0.modified.cmp(&b.0.modified),
+        EDownloadsColumn::NexusId => a_meta
+            .and_then(|m| m.mod_id)
+            .cmp(&b_meta.and_then(|m| m.mod_id)),
+        EDownloadsColumn::State => a.1.cmp(&b.1),
+    }
+}
@@ -0,0 +1,25 @@
+use crate::TemplateApp;
+
+impl TemplateApp {
+    /// progress panel of the running background jobs
+    pub fn jobs_view(&mut self, ui: &mut egui::Ui) {
+        for job in self.jobs.iter() {
+            ui.horizontal(|ui| {
+                let (fraction, message) = job.get_progress();
+                ui.label(&job.name);
+                ui.add(
+                    egui::ProgressBar::new(fraction)
+                        .text(message)
+                        .desired_width(300_f32)
+                        .animate(true),
+                );
+                if ui
+                    .add_enabled(!job.is_cancelled(), egui::Button::new("Cancel"))
+                    .clicked()
+                {
+                    job.cancel();
+                }
+            });
+        }
+    }
+}
@@ -1,8 +1,60 @@
-use crate::{ModViewModel, TemplateApp};
+use std::{cmp::Ordering, path::PathBuf};
+
+use common::meta::compare_versions;
+
+use crate::{
+    filters::{get_range, is_fuzzy_match, EListFilter},
+    history::EListEdit,
+    mods::{EConfirmAction, ModId},
+    separators::{get_group_end, get_group_mods, get_visible_rows, move_mods, move_row, EModRow},
+    views::{color_tag_ui, conflicts_ui},
+    EModTag, ModSeparator, ModViewModel, TemplateApp,
+};
 
 impl TemplateApp {
     pub fn main_view(&mut self, ui: &mut egui::Ui) {
-        ui.heading("Mods");
+        ui.horizontal(|ui| {
+            ui.heading("Mods");
+            // edits of the lists are written to the cfg once they are applied
+            if ui
+                .add_enabled(self.is_cfg_outdated, egui::Button::new("Apply"))
+                .on_hover_text("Write the mod and plugin lists to openmw.cfg")
+                .clicked()
+            {
+                if self.update_cfg() {
+                    self.toasts.success("openmw.cfg updated");
+                } else {
+                    self.toasts.error("Could not update openmw.cfg");
+                }
+            }
+            if ui
+                .button("Preview")
+                .on_hover_text("Show what Apply changes in openmw.cfg")
+                .clicked()
+            {
+                self.cfg_diff_with = None;
+                self.refresh_cfg_diff();
+            }
+            if self.is_cfg_outdated {
+                ui.weak("unapplied changes");
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui
+                    .add_enabled(self.history.can_redo(), egui::Button::new("Redo"))
+                    .on_hover_text("Ctrl+Y")
+                    .clicked()
+                {
+                    self.redo();
+                }
+                if ui
+                    .add_enabled(self.history.can_undo(), egui::Button::new("Undo"))
+                    .on_hover_text("Ctrl+Z")
+                    .clicked()
+                {
+                    self.undo();
+                }
+            });
+        });
         ui.separator();
 
         // check
@@ -15,55 +67,401 @@ impl TemplateApp {
         // the installed mods info can be serialized centrally
         // we can add a health check on app start, rest is user fault
 
+        // groups and filters
+        let categories = self.get_mod_categories();
+        ui.horizontal(|ui| {
+            if ui
+                .button("Add separator")
+                .on_hover_text("Group the mods below a separator")
+                .clicked()
+            {
+                let separator = ModSeparator {
+                    name: "New separator".to_owned(),
+                    ..Default::default()
+                };
+                self.mod_separators.push(separator.clone());
+                self.record_edit(EListEdit::AddSeparator {
+                    index: self.mod_separators.len() - 1,
+                    separator,
+                });
+            }
+            ui.label("Category: ");
+            egui::ComboBox::from_id_source("mods_category_filter")
+                .selected_text(self.mods_category_filter.as_deref().unwrap_or("All"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.mods_category_filter, None, "All");
+                    for category in categories.iter() {
+                        ui.selectable_value(
+                            &mut self.mods_category_filter,
+                            Some(category.to_owned()),
+                            category,
+                        );
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Search: ");
+            ui.text_edit_singleline(&mut self.mods_filter);
+            if ui.button("x").on_hover_text("Clear search").clicked() {
+                self.mods_filter.clear();
+            }
+            egui::ComboBox::from_id_source("mods_state_filter")
+                .selected_text(self.mods_state_filter.get_name())
+                .show_ui(ui, |ui| {
+                    for filter in EListFilter::MODS {
+                        ui.selectable_value(&mut self.mods_state_filter, filter, filter.get_name());
+                    }
+                });
+        });
+        ui.separator();
+
         let mut is_any_changed = false;
-        let mut to_delete: Vec<usize> = vec![];
-        let mut i = 0;
+        // mods enabled or disabled: id, enabled before, enabled after
+        let mut toggled: Vec<(ModId, bool, bool)> = vec![];
+        // destructive actions are confirmed first
+        let mut to_confirm: Option<EConfirmAction> = None;
+        let mut to_update: Option<(String, PathBuf)> = None;
+        let mut to_add_separator: Option<PathBuf> = None;
+        let mut to_remove_separator: Option<usize> = None;
+        let mut to_enable_group: Option<(usize, bool)> = None;
+        let mut to_enable_selection: Option<bool> = None;
+        let mut to_move_selection: Option<usize> = None;
+        let mut clicked: Option<(usize, egui::Modifiers)> = None;
+        // newer archives of installed mods in the downloads library
+        let updates = self.get_mod_updates();
+
+        // a filtered list is flat and can not be reordered
+        let mod_categories: Vec<Option<String>> =
+            self.mods.iter().map(|m| self.get_mod_category(m)).collect();
+        let rows = self.get_mod_rows();
+        let is_filtered = self.mods_category_filter.is_some()
+            || !self.mods_filter.trim().is_empty()
+            || self.mods_state_filter != EListFilter::All;
+        let mut visible: Vec<EModRow> = if is_filtered {
+            rows.iter()
+                .filter(|row| {
+                    let EModRow::Mod(i) = row else { return false };
+                    let mod_info = &self.mods[*i];
+                    let is_state = match self.mods_state_filter {
+                        EListFilter::Enabled => mod_info.enabled,
+                        EListFilter::Disabled => !mod_info.enabled,
+                        EListFilter::Conflicting => {
+                            self.file_conflicts.contains_key(&mod_info.full_name)
+                        }
+                        EListFilter::Updatable => {
+                            updates.contains_key(&mod_info.full_name)
+                                || get_online_update(mod_info).is_some()
+                        }
+                        EListFilter::All | EListFilter::MissingMasters => true,
+                    };
+                    let name = mod_info
+                        .full_name
+                        .file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    is_state
+                        && is_fuzzy_match(&self.mods_filter, &name)
+                        && self
+                            .mods_category_filter
+                            .as_ref()
+                            .map_or(true, |c| mod_categories[*i].as_ref() == Some(c))
+                })
+                .copied()
+                .collect()
+        } else {
+            get_visible_rows(&rows, &self.mod_separators)
+        };
+        let groups = get_group_mods(&rows, self.mod_separators.len());
+
+        // bulk actions
+        if self.mods_selection.len() > 1 {
+            ui.horizontal(|ui| {
+                ui.label(format!("{} selected", self.mods_selection.len()));
+                if ui.button("Enable").clicked() {
+                    to_enable_selection = Some(true);
+                }
+                if ui.button("Disable").clicked() {
+                    to_enable_selection = Some(false);
+                }
+                ui.menu_button("Move to", |ui| {
+                    if ui.button("Top").clicked() {
+                        to_move_selection = Some(0);
+                        ui.close_menu();
+                    }
+                    if ui.button("Bottom").clicked() {
+                        to_move_selection = Some(rows.len());
+                        ui.close_menu();
+                    }
+                    if !self.mod_separators.is_empty() {
+                        ui.separator();
+                    }
+                    for (j, separator) in self.mod_separators.iter().enumerate() {
+                        if ui.button(&separator.name).clicked() {
+                            to_move_selection = get_group_end(&rows, j);
+                            ui.close_menu();
+                        }
+                    }
+                });
+                // mods omwmm owns are uninstalled, the others are only removed from the list
+                let (managed, unmanaged): (Vec<&ModViewModel>, Vec<&ModViewModel>) = self
+                    .mods
+                    .iter()
+                    .filter(|m| self.mods_selection.contains(&m.full_name) && !m.external)
+                    .partition(|m| m.tag == EModTag::Managed);
+                if !managed.is_empty() && ui.button("Uninstall").clicked() {
+                    to_confirm = Some(EConfirmAction::Uninstall(
+                        managed.iter().map(|m| m.id).collect(),
+                    ));
+                }
+                if !unmanaged.is_empty() && ui.button("Remove from list").clicked() {
+                    to_confirm = Some(EConfirmAction::Remove(
+                        unmanaged.iter().map(|m| m.id).collect(),
+                    ));
+                }
+                if ui.button("Clear selection").clicked() {
+                    self.mods_selection.clear();
+                }
+            });
+            ui.separator();
+        }
 
         egui::ScrollArea::vertical()
             .auto_shrink([false, false])
             .show(ui, |ui| {
-                let response = self.dnd_mods.ui::<ModViewModel>(
+                let response = self.dnd_mods.ui::<EModRow>(
                     ui,
-                    self.mods.iter_mut(),
-                    |mod_info, ui, handle| {
+                    visible.iter_mut(),
+                    |row, ui, handle| {
+                        let i = match *row {
+                            EModRow::Mod(i) => i,
+                            EModRow::Separator(j) => {
+                                // the separator view, it is dragged with its mods
+                                let separator = &mut self.mod_separators[j];
+                                ui.horizontal(|ui| {
+                                    handle.ui(ui, row, |ui| {
+                                        ui.label(" :: ");
+                                    });
+                                    let arrow = if separator.collapsed { "▶" } else { "▼" };
+                                    if ui.small_button(arrow).clicked() {
+                                        separator.collapsed = !separator.collapsed;
+                                    }
+                                    let mut text = egui::RichText::new(format!(
+                                        "{} ({})",
+                                        separator.name,
+                                        groups[j].len()
+                                    ))
+                                    .strong();
+                                    if let Some(color) = separator.color {
+                                        text = text.color(egui::Color32::from(color));
+                                    }
+                                    let r = ui
+                                        .add(egui::Label::new(text).sense(egui::Sense::click()));
+                                    if r.double_clicked() {
+                                        separator.collapsed = !separator.collapsed;
+                                    }
+                                    r.context_menu(|ui| {
+                                        ui.horizontal(|ui| {
+                                            ui.label("Name: ");
+                                            ui.text_edit_singleline(&mut separator.name);
+                                        });
+                                        ui.menu_button("Colour", |ui| {
+                                            if color_tag_ui(ui, &mut separator.color) {
+                                                ui.close_menu();
+                                            }
+                                        });
+                                        ui.separator();
+                                        if ui.button("Enable all").clicked() {
+                                            to_enable_group = Some((j, true));
+                                            ui.close_menu();
+                                        }
+                                        if ui.button("Disable all").clicked() {
+                                            to_enable_group = Some((j, false));
+                                            ui.close_menu();
+                                        }
+                                        ui.separator();
+                                        if ui
+                                            .button("Remove separator")
+                                            .on_hover_text("The mods are kept")
+                                            .clicked()
+                                        {
+                                            to_remove_separator = Some(j);
+                                            ui.close_menu();
+                                        }
+                                    });
+                                });
+                                return;
+                            }
+                        };
+                        let mod_info = &mut self.mods[i];
+
                         // the list item view
                         ui.horizontal(|ui| {
                             let r = ui.push_id(&mod_info.full_name.clone(), |ui| {
                                 ui.horizontal(|ui| {
                                     // Anything in the handle can be used to drag the item
-                                    handle.ui(ui, mod_info, |ui| {
-                                        ui.label(" :: ");
-                                    });
-                                    if ui.checkbox(&mut mod_info.enabled, "").changed() {
-                                        is_any_changed = true;
+                                    // external mods are locked
+                                    if mod_info.external || is_filtered {
+                                        ui.label("    ");
+                                    } else {
+                                        handle.ui(ui, row, |ui| {
+                                            ui.label(" :: ");
+                                        });
+                                    }
+                                    // base game data is always enabled
+                                    let is_base_game = mod_info.tag == EModTag::BaseGame;
+                                    if ui
+                                        .add_enabled(
+                                            !is_base_game && !mod_info.external,
+                                            egui::Checkbox::new(&mut mod_info.enabled, ""),
+                                        )
+                                        .changed()
+                                    {
+                                        toggled.push((
+                                            mod_info.id,
+                                            !mod_info.enabled,
+                                            mod_info.enabled,
+                                        ));
+                                    }
+                                    if let Some(color) = mod_info.color {
+                                        ui.colored_label(egui::Color32::from(color), "■");
+                                    }
+                                    // select to show the properties
+                                    // ctrl and shift add to the selection for bulk actions
+                                    let is_selected =
+                                        self.selected_mod.as_ref() == Some(&mod_info.full_name)
+                                            || self.mods_selection.contains(&mod_info.full_name);
+                                    if ui
+                                        .selectable_label(
+                                            is_selected,
+                                            mod_info
+                                                .full_name
+                                                .file_name()
+                                                .unwrap()
+                                                .to_string_lossy(),
+                                        )
+                                        .clicked()
+                                    {
+                                        clicked = Some((i, ui.input(|input| input.modifiers)));
+                                    }
+                                    if mod_info.external {
+                                        ui.weak("external").on_hover_text(
+                                            "Added to openmw.cfg outside of omwmm, it is kept at its position",
+                                        );
+                                    } else {
+                                        match mod_info.tag {
+                                            EModTag::BaseGame => ui.weak("base game"),
+                                            EModTag::Unmanaged => ui.weak("unmanaged"),
+                                            EModTag::Managed => ui.weak(""),
+                                        };
+                                    }
+                                    if let Some(category) = &mod_categories[i] {
+                                        ui.weak(category);
+                                    }
+                                    if !mod_info.notes.is_empty() {
+                                        ui.weak("notes").on_hover_text(&mod_info.notes);
+                                    }
+                                    // newer version in the downloads library
+                                    if let Some((file_name, version)) =
+                                        updates.get(&mod_info.full_name)
+                                    {
+                                        let installed_version = mod_info
+                                            .meta_data
+                                            .as_ref()
+                                            .and_then(|m| m.version.as_deref())
+                                            .unwrap_or_default();
+                                        if ui
+                                            .small_button(
+                                                egui::RichText::new("update")
+                                                    .color(egui::Color32::YELLOW),
+                                            )
+                                            .on_hover_text(format!(
+                                                "Update {} to {} from {}",
+                                                installed_version, version, file_name
+                                            ))
+                                            .clicked()
+                                        {
+                                            to_update = Some((
+                                                file_name.to_owned(),
+                                                mod_info.full_name.clone(),
+                                            ));
+                                        }
+                                    } else if let Some(newest_version) = get_online_update(mod_info)
+                                    {
+                                        ui.weak("update online").on_hover_text(format!(
+                                            "Version {} is available on Nexus Mods",
+                                            newest_version
+                                        ));
+                                    }
+                                    // file conflicts
+                                    if let Some(conflicts) =
+                                        self.file_conflicts.get(&mod_info.full_name)
+                                    {
+                                        conflicts_ui(
+                                            ui,
+                                            &conflicts.overrides,
+                                            &conflicts.overridden,
+                                        );
                                     }
-                                    ui.label(
-                                        mod_info.full_name.file_name().unwrap().to_string_lossy(),
-                                    );
                                 })
                             });
                             // context menu
                             r.response.context_menu(|ui| {
-                                // uninstall mod
-                                if ui.button("Uninstall").clicked() {
-                                    // delete the mod from the mod library
-                                    if mod_info.full_name.exists() {
-                                        match std::fs::remove_dir_all(&mod_info.full_name) {
-                                            Ok(_) => {
-                                                self.toasts.success("Mod removed");
-                                                // remove the mod from the list
-                                                to_delete.push(i);
-                                                ui.close_menu();
-                                            }
-                                            Err(err) => {
-                                                log::error!(
-                                                    "failed to remove mod {}: {}",
-                                                    mod_info.full_name.display(),
-                                                    err
-                                                );
+                                // take over an external entry
+                                if mod_info.external {
+                                    if ui.button("Add to mods").clicked() {
+                                        mod_info.external = false;
+                                        is_any_changed = true;
+                                        ui.close_menu();
+                                    }
+                                    return;
+                                }
+
+                                // tag
+                                ui.menu_button("Tag", |ui| {
+                                    for (tag, text) in [
+                                        (EModTag::BaseGame, "Base game"),
+                                        (EModTag::Unmanaged, "Unmanaged"),
+                                        (EModTag::Managed, "Managed"),
+                                    ] {
+                                        if ui.radio_value(&mut mod_info.tag, tag, text).clicked() {
+                                            if tag == EModTag::BaseGame {
+                                                mod_info.enabled = true;
                                             }
+                                            is_any_changed = true;
+                                            ui.close_menu();
                                         }
                                     }
+                                });
+                                ui.menu_button("Colour", |ui| {
+                                    if color_tag_ui(ui, &mut mod_info.color) {
+                                        ui.close_menu();
+                                    }
+                                });
+                                if ui.button("Add separator above").clicked() {
+                                    to_add_separator = Some(mod_info.full_name.clone());
+                                    ui.close_menu();
+                                }
+
+                                if let Some((file_name, version)) =
+                                    updates.get(&mod_info.full_name)
+                                {
+                                    if ui.button(format!("Update to {}", version)).clicked() {
+                                        to_update =
+                                            Some((file_name.to_owned(), mod_info.full_name.clone()));
+                                        ui.close_menu();
+                                    }
+                                }
+
+                                // only remove the entry of mods omwmm does not own
+                                if mod_info.tag != EModTag::Managed {
+                                    if ui.button("Remove from list").clicked() {
+                                        to_confirm = Some(EConfirmAction::Remove(vec![mod_info.id]));
+                                        ui.close_menu();
+                                    }
+                                } else if ui.button("Uninstall").clicked() {
+                                    // move the mod to the trash of the mod library in the background
+                                    to_confirm = Some(EConfirmAction::Uninstall(vec![mod_info.id]));
+                                    ui.close_menu();
                                 }
 
                                 ui.separator();
@@ -74,32 +472,143 @@ impl TemplateApp {
                                 }
                             });
                         });
-
-                        i += 1;
                     },
                 );
 
                 // After the drag is complete, we get a response containing the old index of the
-                // dragged item, as well as the index it was moved to. You can use the
-                // shift_vec function as a helper if you store your items in a Vec.
+                // dragged item, as well as the index it was moved to.
+                // a separator is moved with its mods
                 if let Some(response) = response.completed {
-                    egui_dnd::utils::shift_vec(response.from, response.to, &mut self.mods);
+                    let is_locked = matches!(
+                        visible.get(response.from),
+                        Some(EModRow::Mod(i)) if self.mods[*i].external
+                    );
+                    if !is_locked {
+                        let before = self.get_mod_order();
+                        let rows = move_row(&rows, &visible, response.from, response.to);
+                        self.set_mod_rows(&rows);
+                        // the load order changed
+                        let after = self.get_mod_order();
+                        self.record_edit(EListEdit::MoveMods { before, after });
+                    }
                 }
             });
 
-        // delete mods
-        for idx in to_delete {
-            self.mods.remove(idx);
-            is_any_changed = true;
+        // selection
+        if let Some((i, modifiers)) = clicked {
+            let path = self.mods[i].full_name.to_owned();
+            if modifiers.command {
+                if !self.mods_selection.remove(&path) {
+                    self.mods_selection.insert(path.to_owned());
+                }
+            } else if modifiers.shift {
+                let visible_mods: Vec<PathBuf> = visible
+                    .iter()
+                    .filter_map(|row| match row {
+                        EModRow::Mod(i) => Some(self.mods[*i].full_name.to_owned()),
+                        EModRow::Separator(_) => None,
+                    })
+                    .collect();
+                let anchor = self.selected_mod.clone().unwrap_or(path.to_owned());
+                self.mods_selection
+                    .extend(get_range(&visible_mods, &anchor, &path));
+            } else {
+                self.mods_selection.clear();
+                self.mods_selection.insert(path.to_owned());
+            }
+            self.selected_mod = Some(path);
+        }
+        if let Some(enabled) = to_enable_selection {
+            for mod_info in self.mods.iter_mut().filter(|m| {
+                self.mods_selection.contains(&m.full_name)
+                    && m.tag != EModTag::BaseGame
+                    && !m.external
+            }) {
+                toggled.push((mod_info.id, mod_info.enabled, enabled));
+                mod_info.enabled = enabled;
+            }
+        }
+        // external mods are locked
+        if let Some(target) = to_move_selection {
+            let selected: Vec<usize> = self
+                .mods
+                .iter()
+                .enumerate()
+                .filter(|(_, m)| self.mods_selection.contains(&m.full_name) && !m.external)
+                .map(|(i, _)| i)
+                .collect();
+            let before = self.get_mod_order();
+            let rows = move_mods(&rows, &selected, target);
+            self.set_mod_rows(&rows);
+            // the load order changed
+            let after = self.get_mod_order();
+            self.record_edit(EListEdit::MoveMods { before, after });
+        }
+
+        // separators
+        // adding and removing separators can be undone
+        if let Some(path) = to_add_separator {
+            let separator = ModSeparator {
+                name: "New separator".to_owned(),
+                before: Some(path),
+                ..Default::default()
+            };
+            self.mod_separators.push(separator.clone());
+            self.record_edit(EListEdit::AddSeparator {
+                index: self.mod_separators.len() - 1,
+                separator,
+            });
+        }
+        if let Some(j) = to_remove_separator {
+            let separator = self.mod_separators.remove(j);
+            self.record_edit(EListEdit::RemoveSeparator {
+                index: j,
+                separator,
+            });
+        }
+        if let Some((j, enabled)) = to_enable_group {
+            for i in groups[j].iter() {
+                let mod_info = &mut self.mods[*i];
+                if mod_info.tag != EModTag::BaseGame && !mod_info.external {
+                    toggled.push((mod_info.id, mod_info.enabled, enabled));
+                    mod_info.enabled = enabled;
+                }
+            }
+        }
+
+        if to_confirm.is_some() {
+            self.pending_confirmation = to_confirm;
+        }
+
+        // replace the mod folder with the newer archive
+        if let Some((file_name, path)) = to_update {
+            self.install_archive(&file_name, Some(path));
         }
 
-        // update cfg
+        // toggles can be undone, the cfg is written once the changes are applied
+        if !toggled.is_empty() {
+            self.record_edit(EListEdit::SetModsEnabled(toggled));
+        }
         if is_any_changed {
             // update serialized mod list
             self.update_profile_mods();
 
-            // update openmwcfg
-            self.update_cfg();
+            // plugins of the mods
+            self.update_plugins();
+            self.update_profile_plugins();
+            self.update_watched_paths();
+            self.is_cfg_outdated = true;
+
+            self.update_file_conflicts();
+            self.update_record_conflicts();
         }
     }
 }
+
+/// the newest version online if it is newer than the installed one
+fn get_online_update(mod_info: &ModViewModel) -> Option<&str> {
+    let meta = mod_info.meta_data.as_ref()?;
+    let newest_version = meta.newest_version.as_deref()?;
+    let version = meta.version.as_deref()?;
+    (compare_versions(newest_version, version) == Ordering::Greater).then_some(newest_version)
+}
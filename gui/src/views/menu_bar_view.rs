@@ -1,4 +1,11 @@
-use crate::{app::ETheme, EScale, TemplateApp};
+use crate::{
+    app::ETheme,
+    launcher::{find_executable, EExecutable},
+    mods::{EConfirmAction, ModId},
+    profiles::EProfileDialog,
+    views::format_date,
+    EScale, TemplateApp,
+};
 
 impl TemplateApp {
     #[allow(unused_variables)] // for wasm
@@ -13,6 +20,33 @@ impl TemplateApp {
 
                 ui.separator();
 
+                // import other mod managers
+                if ui.button("Import MO2 instance...").clicked() {
+                    if let Some(folder) = rfd::FileDialog::new().pick_folder() {
+                        if self.import_mo2(&folder) {
+                            self.toasts.success("MO2 instance imported");
+                        } else {
+                            self.toasts.error("Could not import MO2 instance");
+                        }
+                    }
+                    ui.close_menu();
+                }
+                if ui.button("Import Wrye Mash load order...").clicked() {
+                    if let Some(file) = rfd::FileDialog::new()
+                        .add_filter("Morrowind.ini", &["ini"])
+                        .pick_file()
+                    {
+                        if self.import_morrowind_ini(&file) {
+                            self.toasts.success("Morrowind.ini imported");
+                        } else {
+                            self.toasts.error("Could not import Morrowind.ini");
+                        }
+                    }
+                    ui.close_menu();
+                }
+
+                ui.separator();
+
                 // Quit button
                 if ui.button("Quit").clicked() {
                     frame.close();
@@ -21,23 +55,42 @@ impl TemplateApp {
 
             // GAME menu
             ui.menu_button("Game", |ui| {
-                // star game
+                // start game
                 if ui.button("Start game").clicked() {
-                    // TODO menu: start game/launcher
+                    self.launch(EExecutable::OpenMW);
+                    ui.close_menu();
+                }
+                if ui.button("Start launcher").clicked() {
+                    self.launch(EExecutable::Launcher);
+                    ui.close_menu();
+                }
+                if ui.button("Start OpenMW-CS").clicked() {
+                    self.launch(EExecutable::CS);
+                    ui.close_menu();
                 }
 
                 ui.separator();
 
                 // open game folder
                 if ui.button("Open game directory").clicked() {
-                    // TODO menu: open game folder
+                    match find_executable(EExecutable::OpenMW, &self.openmw_dir)
+                        .and_then(|p| p.parent().map(|d| d.to_path_buf()))
+                    {
+                        Some(dir) => {
+                            if open::that(dir).is_err() {
+                                self.toasts.error("Could not open game directory");
+                            }
+                        }
+                        None => {
+                            self.toasts.error("Could not find the game directory");
+                        }
+                    }
                 }
                 // open cfg
                 if ui.button("Open config").clicked() {
-                    if let Some(cfg_path) = common::get_openmwcfg() {
-                        if open::that(cfg_path).is_err() {
-                            self.toasts.error("Could not open openmw.cfg");
-                        }
+                    let cfg_path = self.get_profile_cfg();
+                    if open::that(cfg_path).is_err() {
+                        self.toasts.error("Could not open openmw.cfg");
                     }
                 }
             });
@@ -52,6 +105,107 @@ impl TemplateApp {
                 if ui.button("Open downloads library").clicked() {
                     // TODO menu: open downloads folder
                 }
+
+                ui.separator();
+
+                if ui.button("Check for updates").clicked() {
+                    self.fetch_mod_infos(None);
+                    ui.close_menu();
+                }
+
+                ui.separator();
+
+                if ui
+                    .add_enabled(self.mods_library.is_some(), egui::Button::new("Trash..."))
+                    .on_hover_text("Restore or delete uninstalled mods")
+                    .clicked()
+                {
+                    self.refresh_trash();
+                    self.show_trash = true;
+                    ui.close_menu();
+                }
+            });
+
+            // PROFILES Menu
+            ui.menu_button("Profiles", |ui| {
+                if ui.button("New profile").clicked() {
+                    self.open_profile_dialog(EProfileDialog::Create);
+                    ui.close_menu();
+                }
+                if ui.button("Clone profile").clicked() {
+                    self.open_profile_dialog(EProfileDialog::Clone);
+                    ui.close_menu();
+                }
+                if ui.button("Rename profile").clicked() {
+                    self.open_profile_dialog(EProfileDialog::Rename);
+                    ui.close_menu();
+                }
+                if ui
+                    .add_enabled(
+                        self.profiles.len() > 1,
+                        egui::Button::new("Compare profiles..."),
+                    )
+                    .on_hover_text("Show the load order changes to another profile")
+                    .clicked()
+                {
+                    self.cfg_diff_with = self
+                        .profiles
+                        .iter()
+                        .find(|p| **p != self.current_profile)
+                        .cloned();
+                    self.refresh_cfg_diff();
+                    ui.close_menu();
+                }
+
+                ui.separator();
+
+                // share profiles
+                if ui.button("Export modlist...").clicked() {
+                    if let Some(file) = rfd::FileDialog::new()
+                        .add_filter("Modlist", &["toml"])
+                        .set_file_name(&format!("{}.toml", self.current_profile))
+                        .save_file()
+                    {
+                        self.export_modlist(file);
+                    }
+                    ui.close_menu();
+                }
+                if ui.button("Import modlist...").clicked() {
+                    if let Some(file) = rfd::FileDialog::new()
+                        .add_filter("Modlist", &["toml"])
+                        .pick_file()
+                    {
+                        if self.import_modlist(&file) {
+                            self.toasts.success("Modlist imported");
+                        } else {
+                            self.toasts.error("Could not import modlist");
+                        }
+                    }
+                    ui.close_menu();
+                }
+                if ui
+                    .button("Install modlist...")
+                    .on_hover_text("Install the mods of a modlist from the downloads library")
+                    .clicked()
+                {
+                    if let Some(file) = rfd::FileDialog::new()
+                        .add_filter("Modlist", &["toml"])
+                        .pick_file()
+                    {
+                        self.install_modlist(&file, false);
+                    }
+                    ui.close_menu();
+                }
+
+                ui.separator();
+
+                if ui
+                    .add_enabled(self.profiles.len() > 1, egui::Button::new("Delete profile"))
+                    .clicked()
+                {
+                    self.open_profile_dialog(EProfileDialog::Delete);
+                    ui.close_menu();
+                }
             });
 
             // right settings //TODO move to settings?
@@ -67,9 +221,278 @@ impl TemplateApp {
                         ui.selectable_value(&mut self.scale, EScale::Medium, "Medium");
                         ui.selectable_value(&mut self.scale, EScale::Large, "Large");
                     });
+                // profile
+                let mut profile_to_load = None;
+                egui::ComboBox::from_label("Profile: ")
+                    .selected_text(&self.current_profile)
+                    .show_ui(ui, |ui| {
+                        for profile in &self.profiles {
+                            if ui
+                                .selectable_label(*profile == self.current_profile, profile)
+                                .clicked()
+                            {
+                                profile_to_load = Some(profile.to_owned());
+                            }
+                        }
+                    });
+                if let Some(profile) = profile_to_load {
+                    self.switch_profile(&profile);
+                }
             });
         });
     }
+
+    fn open_profile_dialog(&mut self, dialog: EProfileDialog) {
+        self.profile_name_input = match dialog {
+            EProfileDialog::Create => String::new(),
+            EProfileDialog::Clone => format!("{} (copy)", self.current_profile),
+            EProfileDialog::Rename | EProfileDialog::Delete => self.current_profile.to_owned(),
+        };
+        self.profile_dialog = Some(dialog);
+    }
+
+    /// modal window to create, clone, rename or delete a profile
+    pub fn profile_dialog_view(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = self.profile_dialog else {
+            return;
+        };
+        let title = match dialog {
+            EProfileDialog::Create => "New profile",
+            EProfileDialog::Clone => "Clone profile",
+            EProfileDialog::Rename => "Rename profile",
+            EProfileDialog::Delete => "Delete profile",
+        };
+
+        let mut confirmed = false;
+        let mut cancelled = false;
+        egui::Window::new(title)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                if dialog == EProfileDialog::Delete {
+                    ui.label(format!(
+                        "Delete profile {} and its files?",
+                        self.current_profile
+                    ));
+                } else {
+                    ui.horizontal(|ui| {
+                        ui.label("Name: ");
+                        ui.text_edit_singleline(&mut self.profile_name_input);
+                    });
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Ok").clicked() {
+                        confirmed = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancelled = true;
+                    }
+                });
+            });
+
+        if confirmed {
+            let name = self.profile_name_input.trim().to_owned();
+            let success = match dialog {
+                EProfileDialog::Create => self.create_profile(&name),
+                EProfileDialog::Clone => self.clone_profile(&name),
+                EProfileDialog::Rename => self.rename_profile(&name),
+                EProfileDialog::Delete => self.delete_profile(),
+            };
+            if success {
+                self.toasts.success(format!("{} done", title));
+                self.profile_dialog = None;
+            } else {
+                self.toasts.error(format!("{} failed", title));
+            }
+        } else if cancelled {
+            self.profile_dialog = None;
+        }
+    }
+
+    /// lists the archives and mods of an imported modlist that are missing or differ
+    pub fn modlist_report_view(&mut self, ctx: &egui::Context) {
+        let Some(report) = &self.modlist_report else { return };
+
+        let mut open = true;
+        egui::Window::new("Modlist report")
+            .open(&mut open)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        if !report.missing_archives.is_empty() {
+                            ui.label("Download these archives to the downloads library:");
+                            egui::Grid::new("missing_archives_grid")
+                                .num_columns(3)
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    for archive in report.missing_archives.iter() {
+                                        ui.label(&archive.file_name);
+                                        ui.label(archive.version.as_deref().unwrap_or_default());
+                                        match archive.get_url() {
+                                            Some(url) => {
+                                                ui.hyperlink_to("Nexus Mods", url);
+                                            }
+                                            None => {
+                                                ui.label("");
+                                            }
+                                        }
+                                        ui.end_row();
+                                    }
+                                });
+                        }
+                        if !report.missing_mods.is_empty() {
+                            ui.separator();
+                            ui.label("These mods were added by hand and need to be copied:");
+                            for name in report.missing_mods.iter() {
+                                ui.label(name);
+                            }
+                        }
+                    });
+            });
+        if !open {
+            self.modlist_report = None;
+        }
+    }
+
+    /// modal window that asks before uninstalling, removing or deleting mods
+    pub fn confirm_dialog_view(&mut self, ctx: &egui::Context) {
+        let Some(action) = self.pending_confirmation.clone() else { return };
+        let get_names = |ids: &[ModId]| -> String {
+            ids.iter()
+                .filter_map(|id| self.get_mod(*id))
+                .filter_map(|m| m.full_name.file_name())
+                .map(|n| n.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let (title, text) = match &action {
+            EConfirmAction::Uninstall(ids) => (
+                "Uninstall mods",
+                format!("Move {} to the trash?", get_names(ids)),
+            ),
+            EConfirmAction::Remove(ids) => (
+                "Remove mods",
+                format!(
+                    "Remove {} from the list? Their files are kept.",
+                    get_names(ids)
+                ),
+            ),
+            EConfirmAction::DeleteTrashed(path) => (
+                "Delete mod",
+                format!(
+                    "Delete {} for good?",
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                ),
+            ),
+            EConfirmAction::EmptyTrash => (
+                "Empty trash",
+                "Delete all mods in the trash for good?".to_owned(),
+            ),
+            EConfirmAction::ReplaceMods { names, .. } => (
+                "Install modlist",
+                format!(
+                    "Replace the installed mods {} with the archives of the modlist?",
+                    names.join(", ")
+                ),
+            ),
+            EConfirmAction::RemoveArchive(file_name) => (
+                "Remove archive",
+                format!("Delete {} from the downloads library?", file_name),
+            ),
+        };
+
+        let mut confirmed = false;
+        let mut cancelled = false;
+        egui::Window::new(title)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(text);
+                ui.horizontal(|ui| {
+                    if ui.button("Ok").clicked() {
+                        confirmed = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancelled = true;
+                    }
+                });
+            });
+
+        if confirmed {
+            self.pending_confirmation = None;
+            if !self.confirm_action(action) {
+                self.toasts.error(format!("{} failed", title));
+            }
+        } else if cancelled {
+            self.pending_confirmation = None;
+        }
+    }
+
+    /// lists the uninstalled mods in the trash of the mods library
+    pub fn trash_view(&mut self, ctx: &egui::Context) {
+        if !self.show_trash {
+            return;
+        }
+
+        let mut to_restore = None;
+        let mut to_confirm = None;
+        let mut open = true;
+        egui::Window::new("Trash")
+            .open(&mut open)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                if self.trash.is_empty() {
+                    ui.label("The trash is empty");
+                    return;
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("trash_grid")
+                            .num_columns(4)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for trashed in self.trash.iter() {
+                                    ui.label(&trashed.name);
+                                    ui.label(format_date(trashed.trashed));
+                                    if ui.button("Restore").clicked() {
+                                        to_restore = Some(trashed.path.to_owned());
+                                    }
+                                    if ui.button("Delete").clicked() {
+                                        to_confirm = Some(EConfirmAction::DeleteTrashed(
+                                            trashed.path.to_owned(),
+                                        ));
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                ui.separator();
+                if ui.button("Empty trash").clicked() {
+                    to_confirm = Some(EConfirmAction::EmptyTrash);
+                }
+            });
+
+        if let Some(path) = to_restore {
+            if self.restore_mod(&path) {
+                self.toasts.success("Mod restored");
+            } else {
+                self.toasts.error("Could not restore mod");
+            }
+        }
+        if to_confirm.is_some() {
+            self.pending_confirmation = to_confirm;
+        }
+        if !open {
+            self.show_trash = false;
+        }
+    }
 }
 
 fn theme_switch(ui: &mut egui::Ui, theme: &mut crate::app::ETheme) {
@@ -1,9 +1,11 @@
-use std::time::Duration;
-
 use egui_dnd::utils::shift_vec;
-use log::warn;
 
-use crate::{ModViewModel, PluginViewModel, TemplateApp};
+use crate::{
+    filters::{get_range, is_fuzzy_match, move_selected, EListFilter},
+    history::EListEdit,
+    views::{color_tag_ui, conflicts_ui},
+    PluginViewModel, TemplateApp,
+};
 
 impl TemplateApp {
     /// right panel
@@ -33,6 +35,8 @@ impl TemplateApp {
                 crate::app::ETabView::Settings,
                 "Settings",
             );
+
+            ui.selectable_value(&mut self.current_tab_view, crate::app::ETabView::Log, "Log");
         });
 
         ui.separator();
@@ -50,6 +54,9 @@ impl TemplateApp {
             crate::app::ETabView::Settings => {
                 self.settings_view(ui);
             }
+            crate::app::ETabView::Log => {
+                self.log_view(ui);
+            }
         }
     }
 
@@ -58,18 +65,123 @@ impl TemplateApp {
         ui.heading("Plugins");
         ui.separator();
 
+        // filters
+        ui.horizontal(|ui| {
+            ui.label("Search: ");
+            ui.text_edit_singleline(&mut self.plugins_filter);
+            if ui.button("x").on_hover_text("Clear search").clicked() {
+                self.plugins_filter.clear();
+            }
+            egui::ComboBox::from_id_source("plugins_state_filter")
+                .selected_text(self.plugins_state_filter.get_name())
+                .show_ui(ui, |ui| {
+                    for filter in EListFilter::PLUGINS {
+                        ui.selectable_value(
+                            &mut self.plugins_state_filter,
+                            filter,
+                            filter.get_name(),
+                        );
+                    }
+                });
+        });
+        ui.separator();
+
+        // plugins enabled or disabled: name, enabled before, enabled after
+        let mut toggled: Vec<(String, bool, bool)> = vec![];
+        let mut to_enable_selection: Option<bool> = None;
+        let mut to_move_selection: Option<bool> = None;
+        let mut clicked: Option<(String, egui::Modifiers)> = None;
+
+        // bulk actions
+        if self.plugins_selection.len() > 1 {
+            ui.horizontal(|ui| {
+                ui.label(format!("{} selected", self.plugins_selection.len()));
+                if ui.button("Enable").clicked() {
+                    to_enable_selection = Some(true);
+                }
+                if ui.button("Disable").clicked() {
+                    to_enable_selection = Some(false);
+                }
+                if ui.button("Move to top").clicked() {
+                    to_move_selection = Some(true);
+                }
+                if ui.button("Move to bottom").clicked() {
+                    to_move_selection = Some(false);
+                }
+                if ui.button("Clear selection").clicked() {
+                    self.plugins_selection.clear();
+                }
+            });
+            ui.separator();
+        }
+
+        // a filtered list can not be reordered
+        let is_filtered =
+            !self.plugins_filter.trim().is_empty() || self.plugins_state_filter != EListFilter::All;
+        let mut visible: Vec<String> = vec![];
         egui::ScrollArea::vertical().show(ui, |ui| {
             // a read-only but reorderable list of plugins
             let response = self.dnd_plugins.ui::<PluginViewModel>(
                 ui,
-                self.plugins.iter_mut(),
+                self.plugins.iter_mut().filter(|p| {
+                    let is_state = match self.plugins_state_filter {
+                        EListFilter::Enabled => p.enabled,
+                        EListFilter::Disabled => !p.enabled,
+                        EListFilter::Conflicting => self.record_conflicts.contains_key(&p.name),
+                        EListFilter::MissingMasters => self.missing_masters.contains_key(&p.name),
+                        EListFilter::All | EListFilter::Updatable => true,
+                    };
+                    let is_listed = is_state && is_fuzzy_match(&self.plugins_filter, &p.name);
+                    if is_listed {
+                        visible.push(p.name.to_owned());
+                    }
+                    is_listed
+                }),
                 |item, ui, handle| {
                     ui.horizontal(|ui| {
                         // Anything in the handle can be used to drag the item
-                        ui.checkbox(&mut item.enabled, "");
-                        handle.ui(ui, item, |ui| {
-                            ui.label(&item.name);
-                        });
+                        // external plugins are locked
+                        if item.external || is_filtered {
+                            ui.label("    ");
+                        } else {
+                            handle.ui(ui, item, |ui| {
+                                ui.label(" :: ");
+                            });
+                        }
+                        if ui
+                            .add_enabled(!item.external, egui::Checkbox::new(&mut item.enabled, ""))
+                            .changed()
+                        {
+                            toggled.push((item.name.to_owned(), !item.enabled, item.enabled));
+                        }
+                        // ctrl and shift add to the selection for bulk actions
+                        let is_selected = self.plugins_selection.contains(&item.name);
+                        let text = if item.provider_disabled {
+                            egui::RichText::new(&item.name).weak()
+                        } else {
+                            egui::RichText::new(&item.name)
+                        };
+                        if ui.selectable_label(is_selected, text).clicked() {
+                            clicked = Some((item.name.to_owned(), ui.input(|i| i.modifiers)));
+                        }
+                        if item.external {
+                            ui.weak("external").on_hover_text(
+                                "Listed in openmw.cfg by another tool, its load order position is kept",
+                            );
+                        } else if item.provider_disabled {
+                            ui.weak("mod disabled").on_hover_text(
+                                "Enable a mod that contains this plugin to load it",
+                            );
+                        }
+                        // masters that load later or not at all
+                        if let Some(masters) = self.missing_masters.get(&item.name) {
+                            ui.colored_label(egui::Color32::RED, "missing masters")
+                                .on_hover_text(masters.join("\n"));
+                        }
+                        // record conflicts
+                        if let Some(conflicts) = self.record_conflicts.get(&item.name) {
+                            conflicts_ui(ui, &conflicts.overrides, &conflicts.overridden);
+                        }
                     });
                 },
             );
@@ -78,62 +190,195 @@ impl TemplateApp {
             // dragged item, as well as the index it was moved to. You can use the
             // shift_vec function as a helper if you store your items in a Vec.
             if let Some(response) = response.completed {
-                shift_vec(response.from, response.to, &mut self.plugins);
+                if !is_filtered && !self.plugins[response.from].external {
+                    let before = self.get_plugin_order();
+                    shift_vec(response.from, response.to, &mut self.plugins);
+                    let after = self.get_plugin_order();
+                    self.record_edit(EListEdit::MovePlugins { before, after });
+                }
             }
         });
+
+        // selection
+        if let Some((name, modifiers)) = clicked {
+            if modifiers.command {
+                if !self.plugins_selection.remove(&name) {
+                    self.plugins_selection.insert(name.to_owned());
+                }
+            } else if modifiers.shift {
+                let anchor = self.plugins_last_clicked.clone().unwrap_or(name.to_owned());
+                self.plugins_selection
+                    .extend(get_range(&visible, &anchor, &name));
+            } else {
+                self.plugins_selection.clear();
+                self.plugins_selection.insert(name.to_owned());
+            }
+            self.plugins_last_clicked = Some(name);
+        }
+        // external plugins are locked
+        if let Some(enabled) = to_enable_selection {
+            for p in self
+                .plugins
+                .iter_mut()
+                .filter(|p| self.plugins_selection.contains(&p.name) && !p.external)
+            {
+                toggled.push((p.name.to_owned(), p.enabled, enabled));
+                p.enabled = enabled;
+            }
+        }
+        if let Some(to_top) = to_move_selection {
+            let before = self.get_plugin_order();
+            move_selected(
+                &mut self.plugins,
+                |p| self.plugins_selection.contains(&p.name) && !p.external,
+                to_top,
+            );
+            let after = self.get_plugin_order();
+            self.record_edit(EListEdit::MovePlugins { before, after });
+        }
+
+        // toggles can be undone, the cfg is written once the changes are applied
+        if !toggled.is_empty() {
+            self.record_edit(EListEdit::SetPluginsEnabled(toggled));
+        }
     }
 
-    /// list of mod packages
-    pub fn downloads_view(&mut self, ui: &mut egui::Ui) {
-        ui.heading("Archives");
+    /// mod property view
+    pub fn properties_view(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Info");
         ui.separator();
 
-        // check
-        let Some(_library_path) = self.downloads_library.clone() else { return };
+        let Some(mod_info) = self
+            .selected_mod
+            .as_ref()
+            .and_then(|path| self.mods.iter().find(|m| m.full_name == *path))
+        else {
+            ui.label("Select a mod to show its properties");
+            return;
+        };
+        let path = mod_info.full_name.to_owned();
 
-        // downloads list
+        let mut to_fetch = false;
         egui::ScrollArea::vertical().show(ui, |ui| {
-            for path in self.downloads.iter() {
-                // create viewmodel
-                if ui
-                    .add(egui::Label::new(&path.file_name).sense(egui::Sense::click()))
-                    .double_clicked()
-                {
-                    // install mod
-                    // extract to mod lib
-                    // add to mods
-                    if let Some(library) = self.mods_library.clone() {
-                        let mut install_path = library.join(&path.file_name);
-
-                        install_path.set_extension("");
-                        let mod_info = ModViewModel {
-                            enabled: false,
-                            full_name: install_path.clone(),
-                        };
+            egui::Grid::new("properties_grid")
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("Path");
+                    ui.label(path.display().to_string());
+                    ui.end_row();
 
-                        if !self.mods.iter().any(|e| e.full_name == install_path) {
-                            // TODO install mod
-                            // support 7z, zip, rar
+                    // local metadata
+                    if let Some(meta) = &mod_info.meta_data {
+                        for (key, value) in [
+                            ("Name", meta.mod_name.as_ref().or(meta.name.as_ref())),
+                            ("Version", meta.version.as_ref()),
+                            ("Newest version", meta.newest_version.as_ref()),
+                            ("Installed from", meta.installation_file.as_ref()),
+                        ] {
+                            if let Some(value) = value {
+                                ui.label(key);
+                                ui.label(value);
+                                ui.end_row();
+                            }
+                        }
+                        if let Some(mod_id) = meta.mod_id {
+                            ui.label("Nexus id");
+                            ui.hyperlink_to(
+                                mod_id.to_string(),
+                                format!(
+                                    "https://www.nexusmods.com/{}/mods/{}",
+                                    meta.game_name.as_deref().unwrap_or("morrowind"),
+                                    mod_id
+                                ),
+                            );
+                            ui.end_row();
+                        }
+                    }
 
-                            self.mods.push(mod_info);
-                            self.toasts
-                                .success("Mod installed")
-                                .set_duration(Some(Duration::from_secs(3)));
+                    // online info
+                    if let Some(info) = self.mod_infos.get(&path) {
+                        let latest_file = info.latest_file.as_ref();
+                        for (key, value) in [
+                            ("Online name", info.name.as_ref()),
+                            ("Online version", info.version.as_ref()),
+                            ("Category", info.category.as_ref()),
+                            (
+                                "Latest file",
+                                latest_file.and_then(|f| f.file_name.as_ref()),
+                            ),
+                            (
+                                "Latest file version",
+                                latest_file.and_then(|f| f.version.as_ref()),
+                            ),
+                            ("Summary", info.summary.as_ref()),
+                        ] {
+                            if let Some(value) = value {
+                                ui.label(key);
+                                ui.label(value);
+                                ui.end_row();
+                            }
                         }
-                    } else {
-                        warn!("No mod library found.")
                     }
-                }
+                });
+
+            if let Some(description) = self
+                .mod_infos
+                .get(&path)
+                .and_then(|i| i.description.as_ref())
+            {
+                ui.collapsing("Description", |ui| {
+                    ui.label(description);
+                });
+            }
+
+            if mod_info
+                .meta_data
+                .as_ref()
+                .is_some_and(|m| m.mod_id.is_some())
+                && ui.button("Refresh online info").clicked()
+            {
+                to_fetch = true;
             }
         });
-    }
 
-    /// mod property view
-    pub fn properties_view(&mut self, ui: &mut egui::Ui) {
-        ui.heading("Info");
+        if to_fetch {
+            self.fetch_mod_infos(Some(path.to_owned()));
+        }
+
+        // set by the user, saved with the mod list
         ui.separator();
+        let online_category = self
+            .mod_infos
+            .get(&path)
+            .and_then(|i| i.category.clone())
+            .unwrap_or_default();
+        let Some(mod_info) = self.mods.iter_mut().find(|m| m.full_name == path) else { return };
+        egui::Grid::new("properties_user_grid")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Category");
+                let mut category = mod_info.category.clone().unwrap_or_default();
+                if ui
+                    .add(egui::TextEdit::singleline(&mut category).hint_text(online_category))
+                    .changed()
+                {
+                    mod_info.category = (!category.trim().is_empty()).then_some(category);
+                }
+                ui.end_row();
 
-        ui.label("TODO mod properties");
+                ui.label("Colour");
+                ui.horizontal_wrapped(|ui| {
+                    color_tag_ui(ui, &mut mod_info.color);
+                });
+                ui.end_row();
+            });
+        ui.label("Notes");
+        ui.add(
+            egui::TextEdit::multiline(&mut mod_info.notes)
+                .desired_width(f32::INFINITY)
+                .hint_text("Install notes, load order hints, ..."),
+        );
     }
 
     /// app settings view
@@ -153,6 +398,7 @@ impl TemplateApp {
             if ui.button("...").clicked() {
                 if let Some(folder) = rfd::FileDialog::new().set_directory("/").pick_folder() {
                     self.mods_library = Some(folder);
+                    self.update_watched_paths();
                 }
             }
         });
@@ -172,10 +418,102 @@ impl TemplateApp {
             if ui.button("...").clicked() {
                 if let Some(folder) = rfd::FileDialog::new().set_directory("/").pick_folder() {
                     self.downloads_library = Some(folder);
+                    self.update_watched_paths();
+                }
+            }
+        });
+
+        ui.separator();
+
+        // nexus api key for update checks
+        ui.horizontal(|ui| {
+            ui.label("Nexus Mods API key: ");
+            ui.add(egui::TextEdit::singleline(&mut self.nexus_api_key).password(true))
+                .on_hover_text("Your personal API key from the Nexus Mods account settings");
+        });
+        #[cfg(target_os = "linux")]
+        if ui
+            .button("Handle nxm:// links")
+            .on_hover_text("Download with omwmm from the Nexus Mods website")
+            .clicked()
+        {
+            match crate::downloader::register_nxm_handler() {
+                Ok(_) => {
+                    self.toasts.success("omwmm now handles nxm:// links");
+                }
+                Err(err) => {
+                    log::error!("Could not register nxm handler: {}", err);
+                    self.toasts.error("Could not register nxm handler");
+                }
+            }
+        }
+
+        ui.separator();
+
+        // openmw folder path
+        ui.horizontal(|ui| {
+            ui.label("OpenMW directory: ");
+
+            if let Some(p) = self.openmw_dir.clone() {
+                ui.label(p.display().to_string());
+                if ui
+                    .button("x")
+                    .on_hover_text("Search automatically")
+                    .clicked()
+                {
+                    self.openmw_dir = None;
+                }
+            } else {
+                ui.label("Found automatically");
+            }
+
+            if ui.button("...").clicked() {
+                if let Some(folder) = rfd::FileDialog::new().set_directory("/").pick_folder() {
+                    self.openmw_dir = Some(folder);
                 }
             }
         });
 
         ui.separator();
+
+        // profile config
+        ui.checkbox(
+            &mut self.use_profile_settings,
+            "Use a separate settings.cfg per profile",
+        );
+        ui.horizontal(|ui| {
+            let launch_args = self.get_launch_args().join(" ");
+            ui.label("Launch arguments: ");
+            ui.label(&launch_args);
+            if ui.button("Copy").clicked() {
+                ui.output_mut(|o| o.copied_text = launch_args);
+            }
+        });
+
+        ui.separator();
+    }
+
+    /// output of launched processes
+    pub fn log_view(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("Log");
+            if ui.button("Clear").clicked() {
+                if let Ok(mut log) = self.game_log.lock() {
+                    log.clear();
+                }
+            }
+        });
+        ui.separator();
+
+        egui::ScrollArea::both()
+            .auto_shrink([false, false])
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                if let Ok(log) = self.game_log.lock() {
+                    for line in log.iter() {
+                        ui.monospace(line);
+                    }
+                }
+            });
     }
 }
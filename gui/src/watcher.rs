@@ -0,0 +1,234 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Component, Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+    time::{Duration, Instant},
+};
+
+use common::{archive::get_part_path, profile::get_library_mods};
+use log::{error, info, warn};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{mods::push_mod, EModTag, ModViewModel, TemplateApp};
+
+/// how long the files have to be unchanged before the changes are applied
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Watches the libraries and data directories for changes made outside of omwmm
+pub struct LibraryWatcher {
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    /// watched paths and how they are watched
+    watched: HashMap<PathBuf, RecursiveMode>,
+    /// changed paths that are not applied yet and when the last one changed
+    pending: HashSet<PathBuf>,
+    last_change: Option<Instant>,
+    ctx: egui::Context,
+}
+
+impl LibraryWatcher {
+    /// creates a watcher that repaints the ui when files change
+    pub fn new(ctx: &egui::Context) -> Option<Self> {
+        let (sender, events) = mpsc::channel();
+        let repaint_ctx = ctx.clone();
+        let watcher = notify::recommended_watcher(move |event| {
+            if sender.send(event).is_ok() {
+                repaint_ctx.request_repaint();
+            }
+        });
+        match watcher {
+            Ok(watcher) => Some(Self {
+                watcher,
+                events,
+                watched: HashMap::new(),
+                pending: HashSet::new(),
+                last_change: None,
+                ctx: ctx.clone(),
+            }),
+            Err(err) => {
+                error!("Could not create file watcher: {}", err);
+                None
+            }
+        }
+    }
+
+    /// watches exactly the given paths and unwatches all others
+    pub fn set_paths(&mut self, paths: HashMap<PathBuf, RecursiveMode>) {
+        let unwatched: Vec<PathBuf> = self
+            .watched
+            .iter()
+            .filter(|(path, mode)| paths.get(*path) != Some(mode))
+            .map(|(path, _)| path.to_owned())
+            .collect();
+        for path in unwatched {
+            if let Err(err) = self.watcher.unwatch(&path) {
+                warn!("Could not unwatch {}: {}", path.display(), err);
+            }
+            self.watched.remove(&path);
+        }
+
+        for (path, mode) in paths {
+            if self.watched.contains_key(&path) || !path.exists() {
+                continue;
+            }
+            match self.watcher.watch(&path, mode) {
+                Ok(_) => {
+                    self.watched.insert(path, mode);
+                }
+                Err(err) => warn!("Could not watch {}: {}", path.display(), err),
+            }
+        }
+    }
+
+    /// returns all paths that changed since the last call, once no file changed for a moment
+    /// so an extraction or copy is applied once when it is done
+    pub fn get_changed_paths(&mut self) -> Vec<PathBuf> {
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                Ok(event) => {
+                    if matches!(event.kind, EventKind::Access(_)) {
+                        continue;
+                    }
+                    for path in event.paths {
+                        if !self.is_ignored(&path) {
+                            self.pending.insert(path);
+                            self.last_change = Some(Instant::now());
+                        }
+                    }
+                }
+                Err(err) => warn!("File watcher error: {}", err),
+            }
+        }
+
+        let Some(last_change) = self.last_change else {
+            return vec![];
+        };
+        let elapsed = last_change.elapsed();
+        if elapsed < DEBOUNCE {
+            self.ctx.request_repaint_after(DEBOUNCE - elapsed);
+            return vec![];
+        }
+        self.last_change = None;
+        self.pending.drain().collect()
+    }
+
+    /// hidden files and folders and unfinished installs or downloads (.part) are ignored
+    /// only the part of the path inside the watched folder is checked
+    fn is_ignored(&self, path: &Path) -> bool {
+        let relative = self
+            .watched
+            .keys()
+            .filter_map(|root| path.strip_prefix(root).ok())
+            .min_by_key(|p| p.components().count())
+            .unwrap_or(path);
+        relative.components().any(|c| match c {
+            Component::Normal(name) => {
+                let name = name.to_string_lossy();
+                name.starts_with('.') || name.ends_with(".part")
+            }
+            _ => false,
+        })
+    }
+}
+
+impl TemplateApp {
+    /// watches the downloads library, the mods library and the data directories of all mods
+    pub(crate) fn update_watched_paths(&mut self) {
+        let Some(watcher) = self.watcher.as_mut() else { return };
+
+        let mut paths: HashMap<PathBuf, RecursiveMode> = HashMap::new();
+        if let Some(library) = &self.downloads_library {
+            paths.insert(library.to_owned(), RecursiveMode::NonRecursive);
+        }
+        if let Some(library) = &self.mods_library {
+            paths.insert(library.to_owned(), RecursiveMode::Recursive);
+        }
+        for mod_info in self.mods.iter() {
+            // mods in the library are already watched
+            if !is_in_folder(&mod_info.full_name, &self.mods_library) {
+                paths.insert(mod_info.full_name.to_owned(), RecursiveMode::Recursive);
+            }
+        }
+        watcher.set_paths(paths);
+    }
+
+    /// applies file changes made outside of omwmm
+    pub(crate) fn poll_watcher(&mut self) {
+        let Some(watcher) = &mut self.watcher else { return };
+        let changed_paths = watcher.get_changed_paths();
+        if changed_paths.is_empty() {
+            return;
+        }
+
+        let mut is_downloads_changed = false;
+        let mut is_mods_changed = false;
+        for path in changed_paths {
+            // archives and their meta files
+            if path.parent() == self.downloads_library.as_deref() {
+                is_downloads_changed = true;
+            } else {
+                // files in data directories or mod folders in the library
+                is_mods_changed = true;
+            }
+        }
+
+        if is_downloads_changed {
+            self.scan_downloads();
+        }
+
+        if is_mods_changed {
+            if self.sync_mods_library() {
+                self.update_profile_mods();
+                self.update_watched_paths();
+            }
+            self.update_plugins();
+            self.update_profile_plugins();
+            self.update_file_conflicts();
+            self.update_record_conflicts();
+        }
+    }
+
+    /// removes mods whose folder is gone and adds new folders of the mods library as disabled mods
+    /// returns true if the mod list changed
+    pub(crate) fn sync_mods_library(&mut self) -> bool {
+        let count = self.mods.len();
+        // external entries stay as long as they are in the cfg
+        // mods that are being reinstalled are briefly missing
+        self.retain_mods(|m| {
+            let exists = m.external
+                || m.full_name.exists()
+                || get_part_path(&m.full_name).is_some_and(|p| p.exists());
+            if !exists {
+                info!("Mod {} was removed", m.full_name.display());
+            }
+            exists
+        });
+        let mut is_changed = self.mods.len() != count;
+
+        if let Some(library) = &self.mods_library {
+            for path in get_library_mods(library) {
+                if !self.mods.iter().any(|m| m.full_name == path) {
+                    info!("Mod {} was added", path.display());
+                    push_mod(
+                        &mut self.mods,
+                        ModViewModel {
+                            full_name: path,
+                            enabled: false,
+                            tag: EModTag::Managed,
+                            external: false,
+                            meta_data: None,
+                            ..Default::default()
+                        },
+                    );
+                    is_changed = true;
+                }
+            }
+        }
+        is_changed
+    }
+}
+
+/// checks if a path is inside an optional folder
+fn is_in_folder(path: &Path, folder: &Option<PathBuf>) -> bool {
+    folder.as_ref().is_some_and(|f| path.starts_with(f))
+}
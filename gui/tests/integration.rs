@@ -0,0 +1,517 @@
+#[cfg(test)]
+mod integration_tests {
+    use std::{
+        path::{Path, PathBuf},
+        thread,
+        time::Duration,
+    };
+
+    use omwmm::downloader::{download_file, get_download_part_path, NxmLink};
+    use omwmm::filters::{get_range, is_fuzzy_match, move_selected};
+    use omwmm::history::{sort_by_order, EListEdit, EditHistory, ModOrder, MAX_HISTORY};
+    use omwmm::ipc::EIpcCommand;
+    use omwmm::metadata::{CachedProvider, MetadataProvider, NexusProvider};
+    use omwmm::mods::{
+        assign_mod_ids, get_broken_mods, get_mod_index, get_next_mod_id, insert_mod, push_mod,
+    };
+    use omwmm::separators::{
+        apply_mod_rows, get_group_end, get_group_mods, get_mod_rows, get_visible_rows, move_mods,
+        move_row,
+        EModRow::{Mod, Separator},
+    };
+    use omwmm::{ModSeparator, ModViewModel};
+
+    const MOD_JSON: &str = r#"{"mod_id":123,"name":"Mod","summary":"A mod","description":"Long text","version":"1.2","category_id":7,"author":"someone"}"#;
+    const FILES_JSON: &str = r#"{"files":[
+        {"file_id":1,"name":"Mod","version":"1.0","category_name":"OLD_VERSION","file_name":"Mod-123-1-0.7z","uploaded_timestamp":100},
+        {"file_id":2,"name":"Mod","version":"1.2","category_name":"MAIN","file_name":"Mod-123-1-2.7z","uploaded_timestamp":300},
+        {"file_id":3,"name":"Patch","version":"1.3","category_name":"OPTIONAL","file_name":"Patch-123-1-3.7z","uploaded_timestamp":400}
+    ],"file_updates":[]}"#;
+    const LINKS_JSON: &str =
+        r#"[{"name":"Server","short_name":"s","URI":"https://example.com/Mod-123-1-2.7z"}]"#;
+    const GAME_JSON: &str = r#"{"id":100,"name":"Morrowind","categories":[{"category_id":7,"name":"Gameplay","parent_category":false}]}"#;
+
+    /// serves the Nexus Mods API responses of one mod, returns the base url
+    fn start_mock_server(requests: usize) -> String {
+        let server = tiny_http::Server::http("127.0.0.1:0").expect("Failed to start mock server");
+        let url = format!("http://{}", server.server_addr());
+        thread::spawn(move || {
+            for request in server.incoming_requests().take(requests) {
+                let has_key = request
+                    .headers()
+                    .iter()
+                    .any(|h| h.field.equiv("apikey") && h.value == "key");
+                let body = match request.url() {
+                    _ if !has_key => None,
+                    "/v1/games/morrowind/mods/123.json" => Some(MOD_JSON),
+                    "/v1/games/morrowind/mods/123/files.json" => Some(FILES_JSON),
+                    "/v1/games/morrowind.json" => Some(GAME_JSON),
+                    "/v1/games/morrowind/mods/123/files/2/download_link.json?key=a%26b%3Dc&expires=1" => {
+                        Some(LINKS_JSON)
+                    }
+                    _ => None,
+                };
+                let response = match body {
+                    Some(body) => tiny_http::Response::from_string(body),
+                    None => tiny_http::Response::from_string("{}").with_status_code(404),
+                };
+                request.respond(response).expect("Failed to respond");
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_nexus_provider() {
+        let provider = NexusProvider::with_base_url(&start_mock_server(3), "key");
+        let info = provider
+            .get_mod_info("morrowind", 123)
+            .expect("Failed to get mod info");
+        assert_eq!(info.mod_id, 123);
+        assert_eq!(info.name.as_deref(), Some("Mod"));
+        assert_eq!(info.version.as_deref(), Some("1.2"));
+        assert_eq!(info.category.as_deref(), Some("Gameplay"));
+
+        // optional files are not the latest version
+        let latest_file = info.latest_file.expect("No latest file");
+        assert_eq!(latest_file.file_id, 2);
+        assert_eq!(latest_file.file_name.as_deref(), Some("Mod-123-1-2.7z"));
+
+        // unknown mods fail
+        let provider = NexusProvider::with_base_url(&start_mock_server(1), "key");
+        assert!(provider.get_mod_info("morrowind", 456).is_err());
+
+        // the key of an nxm link is sent as a query value
+        let provider = NexusProvider::with_base_url(&start_mock_server(1), "key");
+        let link = NxmLink {
+            game: "morrowind".to_owned(),
+            mod_id: 123,
+            file_id: 2,
+            key: Some("a&b=c".to_owned()),
+            expires: Some(1),
+        };
+        assert_eq!(
+            provider.get_download_links(&link),
+            Ok(vec!["https://example.com/Mod-123-1-2.7z".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_cached_provider() {
+        // setup test environment
+        let test_env = Path::new("tests/integration/metadata");
+        if test_env.exists() {
+            std::fs::remove_dir_all(test_env).expect("Failed setup test env");
+        }
+
+        // the mock server only answers once
+        let url = start_mock_server(3);
+        let provider = CachedProvider::new(
+            Box::new(NexusProvider::with_base_url(&url, "key")),
+            test_env.to_path_buf(),
+        );
+        assert_eq!(provider.get_cached("morrowind", 123), None);
+        let info = provider
+            .get_mod_info("morrowind", 123)
+            .expect("Failed to get mod info");
+        assert!(test_env.join("nexus/morrowind/123.json").exists());
+        assert_eq!(provider.get_cached("morrowind", 123), Some(info.clone()));
+
+        // game names that could leave the cache folder are rejected
+        assert!(provider.get_mod_info("../morrowind", 123).is_err());
+        assert_eq!(provider.get_cached("../morrowind", 123), None);
+
+        // outdated entries are used when the server is gone
+        let provider = CachedProvider::new(
+            Box::new(NexusProvider::with_base_url(&url, "key")),
+            test_env.to_path_buf(),
+        )
+        .with_max_age(Duration::ZERO);
+        assert_eq!(provider.get_mod_info("morrowind", 123), Ok(info));
+        assert!(provider.get_mod_info("morrowind", 456).is_err());
+
+        // destroy test environment
+        std::fs::remove_dir_all(test_env).expect("Failed destroy test env");
+    }
+
+    #[test]
+    fn test_nxm_link() {
+        let link = NxmLink::parse(
+            "nxm://Morrowind/mods/123/files/456?key=abc&expires=1700000000&user_id=1",
+        )
+        .expect("Failed to parse nxm link");
+        assert_eq!(link.game, "morrowind");
+        assert_eq!(link.mod_id, 123);
+        assert_eq!(link.file_id, 456);
+        assert_eq!(link.key.as_deref(), Some("abc"));
+        assert_eq!(link.expires, Some(1700000000));
+
+        // premium links have no key
+        let link =
+            NxmLink::parse("nxm://morrowind/mods/1/files/2").expect("Failed to parse nxm link");
+        assert_eq!(link.key, None);
+
+        assert_eq!(NxmLink::parse("https://www.nexusmods.com"), None);
+        assert_eq!(NxmLink::parse("nxm://morrowind/mods/abc/files/2"), None);
+        assert_eq!(NxmLink::parse("nxm://mor%2Frowind/mods/1/files/2"), None);
+    }
+
+    #[test]
+    fn test_download_file() {
+        // setup test environment
+        let test_env = Path::new("tests/integration/download");
+        if test_env.exists() {
+            std::fs::remove_dir_all(test_env).expect("Failed setup test env");
+        }
+        std::fs::create_dir_all(test_env).expect("Failed setup test env: folders");
+        let content: Vec<u8> = (0..200_000).map(|i| (i % 251) as u8).collect();
+
+        // a file server with range support
+        let server = tiny_http::Server::http("127.0.0.1:0").expect("Failed to start mock server");
+        let url = format!("http://{}/Mod-123-1-0.7z", server.server_addr());
+        let served = content.clone();
+        let handle = thread::spawn(move || {
+            let mut ranges = vec![];
+            for request in server.incoming_requests().take(2) {
+                let offset = request
+                    .headers()
+                    .iter()
+                    .find(|h| h.field.equiv("Range"))
+                    .and_then(|h| {
+                        h.value
+                            .as_str()
+                            .strip_prefix("bytes=")?
+                            .trim_end_matches('-')
+                            .parse::<usize>()
+                            .ok()
+                    });
+                ranges.push(offset);
+                let response = match offset {
+                    Some(offset) => tiny_http::Response::from_data(served[offset..].to_vec())
+                        .with_status_code(206),
+                    None => tiny_http::Response::from_data(served.clone()),
+                }
+                // send a content length
+                .with_chunked_threshold(usize::MAX);
+                request.respond(response).expect("Failed to respond");
+            }
+            ranges
+        });
+
+        // cancel the first download halfway
+        let destination = test_env.join("Mod-123-1-0.7z");
+        let agent = ureq::Agent::new();
+        let result = download_file(&agent, &url, &destination, |done, total| {
+            assert_eq!(total, content.len() as u64);
+            done < 100_000
+        });
+        assert!(result.is_err());
+        assert!(!destination.exists());
+        let part_len = std::fs::metadata(get_download_part_path(&destination))
+            .expect("No part file")
+            .len();
+        assert!(part_len > 0);
+
+        // resume
+        download_file(&agent, &url, &destination, |_, total| {
+            assert_eq!(total, content.len() as u64);
+            true
+        })
+        .expect("Failed to resume download");
+        assert_eq!(std::fs::read(&destination).expect("No file"), content);
+        assert!(!get_download_part_path(&destination).exists());
+        assert_eq!(
+            handle.join().expect("Mock server failed"),
+            vec![None, Some(part_len as usize)]
+        );
+
+        // destroy test environment
+        std::fs::remove_dir_all(test_env).expect("Failed destroy test env");
+    }
+
+    #[test]
+    fn test_ipc_commands() {
+        let args = [
+            "--profile",
+            "main",
+            "nxm://morrowind/mods/1/files/2",
+            "Cargo.toml",
+            "--profile=other",
+            "missing.7z",
+        ];
+        let commands = EIpcCommand::from_args(args.iter().map(|a| a.to_string()));
+        assert_eq!(commands.len(), 4);
+        assert_eq!(commands[0], EIpcCommand::SwitchProfile("main".to_owned()));
+        assert_eq!(
+            commands[1],
+            EIpcCommand::OpenNxm("nxm://morrowind/mods/1/files/2".to_owned())
+        );
+        // paths are absolute for the running instance
+        match &commands[2] {
+            EIpcCommand::Install(path) => assert!(path.is_absolute()),
+            command => panic!("Unexpected command {:?}", command),
+        }
+        assert_eq!(commands[3], EIpcCommand::SwitchProfile("other".to_owned()));
+
+        // commands survive the socket
+        for command in commands.iter().chain([&EIpcCommand::Focus]) {
+            assert_eq!(
+                EIpcCommand::parse(&command.to_message()).as_ref(),
+                Some(command)
+            );
+        }
+        assert_eq!(EIpcCommand::parse("unknown"), None);
+    }
+
+    #[test]
+    fn test_separators() {
+        let mods: Vec<PathBuf> = ["a", "b", "c", "d"].iter().map(PathBuf::from).collect();
+        let separators = vec![
+            ModSeparator {
+                name: "first".to_owned(),
+                before: Some(PathBuf::from("b")),
+                ..Default::default()
+            },
+            ModSeparator {
+                name: "second".to_owned(),
+                before: Some(PathBuf::from("d")),
+                collapsed: true,
+                ..Default::default()
+            },
+            // the mod is gone
+            ModSeparator {
+                name: "empty".to_owned(),
+                before: Some(PathBuf::from("x")),
+                ..Default::default()
+            },
+        ];
+        let rows = get_mod_rows(&mods, &separators);
+        assert_eq!(
+            rows,
+            vec![
+                Mod(0),
+                Separator(0),
+                Mod(1),
+                Mod(2),
+                Separator(1),
+                Mod(3),
+                Separator(2)
+            ]
+        );
+        assert_eq!(
+            get_group_mods(&rows, separators.len()),
+            vec![vec![1, 2], vec![3], vec![]]
+        );
+        let visible = get_visible_rows(&rows, &separators);
+        assert_eq!(
+            visible,
+            vec![
+                Mod(0),
+                Separator(0),
+                Mod(1),
+                Mod(2),
+                Separator(1),
+                Separator(2)
+            ]
+        );
+
+        // a separator moves with its mods and is dropped between groups
+        let moved = move_row(&rows, &visible, 4, 1);
+        assert_eq!(
+            moved,
+            vec![
+                Mod(0),
+                Separator(1),
+                Mod(3),
+                Separator(0),
+                Mod(1),
+                Mod(2),
+                Separator(2)
+            ]
+        );
+        let (order, moved_separators) = apply_mod_rows(&moved, &mods, &separators);
+        assert_eq!(order, vec![0, 3, 1, 2]);
+        assert_eq!(
+            moved_separators
+                .iter()
+                .map(|s| s.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["second", "first", "empty"]
+        );
+        assert_eq!(moved_separators[0].before, Some(PathBuf::from("d")));
+        assert_eq!(moved_separators[1].before, Some(PathBuf::from("b")));
+        assert_eq!(moved_separators[2].before, None);
+        // ungrouped mods stay on top, dropping into a group keeps the order
+        assert_eq!(move_row(&rows, &visible, 1, 0), rows);
+        assert_eq!(move_row(&rows, &visible, 4, 3), rows);
+
+        // a mod dropped before a collapsed separator ends its group
+        let moved = move_row(&rows, &visible, 0, 4);
+        assert_eq!(
+            moved,
+            vec![
+                Separator(0),
+                Mod(1),
+                Mod(2),
+                Mod(0),
+                Separator(1),
+                Mod(3),
+                Separator(2)
+            ]
+        );
+        // a mod dropped at the end joins the last group
+        let moved = move_row(&rows, &visible, 3, visible.len());
+        assert_eq!(
+            moved,
+            vec![
+                Mod(0),
+                Separator(0),
+                Mod(1),
+                Separator(1),
+                Mod(3),
+                Separator(2),
+                Mod(2)
+            ]
+        );
+        let (order, moved_separators) = apply_mod_rows(&moved, &mods, &separators);
+        assert_eq!(order, vec![0, 1, 3, 2]);
+        assert_eq!(moved_separators[2].before, Some(PathBuf::from("c")));
+    }
+
+    #[test]
+    fn test_filters() {
+        assert!(is_fuzzy_match("", "Tamriel_Data"));
+        assert!(is_fuzzy_match("tamdat", "Tamriel_Data"));
+        assert!(is_fuzzy_match("TR data", "Tamriel_Rebuilt_Data.esm"));
+        assert!(!is_fuzzy_match("datam", "Tamriel_Data"));
+
+        let items = vec!["a", "b", "c", "d"];
+        assert_eq!(get_range(&items, &"c", &"a"), vec!["a", "b", "c"]);
+        assert_eq!(get_range(&items, &"b", &"b"), vec!["b"]);
+        assert!(get_range(&items, &"b", &"x").is_empty());
+
+        let mut moved = items.clone();
+        move_selected(&mut moved, |i| *i == "b" || *i == "d", true);
+        assert_eq!(moved, vec!["b", "d", "a", "c"]);
+        move_selected(&mut moved, |i| *i == "b", false);
+        assert_eq!(moved, vec!["d", "a", "c", "b"]);
+
+        // selected mods are moved into a group in their order
+        let rows = vec![Mod(0), Separator(0), Mod(1), Separator(1), Mod(2), Mod(3)];
+        let target = get_group_end(&rows, 0).expect("No group");
+        assert_eq!(target, 3);
+        assert_eq!(
+            move_mods(&rows, &[3, 0], target),
+            vec![Separator(0), Mod(1), Mod(0), Mod(3), Separator(1), Mod(2)]
+        );
+        assert_eq!(
+            move_mods(&rows, &[1, 2], rows.len()),
+            vec![Mod(0), Separator(0), Separator(1), Mod(3), Mod(1), Mod(2)]
+        );
+        assert_eq!(get_group_end(&rows, 5), None);
+    }
+
+    #[test]
+    fn test_mod_ids() {
+        let test_env = Path::new("tests/integration/mod_ids");
+        std::fs::create_dir_all(test_env.join("A")).expect("Failed setup test env: folders");
+
+        let get_mod = |name: &str, id: u64| ModViewModel {
+            id,
+            full_name: test_env.join(name),
+            ..Default::default()
+        };
+        // mods of older versions have no id, duplicates get a new one
+        let mut mods = vec![get_mod("A", 0), get_mod("B", 3), get_mod("C", 3)];
+        assign_mod_ids(&mut mods);
+        let ids: Vec<u64> = mods.iter().map(|m| m.id).collect();
+        assert_eq!(ids, vec![4, 3, 5]);
+        assert_eq!(get_next_mod_id(&mods), 6);
+
+        // ids stay with their mod when the list changes
+        assert_eq!(insert_mod(&mut mods, 0, get_mod("D", 0)), 6);
+        assert_eq!(push_mod(&mut mods, get_mod("E", 1)), 7);
+        assert_eq!(get_mod_index(&mods, 3), Some(2));
+        assert_eq!(get_mod_index(&mods, 7), Some(4));
+        assert_eq!(get_mod_index(&mods, 1), None);
+
+        // only A exists, external entries are kept
+        mods[0].external = true;
+        assert_eq!(get_broken_mods(&mods), vec![3, 5, 7]);
+
+        // destroy test environment
+        std::fs::remove_dir_all(test_env).expect("Failed destroy test env");
+    }
+
+    #[test]
+    fn test_edit_history() {
+        let toggle = EListEdit::SetModsEnabled(vec![(1, false, true), (2, true, true)]);
+        let inverse = EListEdit::SetModsEnabled(vec![(1, true, false), (2, true, true)]);
+        assert_eq!(toggle.get_inverse(), inverse);
+        assert_eq!(inverse.get_inverse(), toggle);
+        let moved = EListEdit::MovePlugins {
+            before: vec!["a.esp".to_owned(), "b.esp".to_owned()],
+            after: vec!["b.esp".to_owned(), "a.esp".to_owned()],
+        };
+
+        let mut history = EditHistory::default();
+        assert!(!history.can_undo());
+        assert_eq!(history.undo(), None);
+        // edits that change nothing are not recorded
+        history.push(EListEdit::MoveMods {
+            before: ModOrder::default(),
+            after: ModOrder::default(),
+        });
+        assert!(!history.can_undo());
+
+        history.push(toggle.clone());
+        history.push(moved.clone());
+        assert_eq!(history.undo(), Some(moved.get_inverse()));
+        assert!(history.can_redo());
+        assert_eq!(history.redo(), Some(moved.clone()));
+        assert_eq!(history.undo(), Some(moved.get_inverse()));
+        assert_eq!(history.undo(), Some(inverse));
+        assert!(!history.can_undo());
+
+        // adding a separator is undone by removing it again
+        let added = EListEdit::AddSeparator {
+            index: 1,
+            separator: ModSeparator {
+                name: "Textures".to_owned(),
+                ..Default::default()
+            },
+        };
+        history.push(added.clone());
+        assert_eq!(
+            history.undo(),
+            Some(EListEdit::RemoveSeparator {
+                index: 1,
+                separator: ModSeparator {
+                    name: "Textures".to_owned(),
+                    ..Default::default()
+                },
+            })
+        );
+        assert_eq!(history.redo(), Some(added));
+
+        // a new edit drops the undone edits
+        history.push(moved.clone());
+        assert!(!history.can_redo());
+        history.clear();
+
+        // the oldest edits are dropped
+        for _ in 0..MAX_HISTORY + 5 {
+            history.push(toggle.clone());
+        }
+        let mut count = 0;
+        while history.undo().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, MAX_HISTORY);
+
+        // items that are not in the order are kept at the end
+        let mut items = vec!["new", "b", "c", "a"];
+        sort_by_order(&mut items, &["a", "b", "c"], |i| *i);
+        assert_eq!(items, vec!["a", "b", "c", "new"]);
+    }
+}